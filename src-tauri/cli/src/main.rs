@@ -0,0 +1,633 @@
+// ウィンドウを開かずにワークスペース（データファイルとスキーマ）を操作するコマンドライン
+// Tauriに依存しない workspace-core だけを使うため、デスクトップ環境のないマシンでも動作する
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use serde_json::{json, Map, Value};
+
+use workspace_core::delimited::{
+    read_delimited_file, write_delimited_file, DelimitedExportOptions, DelimitedImportOptions,
+    TextEncoding,
+};
+use workspace_core::i18n;
+use workspace_core::query::{run_query, Filter, RowQuery, SortKey};
+use workspace_core::schema::{ColumnDefinition, ColumnType, TableSchema};
+use workspace_core::storage::{self, read_policy};
+use workspace_core::tabular::{parse_bool, parse_number};
+use workspace_core::validation::{has_errors, IssueSeverity, RowIssue};
+use workspace_core::xlsx::{read_xlsx_file, write_xlsx_file, XlsxExportOptions, XlsxImportOptions};
+use workspace_core::{
+    create_workspace, new_row_id, open_workspace, read_evaluated, restore_with_history,
+    save_workspace, tr, validate_workspace, write_imported_workspace, ErrorCode, WorkspaceError,
+};
+
+/// コマンドライン引数
+#[derive(Parser)]
+#[command(
+    name = "get-started-cli",
+    version,
+    about = "ワークスペース（データファイルとスキーマ）をウィンドウを開かずに操作する"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// サブコマンド
+#[derive(Subcommand)]
+enum Command {
+    /// 空のワークスペースを作成する
+    Create {
+        /// 新しいデータファイルのパス（.json は省略可）
+        path: String,
+    },
+    /// スキーマと行データを検証する（エラーがある場合は終了コード 1）
+    Validate {
+        /// データファイルのパス
+        data: PathBuf,
+    },
+    /// CSV/TSV/Excel ファイルを取り込んで新しいワークスペースを作成する
+    Import {
+        /// 取り込むファイル（.xlsx は Excel、それ以外は CSV/TSV として読み込む）
+        source: PathBuf,
+        /// 新しいデータファイルのパス
+        path: String,
+        /// CSV/TSV の区切り文字（省略時は拡張子から判定）
+        #[arg(long)]
+        delimiter: Option<char>,
+        /// 1行目を見出しとして扱わない
+        #[arg(long)]
+        no_header: bool,
+        /// CSV/TSV を Shift_JIS として読み込む
+        #[arg(long)]
+        shift_jis: bool,
+        /// 取り込むシート名（Excel のみ、省略時は先頭のシート）
+        #[arg(long)]
+        sheet: Option<String>,
+    },
+    /// CSV/TSV/Excel ファイルに書き出す（形式は出力先の拡張子で判定）
+    Export {
+        /// データファイルのパス
+        data: PathBuf,
+        /// 出力先のパス
+        target: PathBuf,
+        /// CSV/TSV の区切り文字（省略時は拡張子から判定）
+        #[arg(long)]
+        delimiter: Option<char>,
+        /// 非表示カラムを含める
+        #[arg(long)]
+        include_hidden: bool,
+        /// システム列（_id等）を含める
+        #[arg(long)]
+        include_system: bool,
+        /// CSV/TSV の先頭に UTF-8 の BOM を付ける
+        #[arg(long)]
+        bom: bool,
+    },
+    /// 行を絞り込み・並べ替えて JSON で出力する
+    Query {
+        /// データファイルのパス
+        data: PathBuf,
+        /// 絞り込み条件（query_rows と同じ JSON の式）
+        #[arg(long)]
+        filter: Option<String>,
+        /// 並べ替えのキー（"列ID" または "列ID:desc"、複数指定可）
+        #[arg(long)]
+        sort: Vec<String>,
+        /// 先頭から読み飛ばす件数
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+        /// 出力する件数の上限
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// 行を追加する
+    AddRow {
+        /// データファイルのパス
+        data: PathBuf,
+        /// セルの値（"列ID=値"、複数指定可）
+        #[arg(value_name = "COLUMN=VALUE")]
+        values: Vec<String>,
+    },
+    /// セルの値を更新する
+    SetCell {
+        /// データファイルのパス
+        data: PathBuf,
+        /// 行の_id
+        row_id: String,
+        /// カラムID
+        column: String,
+        /// 新しい値（空文字列で未入力にする）
+        value: String,
+    },
+    /// バックアップを作成する
+    Backup {
+        /// データファイルのパス
+        data: PathBuf,
+        /// 作成せずにバックアップを一覧する
+        #[arg(long)]
+        list: bool,
+    },
+    /// バックアップを復元する
+    Restore {
+        /// データファイルのパス
+        data: PathBuf,
+        /// 復元するバックアップのID（backup --list で確認できる）
+        id: String,
+    },
+}
+
+/// コマンドラインのエントリーポイント
+/// 失敗時や検証エラーがある場合は終了コード 1 で終了する
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    // ワークスペースの処理が返すメッセージはOSの言語設定に従う
+    i18n::set_locale(i18n::detect_locale());
+    match execute(cli.command) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("エラー: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// サブコマンドを実行する
+///
+/// # 引数
+/// * `command` - 実行するサブコマンド
+///
+/// # 戻り値
+/// 成功時は終了コード、失敗時はエラー
+fn execute(command: Command) -> Result<ExitCode, WorkspaceError> {
+    match command {
+        Command::Create { path } => {
+            let storage = create_workspace(&path)?;
+            println!(
+                "ワークスペースを作成しました: {}",
+                storage.data_path().display()
+            );
+        }
+        Command::Validate { data } => {
+            let storage = open_workspace(&data)?;
+            let issues = validate_workspace(storage.as_ref())?;
+            if issues.is_empty() {
+                println!("問題はありません");
+            }
+            print_issues(&issues);
+            if has_errors(&issues) {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Import {
+            source,
+            path,
+            delimiter,
+            no_header,
+            shift_jis,
+            sheet,
+        } => {
+            let imported = if is_xlsx(&source) {
+                let options = XlsxImportOptions {
+                    sheet,
+                    has_header: no_header.then_some(false),
+                    ..Default::default()
+                };
+                read_xlsx_file(&source, &options)?
+            } else {
+                let options = DelimitedImportOptions {
+                    delimiter,
+                    has_header: !no_header,
+                    encoding: if shift_jis {
+                        TextEncoding::ShiftJis
+                    } else {
+                        TextEncoding::Utf8
+                    },
+                };
+                read_delimited_file(&source, &options)?
+            };
+            let (data_path, result) = write_imported_workspace(&path, imported)?;
+            print_issues(&result.issues);
+            println!(
+                "{} 行を取り込みました: {}",
+                result.row_count,
+                data_path.display()
+            );
+        }
+        Command::Export {
+            data,
+            target,
+            delimiter,
+            include_hidden,
+            include_system,
+            bom,
+        } => {
+            let storage = open_workspace(&data)?;
+            let (rows, schema, _) = read_evaluated(storage.as_ref())?;
+            let row_count = if is_xlsx(&target) {
+                let options = XlsxExportOptions {
+                    include_hidden,
+                    include_system,
+                    ..Default::default()
+                };
+                write_xlsx_file(&target, &schema, &rows, &options)?
+            } else {
+                let options = DelimitedExportOptions {
+                    delimiter,
+                    include_hidden,
+                    include_system,
+                    bom,
+                    ..Default::default()
+                };
+                write_delimited_file(&target, &schema, &rows, &options)?
+            };
+            println!("{row_count} 行を書き出しました: {}", target.display());
+        }
+        Command::Query {
+            data,
+            filter,
+            sort,
+            offset,
+            limit,
+        } => {
+            let filter = filter
+                .map(|text| {
+                    serde_json::from_str::<Filter>(&text).map_err(|err| {
+                        WorkspaceError::invalid_input(
+                            "cli.filter_invalid",
+                            tr!("cli.filter_invalid", error = err),
+                        )
+                        .with_source(&err)
+                    })
+                })
+                .transpose()?;
+            let sort = sort.iter().map(|key| parse_sort_key(key)).collect();
+            let query = RowQuery {
+                filter,
+                sort,
+                offset,
+                limit,
+            };
+
+            let storage = open_workspace(&data)?;
+            let revision = storage.revision()?;
+            let (rows, schema, _) = read_evaluated(storage.as_ref())?;
+            let page = run_query(&rows, &schema, &query)?;
+            let result = json!({
+                "rows": page.rows,
+                "total": page.total,
+                "offset": offset,
+                "revision": revision,
+            });
+            let output = serde_json::to_string_pretty(&result).map_err(|err| {
+                WorkspaceError::new(
+                    ErrorCode::Failed,
+                    "cli.output_failed",
+                    tr!("cli.output_failed", error = err),
+                )
+            })?;
+            println!("{output}");
+        }
+        Command::AddRow { data, values } => {
+            let storage = open_workspace(&data)?;
+            let mut rows = storage.read_rows()?;
+            let schema = storage.read_schema()?;
+
+            let id = new_row_id();
+            let mut row = Map::new();
+            row.insert("_id".into(), Value::String(id.clone()));
+            for assignment in &values {
+                let (column, text) = assignment.split_once('=').ok_or_else(|| {
+                    WorkspaceError::invalid_input(
+                        "cli.assignment_format",
+                        tr!("cli.assignment_format", assignment = assignment),
+                    )
+                })?;
+                let definition = editable_column(&schema, column)?;
+                row.insert(definition.id.clone(), parse_cell(definition, text)?);
+            }
+            rows.push(Value::Object(row));
+
+            let result = save_workspace(storage.as_ref(), rows, schema)?;
+            print_issues(&result.issues);
+            println!("行を追加しました: {id}");
+        }
+        Command::SetCell {
+            data,
+            row_id,
+            column,
+            value,
+        } => {
+            let storage = open_workspace(&data)?;
+            let mut rows = storage.read_rows()?;
+            let schema = storage.read_schema()?;
+
+            let definition = editable_column(&schema, &column)?;
+            let value = parse_cell(definition, &value)?;
+            let row = rows
+                .iter_mut()
+                .find(|row| row.get("_id").and_then(Value::as_str) == Some(row_id.as_str()))
+                .and_then(Value::as_object_mut)
+                .ok_or_else(|| {
+                    WorkspaceError::new(
+                        ErrorCode::NotFound,
+                        "changes.row_not_found",
+                        tr!("changes.row_not_found", id = row_id),
+                    )
+                    .with_row(&row_id)
+                })?;
+            row.insert(definition.id.clone(), value);
+
+            let result = save_workspace(storage.as_ref(), rows, schema)?;
+            print_issues(&result.issues);
+            println!("セルを更新しました: {row_id} / {column}");
+        }
+        Command::Backup { data, list } => {
+            let storage = open_workspace(&data)?;
+            if list {
+                for backup in storage::list_backups(storage.as_ref())? {
+                    let row_count = backup
+                        .row_count
+                        .map(|count| count.to_string())
+                        .unwrap_or_else(|| "-".into());
+                    println!("{}\t{}\t{row_count}", backup.id, backup.created_at);
+                }
+            } else {
+                match storage::create_backup(storage.as_ref())? {
+                    Some(id) => {
+                        storage::prune_backups(storage.as_ref(), &read_policy(storage.as_ref())?)?;
+                        println!("バックアップを作成しました: {id}");
+                    }
+                    None => println!("最新のバックアップと内容が同じため、作成しませんでした"),
+                }
+            }
+        }
+        Command::Restore { data, id } => {
+            let storage = open_workspace(&data)?;
+            restore_with_history(storage.as_ref(), &id)?;
+            println!("バックアップを復元しました: {id}");
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Excel ファイルのパスか（拡張子で判定）
+fn is_xlsx(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"))
+}
+
+/// "列ID" または "列ID:desc" の形式の並べ替えのキーを読み込む
+fn parse_sort_key(key: &str) -> SortKey {
+    let (column, descending) = match key.rsplit_once(':') {
+        Some((column, "desc")) => (column, true),
+        Some((column, "asc")) => (column, false),
+        _ => (key, false),
+    };
+    SortKey {
+        column: column.to_string(),
+        descending,
+    }
+}
+
+/// 値を設定できるカラムを探す（システム列と計算列は対象外）
+fn editable_column<'a>(
+    schema: &'a TableSchema,
+    column: &str,
+) -> Result<&'a ColumnDefinition, WorkspaceError> {
+    let definition = schema
+        .columns
+        .iter()
+        .find(|definition| definition.id == column)
+        .ok_or_else(|| {
+            WorkspaceError::invalid_input(
+                "column.not_found",
+                tr!("column.not_found", column = column),
+            )
+            .with_column(column)
+        })?;
+    if definition.system || definition.column_type.is_computed() {
+        return Err(WorkspaceError::invalid_input(
+            "cli.column_not_editable",
+            tr!("cli.column_not_editable", column = column),
+        )
+        .with_column(column));
+    }
+    Ok(definition)
+}
+
+/// コマンドラインで指定された文字列をカラムの型に合わせた値に変換する
+/// multiselect と relation はカンマ区切り、または JSON の配列で指定する
+///
+/// # 引数
+/// * `column` - 値を設定するカラム
+/// * `text` - 指定された文字列（空文字列は未入力）
+///
+/// # 戻り値
+/// 成功時は変換した値、型に合わない場合はエラー
+fn parse_cell(column: &ColumnDefinition, text: &str) -> Result<Value, WorkspaceError> {
+    let invalid = |message_key: &'static str, message: String| {
+        WorkspaceError::invalid_input(message_key, message).with_column(&column.id)
+    };
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return Ok(Value::Null);
+    }
+    match column.column_type {
+        ColumnType::Number => parse_number(trimmed).map(Value::Number).ok_or_else(|| {
+            invalid(
+                "cli.not_a_number",
+                tr!("cli.not_a_number", column = column.id, text = text),
+            )
+        }),
+        ColumnType::Checkbox => parse_bool(trimmed).map(Value::Bool).ok_or_else(|| {
+            invalid(
+                "cli.not_a_boolean",
+                tr!("cli.not_a_boolean", column = column.id, text = text),
+            )
+        }),
+        ColumnType::Multiselect | ColumnType::Relation if trimmed.starts_with('[') => {
+            serde_json::from_str(trimmed).map_err(|err| {
+                invalid(
+                    "cli.array_invalid",
+                    tr!(
+                        "cli.array_invalid",
+                        column = column.id,
+                        text = text,
+                        error = err
+                    ),
+                )
+            })
+        }
+        ColumnType::Multiselect | ColumnType::Relation => Ok(Value::Array(
+            trimmed
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        )),
+        _ => Ok(Value::String(text.to_string())),
+    }
+}
+
+/// 検証で見つかった問題を1件1行で表示する
+fn print_issues(issues: &[RowIssue]) {
+    for issue in issues {
+        let severity = match issue.severity {
+            IssueSeverity::Error => "エラー",
+            IssueSeverity::Warning => "警告",
+        };
+        let row = issue
+            .row_id
+            .clone()
+            .unwrap_or_else(|| format!("#{}", issue.row_index + 1));
+        let column = issue.column_id.as_deref().unwrap_or("-");
+        println!("{severity}\t{row}\t{column}\t{}", issue.message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 名前と価格の列を持つワークスペースを作成する
+    fn workspace(dir: &Path, file_name: &str, rows: &[Value]) -> PathBuf {
+        let path = dir.join(file_name);
+        let storage = create_workspace(&path.to_string_lossy()).unwrap();
+        let mut schema = storage.read_schema().unwrap();
+        schema
+            .columns
+            .push(ColumnDefinition::new("name", "Name", ColumnType::Text));
+        schema
+            .columns
+            .push(ColumnDefinition::new("price", "Price", ColumnType::Number));
+        // 検証を通さずに書き込む（不正な行も用意するため）
+        storage.write_table(rows, &schema).unwrap();
+        path
+    }
+
+    /// カラム名で値を取り出した行
+    fn cells(data: &Path) -> Vec<(Value, Value)> {
+        let storage = open_workspace(data).unwrap();
+        let schema = storage.read_schema().unwrap();
+        let id = |name: &str| {
+            schema
+                .columns
+                .iter()
+                .find(|column| column.name == name)
+                .map(|column| column.id.clone())
+                .unwrap()
+        };
+        let (name, price) = (id("Name"), id("Price"));
+        storage
+            .read_rows()
+            .unwrap()
+            .iter()
+            .map(|row| (row[&name].clone(), row[&price].clone()))
+            .collect()
+    }
+
+    #[test]
+    fn validate_fails_only_for_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = workspace(
+            dir.path(),
+            "valid.json",
+            &[json!({"_id": "a", "name": "A", "price": 1})],
+        );
+        let code = execute(Command::Validate { data: data.clone() }).unwrap();
+        assert_eq!(code, ExitCode::SUCCESS);
+
+        let data = workspace(
+            dir.path(),
+            "invalid.json",
+            &[json!({"_id": "a", "name": "A", "price": "x"})],
+        );
+        let code = execute(Command::Validate { data }).unwrap();
+        assert_eq!(code, ExitCode::FAILURE);
+
+        let error = execute(Command::Validate {
+            data: dir.path().join("missing.json"),
+        })
+        .unwrap_err();
+        assert_eq!(error.message_key, "error.workspace_missing");
+    }
+
+    #[test]
+    fn exported_files_can_be_imported() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = workspace(
+            dir.path(),
+            "items.json",
+            &[
+                json!({"_id": "a", "name": "Apple", "price": 120}),
+                json!({"_id": "b", "name": "Banana", "price": 80}),
+            ],
+        );
+        for extension in ["csv", "xlsx"] {
+            let target = dir.path().join(format!("items.{extension}"));
+            execute(Command::Export {
+                data: data.clone(),
+                target: target.clone(),
+                delimiter: None,
+                include_hidden: false,
+                include_system: false,
+                bom: false,
+            })
+            .unwrap();
+
+            let path = dir.path().join(format!("imported-{extension}.json"));
+            execute(Command::Import {
+                source: target,
+                path: path.to_string_lossy().into_owned(),
+                delimiter: None,
+                no_header: false,
+                shift_jis: false,
+                sheet: None,
+            })
+            .unwrap();
+            assert_eq!(
+                cells(&path),
+                [(json!("Apple"), json!(120)), (json!("Banana"), json!(80))],
+                "{extension}"
+            );
+            let code = execute(Command::Validate { data: path }).unwrap();
+            assert_eq!(code, ExitCode::SUCCESS, "{extension}");
+        }
+    }
+
+    #[test]
+    fn import_does_not_overwrite_existing_workspaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = workspace(dir.path(), "items.json", &[]);
+        let source = dir.path().join("source.csv");
+        std::fs::write(&source, "Name\nApple\n").unwrap();
+        let error = execute(Command::Import {
+            source,
+            path: data.to_string_lossy().into_owned(),
+            delimiter: None,
+            no_header: false,
+            shift_jis: false,
+            sheet: None,
+        })
+        .unwrap_err();
+        assert_eq!(error.code, ErrorCode::AlreadyExists);
+        assert!(cells(&data).is_empty());
+    }
+
+    #[test]
+    fn cells_are_parsed_by_column_type() {
+        let number = ColumnDefinition::new("price", "Price", ColumnType::Number);
+        let tags = ColumnDefinition::new("tags", "Tags", ColumnType::Multiselect);
+        assert_eq!(parse_cell(&number, " 12.5 ").unwrap(), json!(12.5));
+        assert_eq!(parse_cell(&number, "").unwrap(), Value::Null);
+        assert_eq!(parse_cell(&tags, "a, b,").unwrap(), json!(["a", "b"]));
+        assert_eq!(parse_cell(&tags, r#"["a,b"]"#).unwrap(), json!(["a,b"]));
+        let error = parse_cell(&number, "abc").unwrap_err();
+        assert_eq!(error.message_key, "cli.not_a_number");
+        assert_eq!(error.context.column_id.as_deref(), Some("price"));
+    }
+}
@@ -0,0 +1,680 @@
+// 開いているワークスペースを他のローカルツール（スクリプトやノートブック等）から読み書きするHTTP/JSON API
+// 127.0.0.1でのみ待ち受け、すべてのリクエストに起動時に発行したトークンを要求する
+// CORSのヘッダーは返さないため、ブラウザ上のページからは呼び出せない
+use std::io::{Cursor, ErrorKind, Read};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use parking_lot::{Condvar, Mutex};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use tiny_http::{Header, Request, Response, Server};
+
+use workspace_core::changes::{apply_operations, ChangeOperation};
+use workspace_core::query::{run_query, RowQuery};
+use workspace_core::storage::{Storage, StorageKind};
+use workspace_core::{
+    ensure_revision, read_evaluated, save_workspace, tr, workspace_id, ErrorCode, SaveResult,
+    WorkspaceError,
+};
+
+use crate::{emit_saved, AppState, QueryResult};
+
+/// リクエストの本文の上限（バイト）
+const MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
+
+/// リクエストの本文を読み終えるまでの制限時間
+const BODY_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 同時に処理するリクエストの上限（超えた分は503で断る）
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// 停止時に処理中のリクエストを待つ時間の上限
+/// （tiny_httpは接続のタイムアウトを設定できず、送信を止めたクライアントの読み込みは終わらないため）
+const STOP_TIMEOUT: Duration = Duration::from_secs(35);
+
+/// JSONの応答
+type JsonResponse = Response<Cursor<Vec<u8>>>;
+
+/// 起動中のローカルAPIの接続先（フロントエンドに返す）
+#[derive(Serialize, Clone)]
+pub(crate) struct ApiServerInfo {
+    /// 接続先のURL（例: http://127.0.0.1:8765）
+    url: String,
+    port: u16,
+    /// `Authorization: Bearer <token>`で指定するトークン（起動のたびに変わる）
+    token: String,
+}
+
+/// 起動中のローカルAPI
+/// 破棄すると待ち受けを停止し、処理中のリクエストが終わるまで（最大STOP_TIMEOUT）待つ
+pub(crate) struct ApiServer {
+    server: Arc<Server>,
+    worker: Option<JoinHandle<()>>,
+    /// 処理中のリクエストの数
+    active: Arc<ActiveRequests>,
+    info: ApiServerInfo,
+}
+
+impl ApiServer {
+    /// 127.0.0.1の指定ポートで待ち受けを開始する
+    ///
+    /// # 引数
+    /// * `app_handle` - Tauriアプリケーションハンドル（開いているワークスペースの参照に使用）
+    /// * `port` - 待ち受けるポート（0の場合は空いているポート）
+    ///
+    /// # 戻り値
+    /// 成功時は起動したサーバー、ポートを使用できない場合はエラー
+    pub(crate) fn start(app_handle: AppHandle, port: u16) -> Result<Self, WorkspaceError> {
+        let token = nanoid::nanoid!(32);
+        // 書き込みはUIの保存と同じロックで直列化される
+        // 書き込んだワークスペースを開いているウィンドウには、UIの保存と同じファイル変更イベントを送る
+        Self::serve(port, token.clone(), move |request| {
+            let state = app_handle.state::<AppState>();
+            handle(&state, &token, request, &|storage, saved| {
+                emit_saved(&app_handle, &state, storage, saved)
+            })
+        })
+    }
+
+    /// 待ち受けを開始し、リクエストごとにスレッドで`handler`を呼び出す
+    /// （本文の遅いクライアントが他のリクエストを止めないようにする）
+    ///
+    /// # 引数
+    /// * `port` - 待ち受けるポート（0の場合は空いているポート）
+    /// * `token` - リクエストに要求するトークン
+    /// * `handler` - 1件のリクエストを処理して応答を作る関数
+    fn serve(
+        port: u16,
+        token: String,
+        handler: impl Fn(&mut Request) -> JsonResponse + Send + Sync + 'static,
+    ) -> Result<Self, WorkspaceError> {
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let server = Server::http(address).map_err(|err| {
+            WorkspaceError::new(
+                ErrorCode::Io,
+                "error.api_start_failed",
+                tr!("error.api_start_failed", address = address),
+            )
+            .with_source(&*err)
+        })?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map_or(port, |address| address.port());
+        let info = ApiServerInfo {
+            url: format!("http://{}:{port}", Ipv4Addr::LOCALHOST),
+            port,
+            token,
+        };
+
+        let server = Arc::new(server);
+        let active = Arc::new(ActiveRequests::default());
+        let worker = {
+            let server = Arc::clone(&server);
+            let active = Arc::clone(&active);
+            let handler = Arc::new(handler);
+            thread::spawn(move || {
+                for mut request in server.incoming_requests() {
+                    let Some(slot) = RequestSlot::acquire(&active) else {
+                        let error = WorkspaceError::new(
+                            ErrorCode::Failed,
+                            "error.api_busy",
+                            tr!("error.api_busy"),
+                        );
+                        let _ = request.respond(json_response(503, &error));
+                        continue;
+                    };
+                    let handler = Arc::clone(&handler);
+                    thread::spawn(move || {
+                        let response = handler(&mut request);
+                        let _ = request.respond(response);
+                        drop(slot);
+                    });
+                }
+            })
+        };
+
+        Ok(Self {
+            server,
+            worker: Some(worker),
+            active,
+            info,
+        })
+    }
+
+    /// 接続先
+    pub(crate) fn info(&self) -> &ApiServerInfo {
+        &self.info
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        self.active.wait_idle(STOP_TIMEOUT);
+    }
+}
+
+/// 処理中のリクエストの数（停止時に終わるのを待つ）
+#[derive(Default)]
+struct ActiveRequests {
+    count: Mutex<usize>,
+    idle: Condvar,
+}
+
+impl ActiveRequests {
+    /// 処理中のリクエストがなくなるまで、最大`timeout`待つ
+    fn wait_idle(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        let mut count = self.count.lock();
+        while *count > 0 && !self.idle.wait_until(&mut count, deadline).timed_out() {}
+    }
+}
+
+/// 処理中のリクエストの枠（破棄すると枠を返す）
+struct RequestSlot(Arc<ActiveRequests>);
+
+impl RequestSlot {
+    /// 処理中のリクエストが上限未満なら枠を確保する
+    fn acquire(active: &Arc<ActiveRequests>) -> Option<Self> {
+        let mut count = active.count.lock();
+        if *count >= MAX_CONCURRENT_REQUESTS {
+            return None;
+        }
+        *count += 1;
+        Some(Self(Arc::clone(active)))
+    }
+}
+
+impl Drop for RequestSlot {
+    fn drop(&mut self) {
+        let mut count = self.0.count.lock();
+        *count -= 1;
+        if *count == 0 {
+            self.0.idle.notify_all();
+        }
+    }
+}
+
+/// 開いているテーブルの一覧の1件
+#[derive(Serialize)]
+struct ApiTable {
+    /// ワークスペースID（各エンドポイントのパスで指定する）
+    id: String,
+    table_name: String,
+    data_path: String,
+    storage: StorageKind,
+    row_count: usize,
+    revision: String,
+}
+
+/// 行の追加・更新・削除の結果
+#[derive(Serialize)]
+struct ApiWriteResult {
+    /// 追加または更新された行の_id
+    changed_ids: Vec<String>,
+    /// 削除された行の_id
+    removed_ids: Vec<String>,
+    #[serde(flatten)]
+    saved: SaveResult,
+}
+
+/// 書き込みが終わったときに呼び出す関数（書き込んだワークスペースと保存結果を受け取る）
+type OnSaved<'a> = &'a dyn Fn(&dyn Storage, &SaveResult);
+
+/// 1件のリクエストを処理して応答を作る
+fn handle(state: &AppState, token: &str, request: &mut Request, on_saved: OnSaved) -> JsonResponse {
+    match authorize(request, token).and_then(|()| route(state, request, on_saved)) {
+        Ok((status, body)) => json_response(status, &body),
+        Err(error) => json_response(status_for(&error), &error),
+    }
+}
+
+/// `Authorization: Bearer <token>`ヘッダーを確認する
+fn authorize(request: &Request, token: &str) -> Result<(), WorkspaceError> {
+    let given = header(request, "Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    match given {
+        Some(given) if same_token(given, token) => Ok(()),
+        _ => Err(WorkspaceError::new(
+            ErrorCode::Unauthorized,
+            "error.unauthorized",
+            tr!("error.unauthorized"),
+        )),
+    }
+}
+
+/// トークンを比較する（一致する長さで処理時間が変わらないよう、全バイトを比較する）
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (left, right)| diff | (left ^ right))
+            == 0
+}
+
+/// メソッドとパスに対応する処理を呼び出す
+///
+/// # 戻り値
+/// 成功時はHTTPステータスと応答の本文、失敗時はエラー
+fn route(
+    state: &AppState,
+    request: &mut Request,
+    on_saved: OnSaved,
+) -> Result<(u16, Value), WorkspaceError> {
+    let method = request.method().to_string();
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let segments: Vec<String> = path
+        .trim_matches('/')
+        .split('/')
+        .map(percent_decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    match (method.as_str(), segments.as_slice()) {
+        ("GET", ["tables"]) => to_body(200, list_tables(state)?),
+        ("GET", ["tables", id, "schema"]) => {
+            let storage = state.storage(id)?;
+            to_body(
+                200,
+                json!({ "schema": storage.read_schema()?, "revision": storage.revision()? }),
+            )
+        }
+        ("GET", ["tables", id, "rows"]) => {
+            let query = RowQuery {
+                offset: query_param(query, "offset")?.unwrap_or(0),
+                limit: query_param(query, "limit")?,
+                ..RowQuery::default()
+            };
+            to_body(200, query_table(state, id, &query)?)
+        }
+        ("POST", ["tables", id, "query"]) => {
+            let query: RowQuery = read_body(request)?;
+            to_body(200, query_table(state, id, &query)?)
+        }
+        ("POST", ["tables", id, "rows"]) => {
+            let operations = match read_body::<Value>(request)? {
+                Value::Object(row) => vec![row],
+                Value::Array(rows) => rows
+                    .into_iter()
+                    .map(|row| match row {
+                        Value::Object(row) => Ok(row),
+                        _ => Err(rows_invalid()),
+                    })
+                    .collect::<Result<_, _>>()?,
+                _ => return Err(rows_invalid()),
+            }
+            .into_iter()
+            .map(|row| ChangeOperation::InsertRow { row, index: None })
+            .collect();
+            to_body(201, write_rows(state, id, request, operations, on_saved)?)
+        }
+        ("PATCH", ["tables", id, "rows", row_id]) => {
+            let operation = ChangeOperation::UpdateCells {
+                id: row_id.to_string(),
+                cells: read_body(request)?,
+            };
+            to_body(
+                200,
+                write_rows(state, id, request, vec![operation], on_saved)?,
+            )
+        }
+        ("DELETE", ["tables", id, "rows", row_id]) => {
+            let operation = ChangeOperation::DeleteRow {
+                id: row_id.to_string(),
+            };
+            to_body(
+                200,
+                write_rows(state, id, request, vec![operation], on_saved)?,
+            )
+        }
+        _ => Err(WorkspaceError::new(
+            ErrorCode::NotFound,
+            "error.api_route_not_found",
+            tr!("error.api_route_not_found", method = method, path = path),
+        )),
+    }
+}
+
+/// 開いているワークスペースのテーブルを一覧する
+fn list_tables(state: &AppState) -> Result<Vec<ApiTable>, WorkspaceError> {
+    state
+        .workspaces
+        .storages()
+        .into_iter()
+        .map(|storage| {
+            Ok(ApiTable {
+                id: workspace_id(storage.as_ref()),
+                table_name: storage.read_schema()?.table_name,
+                data_path: storage.data_path().to_string_lossy().into_owned(),
+                storage: storage.kind(),
+                row_count: storage.read_rows()?.len(),
+                revision: storage.revision()?,
+            })
+        })
+        .collect()
+}
+
+/// 計算列の値を含む行を絞り込み・並べ替えて、指定されたページを返す（query_rowsと同じ）
+fn query_table(
+    state: &AppState,
+    workspace_id: &str,
+    query: &RowQuery,
+) -> Result<QueryResult, WorkspaceError> {
+    let storage = state.storage(workspace_id)?;
+    let revision = storage.revision()?;
+    let (rows, schema, _) = read_evaluated(storage.as_ref())?;
+    let page = run_query(&rows, &schema, query)?;
+    Ok(QueryResult {
+        rows: page.rows,
+        total: page.total,
+        offset: query.offset,
+        revision,
+    })
+}
+
+/// 差分操作を適用し、UIの保存と同じ処理（正規化・検証・バックアップ・履歴）で書き込む
+/// `If-Match`ヘッダーで読み込んだときのリビジョンを必須とし、一致しなければ競合エラーにする
+/// 削除時の動作で他のワークスペースも書き換わるため、確認せずに上書きする`If-Match: *`は受け付けない
+fn write_rows(
+    state: &AppState,
+    workspace_id: &str,
+    request: &Request,
+    operations: Vec<ChangeOperation>,
+    on_saved: OnSaved,
+) -> Result<ApiWriteResult, WorkspaceError> {
+    let revision = header(request, "If-Match")
+        .map(|value| value.trim().trim_matches('"'))
+        .filter(|value| !value.is_empty() && *value != "*")
+        .ok_or_else(|| {
+            WorkspaceError::invalid_input(
+                "error.api_if_match_required",
+                tr!("error.api_if_match_required"),
+            )
+        })?;
+
+    let storage = state.storage(workspace_id)?;
+    let _write_guard = state.write_lock.lock();
+    ensure_revision(storage.as_ref(), Some(revision))?;
+
+    let mut data = storage.read_rows()?;
+    let mut schema = storage.read_schema()?;
+    let outcome = apply_operations(
+        &mut data,
+        &mut schema,
+        &operations,
+        &Utc::now().to_rfc3339(),
+    )?;
+    let saved = save_workspace(storage.as_ref(), data, schema)?;
+    on_saved(storage.as_ref(), &saved);
+
+    Ok(ApiWriteResult {
+        changed_ids: outcome.changed_ids,
+        removed_ids: outcome.removed_ids,
+        saved,
+    })
+}
+
+/// リクエストの本文をJSONとして読み込む
+/// 本文はMAX_BODY_BYTESまでとし、BODY_READ_TIMEOUTまでに読み終わらない場合はエラーにする
+fn read_body<T: serde::de::DeserializeOwned>(request: &mut Request) -> Result<T, WorkspaceError> {
+    let too_large = || {
+        WorkspaceError::invalid_input(
+            "error.api_body_too_large",
+            tr!("error.api_body_too_large", limit = MAX_BODY_BYTES),
+        )
+    };
+    let invalid = || {
+        WorkspaceError::new(
+            ErrorCode::InvalidJson,
+            "error.api_body_invalid",
+            tr!("error.api_body_invalid"),
+        )
+    };
+    if request
+        .body_length()
+        .is_some_and(|length| length > MAX_BODY_BYTES)
+    {
+        return Err(too_large());
+    }
+
+    // tiny_httpは接続のタイムアウトを設定できないため、少しずつ読みながら経過時間を確認する
+    // （長さの分からないchunkedの本文も、上限を超えた時点で読むのをやめる）
+    let deadline = Instant::now() + BODY_READ_TIMEOUT;
+    let mut reader = request.as_reader().take(MAX_BODY_BYTES as u64 + 1);
+    let mut body = Vec::new();
+    let mut chunk = vec![0; 64 * 1024];
+    loop {
+        if Instant::now() > deadline {
+            return Err(WorkspaceError::invalid_input(
+                "error.api_body_timeout",
+                tr!("error.api_body_timeout"),
+            ));
+        }
+        match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => body.extend_from_slice(&chunk[..read]),
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(invalid().with_source(&err)),
+        }
+    }
+    if body.len() > MAX_BODY_BYTES {
+        return Err(too_large());
+    }
+
+    serde_json::from_slice(&body).map_err(|err| {
+        let mut error = invalid();
+        error.context.line = Some(err.line());
+        error.context.column = Some(err.column());
+        error.with_source(&err)
+    })
+}
+
+/// 追加する行の形式が正しくない場合のエラー
+fn rows_invalid() -> WorkspaceError {
+    WorkspaceError::invalid_input("error.api_rows_invalid", tr!("error.api_rows_invalid"))
+}
+
+/// クエリ文字列から数値のパラメーターを読み取る
+fn query_param(query: &str, name: &str) -> Result<Option<usize>, WorkspaceError> {
+    let Some(value) = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+    else {
+        return Ok(None);
+    };
+    value.parse().map(Some).map_err(|_| {
+        WorkspaceError::invalid_input(
+            "error.api_parameter_invalid",
+            tr!("error.api_parameter_invalid", name = name, value = value),
+        )
+    })
+}
+
+/// パスの%エンコードを戻す（_idに空白や記号を含む場合のため）
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| segment.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// エラーに対応するHTTPステータス
+fn status_for(error: &WorkspaceError) -> u16 {
+    match error.message_key {
+        "error.api_body_too_large" => return 413,
+        "error.api_body_timeout" => return 408,
+        "error.api_if_match_required" => return 428,
+        _ => {}
+    }
+    match error.code {
+        ErrorCode::NotFound | ErrorCode::WorkspaceNotLoaded => 404,
+        ErrorCode::AlreadyExists | ErrorCode::Conflict | ErrorCode::Restricted => 409,
+        ErrorCode::InvalidJson | ErrorCode::InvalidSchema | ErrorCode::InvalidInput => 400,
+        ErrorCode::InvalidRows => 422,
+        ErrorCode::Unauthorized => 401,
+        ErrorCode::Io | ErrorCode::Database | ErrorCode::Failed => 500,
+    }
+}
+
+/// 応答の本文をJSONの値にする
+fn to_body<T: Serialize>(status: u16, body: T) -> Result<(u16, Value), WorkspaceError> {
+    let body = serde_json::to_value(body).map_err(|err| {
+        WorkspaceError::new(
+            ErrorCode::Failed,
+            "error.api_response_failed",
+            tr!("error.api_response_failed"),
+        )
+        .with_source(&err)
+    })?;
+    Ok((status, body))
+}
+
+/// リクエストヘッダーの値（名前の大文字・小文字は区別しない）
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+/// JSONの応答を作る
+fn json_response<T: Serialize>(status: u16, body: &T) -> JsonResponse {
+    let body = serde_json::to_vec(body).unwrap_or_default();
+    let content_type = Header::from_bytes("Content-Type", "application/json; charset=utf-8")
+        .expect("static header is valid");
+    Response::from_data(body)
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    const TOKEN: &str = "test-token";
+
+    /// ワークスペースを開いていない状態のAPIを起動する
+    fn server() -> ApiServer {
+        let state = AppState::default();
+        ApiServer::serve(0, TOKEN.to_string(), move |request| {
+            handle(&state, TOKEN, request, &|_, _| {})
+        })
+        .unwrap()
+    }
+
+    /// リクエストを送信し、応答のステータスと本文を返す
+    fn send(server: &ApiServer, request: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(("127.0.0.1", server.info().port)).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn requests_need_the_token() {
+        let server = server();
+        let (status, body) = send(&server, "GET /tables HTTP/1.1\r\nConnection: close\r\n\r\n");
+        assert_eq!(status, 401);
+        assert_eq!(body["message_key"], "error.unauthorized");
+        let (status, _) = send(
+            &server,
+            "GET /tables HTTP/1.1\r\nConnection: close\r\nAuthorization: Bearer test-tokem\r\n\r\n",
+        );
+        assert_eq!(status, 401);
+
+        let (status, body) = send(
+            &server,
+            "GET /tables HTTP/1.1\r\nConnection: close\r\nauthorization: Bearer test-token\r\n\r\n",
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body, json!([]));
+        let (status, _) = send(
+            &server,
+            "GET /tables/missing/schema HTTP/1.1\r\nConnection: close\r\nAuthorization: Bearer test-token\r\n\r\n",
+        );
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn bodies_over_the_limit_are_rejected() {
+        let server = server();
+        // 本文を送る前に、Content-Lengthだけで断る
+        let (status, body) = send(
+            &server,
+            &format!(
+                "POST /tables/a/query HTTP/1.1\r\nConnection: close\r\nAuthorization: Bearer {TOKEN}\r\nContent-Length: {}\r\n\r\n",
+                MAX_BODY_BYTES + 1
+            ),
+        );
+        assert_eq!(status, 413);
+        assert_eq!(body["message_key"], "error.api_body_too_large");
+    }
+
+    #[test]
+    fn writes_need_if_match() {
+        let server = server();
+        let (status, body) = send(
+            &server,
+            &format!(
+                "POST /tables/a/rows HTTP/1.1\r\nConnection: close\r\nAuthorization: Bearer {TOKEN}\r\nContent-Length: 2\r\n\r\n{{}}"
+            ),
+        );
+        assert_eq!(status, 428);
+        assert_eq!(body["message_key"], "error.api_if_match_required");
+
+        // リビジョンを確認せずに上書きする指定は受け付けない
+        let (status, body) = send(
+            &server,
+            &format!(
+                "DELETE /tables/a/rows/r1 HTTP/1.1\r\nConnection: close\r\nAuthorization: Bearer {TOKEN}\r\nIf-Match: *\r\n\r\n"
+            ),
+        );
+        assert_eq!(status, 428);
+        assert_eq!(body["message_key"], "error.api_if_match_required");
+
+        // If-Matchを指定した場合は、開いていないワークスペースとして扱う
+        let (status, _) = send(
+            &server,
+            &format!(
+                "POST /tables/a/rows HTTP/1.1\r\nConnection: close\r\nAuthorization: Bearer {TOKEN}\r\nIf-Match: \"r1\"\r\nContent-Length: 2\r\n\r\n{{}}"
+            ),
+        );
+        assert_eq!(status, 404);
+    }
+}
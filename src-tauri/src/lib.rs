@@ -1,8 +1,10 @@
+// 他のローカルツールから開いているワークスペースを読み書きするHTTP/JSON API
+mod api;
+
 // 標準ライブラリからファイルシステムとI/O操作に必要なモジュールをインポート
-use std::fs::{self, File};
-use std::io::Write;
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::time::Duration;
 
 // 外部クレート
@@ -10,66 +12,126 @@ use chrono::Utc;
 use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-use tauri::{AppHandle, Emitter, State};
+use serde_json::Value;
+use tauri::{
+    AppHandle, Emitter, Manager, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+    WindowEvent,
+};
+
+use api::{ApiServer, ApiServerInfo};
+use workspace_core::{
+    aggregate, changes, delimited, diff, history, i18n, merge, query, registry, relation, schema,
+    storage, tabular, tr, validation, xlsx,
+};
+use workspace_core::{
+    build_table_payload, commit_workspace, ensure_revision, prepare_new_workspace, read_evaluated,
+    restore_with_history, save_workspace, workspace_id, write_imported_workspace, ErrorCode,
+    Locale, SaveResult, TablePayload, WorkspaceError,
+};
+
+use aggregate::{run_aggregate, AggregateGroup, AggregateQuery};
+use changes::{apply_operations, ChangeOperation};
+use delimited::{
+    read_delimited_file, write_delimited_file, DelimitedExportOptions, DelimitedImportOptions,
+};
+
+use diff::{diff_tables, TableDiff};
+use history::{
+    apply_change_set, history_items, history_path, read_history, undo_stacks, Direction,
+    HistoryAction, HistoryItem,
+};
+use merge::{merge_rows, MergeConflict};
+use query::{run_query, RowQuery};
+use registry::{WindowSet, WorkspaceRegistry};
+use relation::related_watch_paths;
+use schema::TableSchema;
+use storage::{
+    default_conversion_path, ensure_data_files, open_storage, open_storage_as, read_policy,
+    read_snapshot, write_policy, BackupInfo, BackupPolicy, CatalogEntry, SnapshotInfo, Storage,
+    StorageKind,
+};
+use tabular::ImportedTable;
+use validation::RowIssue;
+use xlsx::{read_xlsx_file, write_xlsx_file, XlsxExportOptions, XlsxImportOptions};
 
 // ファイル変更イベントの名前
 const FILE_CHANGED_EVENT: &str = "workspace:file-changed";
+// リレーションの参照先のワークスペースの変更イベントの名前
+const RELATED_CHANGED_EVENT: &str = "workspace:related-changed";
+// フォルダ内のテーブルの一覧の変更イベントの名前
+const CATALOG_CHANGED_EVENT: &str = "workspace:catalog-changed";
 // ファイル監視エラーイベントの名前
 const WATCH_ERROR_EVENT: &str = "workspace:watch-error";
+// テーブルを開いたウィンドウのラベルの接頭辞（capabilitiesの対象と合わせる）
+const TABLE_WINDOW_PREFIX: &str = "table-";
+// 差分で現在のファイルを表す名前
+const CURRENT_LABEL: &str = "current";
+// 履歴の閲覧で返す件数の既定値
+const DEFAULT_HISTORY_LIMIT: usize = 200;
+// ローカルAPIが待ち受けるポートの既定値
+const DEFAULT_API_PORT: u16 = 8765;
 
 /// アプリケーション全体の状態を管理する構造体
 /// 複数のスレッドから安全にアクセスできるようにMutexで保護されている
 #[derive(Default)]
 struct AppState {
-    workspace: Mutex<Option<WorkspaceState>>,
+    /// 開いているワークスペースと、それを表示しているウィンドウ
+    workspaces: WorkspaceRegistry<WorkspaceWatcher>,
+    /// リビジョンの確認から書き込みまでを直列化するためのロック（ブロッキング処理用のスレッドで取得する）
+    write_lock: Arc<Mutex<()>>,
+    /// 起動中のローカルAPI（利用者が開始するまでは起動しない）
+    api: Mutex<Option<ApiServer>>,
 }
 
-/// ワークスペースの状態を保持する構造体
-/// データファイルとスキーマファイルのパス、およびファイル監視機能を含む
-struct WorkspaceState {
-    /// データファイル(.json)のパス
-    data_path: PathBuf,
-    /// スキーマファイル(.schema.json)のパス
-    schema_path: PathBuf,
-    /// ファイル変更を監視するウォッチャー
-    watcher: Option<RecommendedWatcher>,
+/// 開いているワークスペースのファイル監視（破棄すると監視を停止する）
+struct WorkspaceWatcher {
+    /// ファイル変更を監視するウォッチャー（参照先の変更に合わせて監視するフォルダを変えるため共有する）
+    slot: Arc<Mutex<Option<FolderWatcher>>>,
 }
 
-impl WorkspaceState {
-    /// 新しいWorkspaceStateインスタンスを作成する
-    ///
-    /// # 引数
-    /// * `data_path` - データファイルのパス
-    /// * `schema_path` - スキーマファイルのパス
-    fn new(data_path: PathBuf, schema_path: PathBuf) -> Self {
-        Self {
-            data_path,
-            schema_path,
-            watcher: None,
-        }
-    }
-
+impl WorkspaceWatcher {
     /// ファイル監視を開始する
-    /// データファイルとスキーマファイルの変更を監視し、変更があればフロントエンドにイベントを送信する
+    /// ストレージのファイルの変更を監視し、変更があればワークスペースを表示しているウィンドウにイベントを送信する
+    /// リレーションの参照先のファイルも監視し、lookup・rollup列の再計算のためのイベントを送信する
+    /// （参照先はスキーマが変更されたときに求め直し、監視するフォルダも入れ替える）
+    /// フォルダ内のテーブルが追加・削除・名前変更された場合は、新しい一覧をイベントで送信する
     ///
     /// # 引数
     /// * `app_handle` - Tauriアプリケーションハンドル（イベント送信に使用）
+    /// * `id` - ワークスペースID
+    /// * `storage` - ワークスペースのストレージ
+    /// * `windows` - ワークスペースを表示しているウィンドウ（イベントの送信先）
     ///
     /// # 戻り値
-    /// 成功時は`Ok(())`、失敗時はエラーメッセージを含む`Err(String)`
-    fn start_watcher(&mut self, app_handle: AppHandle) -> Result<(), String> {
+    /// 成功時は開始したファイル監視、失敗時は`Err(WorkspaceError)`
+    fn start(
+        app_handle: &AppHandle,
+        id: &str,
+        storage: &Arc<dyn Storage>,
+        windows: &WindowSet,
+    ) -> Result<Self, WorkspaceError> {
         // Arc（原子参照カウント）でパスを共有可能にする（クロージャ内で使用するため）
-        let data_path = Arc::new(self.data_path.clone());
-        let schema_path = Arc::new(self.schema_path.clone());
-        let data_path_str = Arc::new(data_path.to_string_lossy().into_owned());
-        let schema_path_str = Arc::new(schema_path.to_string_lossy().into_owned());
+        let watch_paths = Arc::new(storage.watch_paths());
+        let data_path_str = Arc::new(storage.data_path().to_string_lossy().into_owned());
+        let schema_path_str = Arc::new(storage.schema_path().to_string_lossy().into_owned());
         let handle = app_handle.clone();
+        let id = id.to_string();
+        let windows = windows.clone();
+        let storage = Arc::clone(storage);
+        let folder = watch_folder(storage.as_ref());
+        // 前回送信したテーブルの一覧（変わらない場合はイベントを送信しない）
+        let catalog = Mutex::new(storage::list_tables(&folder).ok());
+        // リレーションの参照先のファイル（スキーマが変更されたときだけ求め直す）
+        let related_paths = Mutex::new(related_watch_paths(storage.as_ref()));
+        let initial_folders = watched_folders(&folder, &related_paths.lock());
+        let slot = Arc::default();
+        let weak_slot = Arc::downgrade(&slot);
+        let schema_path = storage.schema_path().to_path_buf();
+        let data_path = storage.data_path().to_path_buf();
 
         // ファイル監視ウォッチャーを作成し、イベントハンドラを設定
         let mut watcher = notify::recommended_watcher({
-            let data_path = Arc::clone(&data_path);
-            let schema_path = Arc::clone(&schema_path);
+            let watch_paths = Arc::clone(&watch_paths);
             let data_path_str = Arc::clone(&data_path_str);
             let schema_path_str = Arc::clone(&schema_path_str);
             move |res: Result<Event, notify::Error>| match res {
@@ -81,32 +143,73 @@ impl WorkspaceState {
                     ) {
                         return;
                     }
+                    // フォルダ内のテーブルの一覧が変わったかチェック
+                    if event
+                        .paths
+                        .iter()
+                        .any(|path| storage::affects_catalog(path))
+                    {
+                        if let Ok(tables) = storage::list_tables(&folder) {
+                            let mut last = catalog.lock();
+                            if last.as_ref() != Some(&tables) {
+                                *last = Some(tables.clone());
+                                let payload = CatalogPayload {
+                                    workspace_id: id.clone(),
+                                    folder: folder.to_string_lossy().into_owned(),
+                                    tables,
+                                };
+                                emit_to_windows(&handle, &windows, CATALOG_CHANGED_EVENT, payload);
+                            }
+                        }
+                    }
 
                     // 監視対象のファイルが変更されたかチェック
                     let relevant = event
                         .paths
                         .iter()
-                        .any(|path| path == &*data_path || path == &*schema_path);
+                        .any(|path| watch_paths.iter().any(|watched| path == watched));
 
+                    let payload = WorkspaceChangePayload {
+                        workspace_id: id.clone(),
+                        data_path: data_path_str.as_ref().clone(),
+                        schema_path: schema_path_str.as_ref().clone(),
+                    };
                     if relevant {
+                        // スキーマが変更された場合は参照先を求め直し、監視するフォルダを入れ替える
+                        let schema_changed = event.paths.iter().any(|path| path == &schema_path);
+                        if schema_changed {
+                            let paths = related_watch_paths(storage.as_ref());
+                            let mut last = related_paths.lock();
+                            if *last != paths {
+                                let folders = watched_folders(&folder, &paths);
+                                *last = paths;
+                                rewatch(weak_slot.clone(), folders, &handle, &windows, &id);
+                            }
+                        }
                         // フロントエンドにファイル変更イベントを送信
-                        let payload = WorkspaceChangePayload {
-                            data_path: data_path_str.as_ref().clone(),
-                            schema_path: schema_path_str.as_ref().clone(),
-                        };
-                        let _ = handle.emit(FILE_CHANGED_EVENT, payload);
+                        emit_to_windows(&handle, &windows, FILE_CHANGED_EVENT, payload);
+                        return;
+                    }
+
+                    let related = event
+                        .paths
+                        .iter()
+                        .any(|path| related_paths.lock().iter().any(|watched| path == watched));
+                    if related {
+                        emit_to_windows(&handle, &windows, RELATED_CHANGED_EVENT, payload);
                     }
                 }
                 Err(error) => {
                     // エラーが発生した場合、フロントエンドにエラーイベントを送信
                     let payload = WatchErrorPayload {
+                        workspace_id: id.clone(),
                         message: error.to_string(),
                     };
-                    let _ = handle.emit(WATCH_ERROR_EVENT, payload);
+                    emit_to_windows(&handle, &windows, WATCH_ERROR_EVENT, payload);
                 }
             }
         })
-        .map_err(|err| err.to_string())?;
+        .map_err(|err| watch_error(err, &data_path))?;
 
         // ウォッチャーの設定：ファイル内容の比較を有効化し、1秒間隔でポーリング
         watcher
@@ -115,96 +218,290 @@ impl WorkspaceState {
                     .with_compare_contents(true)
                     .with_poll_interval(Duration::from_secs(1)),
             )
-            .map_err(|err| err.to_string())?;
+            .map_err(|err| watch_error(err, &data_path))?;
 
-        // データファイルとスキーマファイルの監視を開始
-        watcher
-            .watch(&*data_path, RecursiveMode::NonRecursive)
-            .map_err(|err| err.to_string())?;
+        // ストレージのファイルと参照先のファイルを含むフォルダの監視を開始
+        // （サブフォルダ全体ではなく、ファイルを含むフォルダだけを監視する）
+        let mut watcher = FolderWatcher {
+            watcher,
+            folders: Vec::new(),
+        };
         watcher
-            .watch(&*schema_path, RecursiveMode::NonRecursive)
-            .map_err(|err| err.to_string())?;
+            .watch_folders(initial_folders)
+            .map_err(|err| watch_error(err, &data_path))?;
 
-        self.watcher = Some(watcher);
-        Ok(())
+        *slot.lock() = Some(watcher);
+        Ok(Self { slot })
     }
+}
 
+impl Drop for WorkspaceWatcher {
     /// ファイル監視を停止する
-    fn stop(&mut self) {
-        if let Some(watcher) = self.watcher.as_mut() {
-            let _ = watcher.unwatch(&self.data_path);
-            let _ = watcher.unwatch(&self.schema_path);
+    fn drop(&mut self) {
+        if let Some(mut watcher) = self.slot.lock().take() {
+            watcher.watch_folders(Vec::new()).ok();
         }
-        self.watcher = None;
     }
 }
 
-impl AppState {
-    /// ワークスペースを設定し、ファイル監視を開始する
+/// ウォッチャーと監視中のフォルダ
+struct FolderWatcher {
+    watcher: RecommendedWatcher,
+    /// 監視中のフォルダ（サブフォルダは含まない）
+    folders: Vec<PathBuf>,
+}
+
+impl FolderWatcher {
+    /// 監視するフォルダを入れ替える（引き続き監視するフォルダはそのまま）
     ///
     /// # 引数
-    /// * `app_handle` - Tauriアプリケーションハンドル
-    /// * `data_path` - データファイルのパス
+    /// * `folders` - 監視するフォルダ
     ///
     /// # 戻り値
-    /// 成功時はスキーマファイルのパス、失敗時はエラーメッセージ
-    fn set_workspace(&self, app_handle: &AppHandle, data_path: PathBuf) -> Result<PathBuf, String> {
-        let schema_path = schema_path_for(&data_path)?;
-        ensure_data_files(&data_path, &schema_path)?;
-
-        // 既存のワークスペースがあれば監視を停止
-        let mut guard = self.workspace.lock();
-        if let Some(existing) = guard.as_mut() {
-            existing.stop();
+    /// 成功時は`Ok(())`、監視を開始できないフォルダがあった場合は最初のエラー（他のフォルダの監視は続ける）
+    fn watch_folders(&mut self, folders: Vec<PathBuf>) -> Result<(), notify::Error> {
+        let watcher = &mut self.watcher;
+        self.folders.retain(|folder| {
+            folders.contains(folder) || {
+                let _ = watcher.unwatch(folder);
+                false
+            }
+        });
+        let mut result = Ok(());
+        for folder in folders {
+            if self.folders.contains(&folder) {
+                continue;
+            }
+            match self.watcher.watch(&folder, RecursiveMode::NonRecursive) {
+                Ok(()) => self.folders.push(folder),
+                Err(err) if result.is_ok() => result = Err(err),
+                Err(_) => {}
+            }
+        }
+        result
+    }
+}
+
+/// 監視するフォルダ（ワークスペースのフォルダと、参照先のファイルを含む既存のフォルダ）
+///
+/// # 引数
+/// * `folder` - ワークスペースのフォルダ
+/// * `related_paths` - 参照先のファイル
+fn watched_folders(folder: &Path, related_paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut folders = vec![folder.to_path_buf()];
+    for parent in related_paths.iter().filter_map(|path| path.parent()) {
+        if parent.is_dir() && !folders.iter().any(|watched| watched == parent) {
+            folders.push(parent.to_path_buf());
         }
+    }
+    folders
+}
 
-        // 新しいワークスペースを作成し、監視を開始
-        let mut workspace = WorkspaceState::new(data_path.clone(), schema_path.clone());
-        workspace.start_watcher(app_handle.clone())?;
-        *guard = Some(workspace);
+/// 監視するフォルダを別のスレッドで入れ替える
+/// （イベントの処理中にウォッチャーを操作すると、notifyのイベントスレッドと待ち合わせになるため）
+///
+/// # 引数
+/// * `slot` - ウォッチャー（監視を停止済みの場合は何もしない）
+/// * `folders` - 監視するフォルダ
+/// * `handle` - Tauriアプリケーションハンドル（エラーの送信に使用）
+/// * `windows` - エラーの送信先のウィンドウのラベル
+/// * `id` - ワークスペースID
+fn rewatch(
+    slot: Weak<Mutex<Option<FolderWatcher>>>,
+    folders: Vec<PathBuf>,
+    handle: &AppHandle,
+    windows: &WindowSet,
+    id: &str,
+) {
+    let handle = handle.clone();
+    let windows = windows.clone();
+    let id = id.to_string();
+    std::thread::spawn(move || {
+        let Some(slot) = slot.upgrade() else {
+            return;
+        };
+        let result = match slot.lock().as_mut() {
+            Some(watcher) => watcher.watch_folders(folders),
+            None => return,
+        };
+        if let Err(error) = result {
+            let payload = WatchErrorPayload {
+                workspace_id: id,
+                message: error.to_string(),
+            };
+            emit_to_windows(&handle, &windows, WATCH_ERROR_EVENT, payload);
+        }
+    });
+}
 
-        Ok(schema_path)
+/// ワークスペースを表示しているウィンドウにだけイベントを送信する
+///
+/// # 引数
+/// * `handle` - Tauriアプリケーションハンドル
+/// * `windows` - 送信先のウィンドウのラベル
+/// * `event` - イベント名
+/// * `payload` - イベントのペイロード
+fn emit_to_windows<S: Serialize + Clone>(
+    handle: &AppHandle,
+    windows: &WindowSet,
+    event: &str,
+    payload: S,
+) {
+    for label in windows.labels() {
+        let _ = handle.emit_to(label.as_str(), event, payload.clone());
     }
+}
 
-    /// 現在のワークスペースのデータファイルとスキーマファイルのパスを取得する
-    ///
-    /// # 戻り値
-    /// 成功時は(データパス, スキーマパス)のタプル、ワークスペースが読み込まれていない場合はエラー
-    fn paths(&self) -> Result<(PathBuf, PathBuf), String> {
-        self.workspace
-            .lock()
-            .as_ref()
-            .map(|workspace| (workspace.data_path.clone(), workspace.schema_path.clone()))
-            .ok_or_else(|| "Workspace not loaded".to_string())
+/// 保存したワークスペースと、削除時の動作で書き換えた他のワークスペースを表示しているウィンドウに
+/// ファイル変更イベントを送信する（ローカルAPIからの書き込みを、UIの保存と同じように開いているウィンドウに反映する）
+///
+/// # 引数
+/// * `handle` - Tauriアプリケーションハンドル
+/// * `state` - アプリケーション状態
+/// * `storage` - 保存したワークスペースのストレージ
+/// * `saved` - 保存結果
+fn emit_saved(handle: &AppHandle, state: &AppState, storage: &dyn Storage, saved: &SaveResult) {
+    let updated: Vec<Arc<dyn Storage>> = saved
+        .updated_workspaces
+        .iter()
+        .filter_map(|path| open_storage(Path::new(path)).ok())
+        .collect();
+    for storage in std::iter::once(storage).chain(updated.iter().map(Arc::as_ref)) {
+        let id = workspace_id(storage);
+        let payload = WorkspaceChangePayload {
+            workspace_id: id.clone(),
+            data_path: storage.data_path().to_string_lossy().into_owned(),
+            schema_path: storage.schema_path().to_string_lossy().into_owned(),
+        };
+        for label in state.workspaces.windows(&id) {
+            let _ = handle.emit_to(label.as_str(), FILE_CHANGED_EVENT, payload.clone());
+        }
     }
 }
 
-/// ワークスペース情報を表す構造体（フロントエンドに送信）
-#[derive(Serialize)]
-struct WorkspaceInfo {
-    data_path: String,
-    schema_path: String,
-    folder: String,
+/// ワークスペースのファイルを含むフォルダ（ファイル監視とテーブルの一覧に使用）
+fn watch_folder(storage: &dyn Storage) -> PathBuf {
+    storage
+        .data_path()
+        .parent()
+        .filter(|folder| !folder.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf()
 }
 
-/// テーブルデータとスキーマをまとめたペイロード（フロントエンドに送信）
-#[derive(Serialize)]
-struct TablePayload {
-    data: Vec<Value>,
-    schema: Value,
-    workspace: WorkspaceInfo,
+/// ファイル監視を開始できなかった場合のエラー
+///
+/// # 引数
+/// * `error` - notify のエラー
+/// * `data_path` - 監視しようとしたワークスペースのデータファイル
+fn watch_error(error: notify::Error, data_path: &Path) -> WorkspaceError {
+    WorkspaceError::new(
+        ErrorCode::Io,
+        "error.watch_failed",
+        tr!("error.watch_failed", path = data_path.display()),
+    )
+    .with_path(data_path)
+    .with_source(&error)
+}
+
+/// 指定されたデータファイルが存在しない場合のエラー
+fn missing_workspace(data_path: &Path) -> WorkspaceError {
+    WorkspaceError::new(
+        ErrorCode::NotFound,
+        "error.workspace_missing",
+        tr!("error.workspace_missing"),
+    )
+    .with_path(data_path)
+}
+
+impl AppState {
+    /// ワークスペースを開いてウィンドウに結び付け、ファイル監視を開始する
+    /// 同じデータファイルのワークスペースが既に開かれている場合は、そのワークスペース（と監視）を共有する
+    ///
+    /// # 引数
+    /// * `app_handle` - Tauriアプリケーションハンドル
+    /// * `window_label` - ワークスペースを表示するウィンドウのラベル
+    /// * `data_path` - データファイルのパス
+    ///
+    /// # 戻り値
+    /// 成功時はワークスペースのストレージ、失敗時はエラーメッセージ
+    fn open_workspace(
+        &self,
+        app_handle: &AppHandle,
+        window_label: &str,
+        data_path: PathBuf,
+    ) -> Result<Arc<dyn Storage>, WorkspaceError> {
+        let storage = open_storage(&data_path)?;
+        storage.ensure()?;
+        self.workspaces
+            .open(storage, window_label, |id, storage, windows| {
+                WorkspaceWatcher::start(app_handle, id, storage, windows)
+            })
+    }
+
+    /// 開いているワークスペースのストレージを取得する
+    ///
+    /// # 引数
+    /// * `workspace_id` - ワークスペースID
+    ///
+    /// # 戻り値
+    /// 成功時はストレージ、ワークスペースが開かれていない場合はエラー
+    fn storage(&self, workspace_id: &str) -> Result<Arc<dyn Storage>, WorkspaceError> {
+        self.workspaces.storage(workspace_id)
+    }
+
+    /// ワークスペースへの書き込みを、他の書き込みと直列にブロッキング処理用のスレッドで実行する
+    /// （ロックの待機やファイル操作で非同期ランタイムのスレッドを止めないため）
+    ///
+    /// # 引数
+    /// * `workspace_id` - ワークスペースID
+    /// * `task` - 書き込みロックを取得した状態で実行する処理
+    ///
+    /// # 戻り値
+    /// 処理の結果、ワークスペースが開かれていない場合や処理を完了できなかった場合はエラー
+    async fn write<T: Send + 'static>(
+        &self,
+        workspace_id: &str,
+        task: impl FnOnce(&dyn Storage) -> Result<T, WorkspaceError> + Send + 'static,
+    ) -> Result<T, WorkspaceError> {
+        let storage = self.storage(workspace_id)?;
+        let write_lock = Arc::clone(&self.write_lock);
+        tauri::async_runtime::spawn_blocking(move || {
+            let _write_guard = write_lock.lock();
+            task(storage.as_ref())
+        })
+        .await
+        .map_err(|err| {
+            WorkspaceError::new(
+                ErrorCode::Failed,
+                "error.task_failed",
+                tr!("error.task_failed"),
+            )
+            .with_source(&err)
+        })?
+    }
 }
 
 /// ワークスペースファイル変更イベントのペイロード
 #[derive(Serialize, Clone)]
 struct WorkspaceChangePayload {
+    workspace_id: String,
     data_path: String,
     schema_path: String,
 }
 
+/// フォルダ内のテーブルの一覧の変更イベントのペイロード
+#[derive(Serialize, Clone)]
+struct CatalogPayload {
+    workspace_id: String,
+    folder: String,
+    tables: Vec<CatalogEntry>,
+}
+
 /// ファイル監視エラーイベントのペイロード
 #[derive(Serialize, Clone)]
 struct WatchErrorPayload {
+    workspace_id: String,
     message: String,
 }
 
@@ -212,20 +509,109 @@ struct WatchErrorPayload {
 #[derive(Deserialize)]
 struct SavePayload {
     data: Vec<Value>,
-    schema: Value,
+    schema: TableSchema,
+    /// 読み込み時のリビジョン（省略時は競合を確認せずに上書きする）
+    #[serde(default)]
+    revision: Option<String>,
 }
 
-/// 保存結果をフロントエンドに返すペイロード
+/// 差分適用の結果をフロントエンドに返すペイロード
 #[derive(Serialize)]
-struct SaveResult {
+struct ApplyResult {
     row_count: usize,
+    /// 変更された行の_updatedに設定した日時
     updated_at: String,
+    /// 挿入または更新された行の_id
+    changed_ids: Vec<String>,
+    /// 削除された行の_id
+    removed_ids: Vec<String>,
+    /// スキーマが変更されたか
+    schema_changed: bool,
+    /// 適用は行われたが残っている問題（必須列が空など）
+    issues: Vec<RowIssue>,
+    /// 適用後のリビジョン
+    revision: String,
+}
+
+/// 行の検索結果をフロントエンドに返すペイロード
+#[derive(Serialize)]
+struct QueryResult {
+    /// 指定されたページの行
+    rows: Vec<Value>,
+    /// 条件に一致した行の総数
+    total: usize,
+    offset: usize,
+    /// 検索した内容のリビジョン
+    revision: String,
+}
+
+/// 集計結果をフロントエンドに返すペイロード
+#[derive(Serialize)]
+struct AggregateResult {
+    /// グループごとの集計結果
+    groups: Vec<AggregateGroup>,
+    /// 絞り込み条件に一致した行数
+    total: usize,
+    /// 集計した内容のリビジョン
+    revision: String,
+}
+
+/// 3-wayマージの結果をフロントエンドに返すペイロード
+#[derive(Serialize)]
+struct MergeResult {
+    /// 統合した行データ
+    data: Vec<Value>,
+    /// 現在保存されているスキーマ
+    schema: TableSchema,
+    /// 自動解決できなかったセル・行の競合（結果には自分の値を採用している）
+    conflicts: Vec<MergeConflict>,
+    /// マージの基準にした保存内容のリビジョン（統合結果を保存する際に送り返す）
+    revision: String,
+}
+
+/// 元に戻す・やり直すの結果をフロントエンドに返すペイロード
+#[derive(Serialize)]
+struct HistoryResult {
+    /// 適用後の内容
+    table: TablePayload,
+    /// 元に戻した・やり直した履歴の番号
+    target: u64,
+    can_undo: bool,
+    can_redo: bool,
+}
+
+/// 変更履歴の閲覧結果をフロントエンドに返すペイロード
+#[derive(Serialize)]
+struct HistoryPayload {
+    /// 新しい順の変更
+    items: Vec<HistoryItem>,
+    can_undo: bool,
+    can_redo: bool,
+    /// 履歴ファイルの読み込めずに飛ばした行
+    skipped: Vec<WorkspaceError>,
+}
+
+/// 書き出し結果をフロントエンドに返すペイロード
+#[derive(Serialize)]
+struct ExportResult {
+    path: String,
+    row_count: usize,
+}
+
+/// ストレージ変換結果をフロントエンドに返すペイロード
+#[derive(Serialize)]
+struct ConvertResult {
+    data_path: String,
+    schema_path: String,
+    storage: StorageKind,
+    row_count: usize,
 }
 
 /// テーブルデータを読み込むTauriコマンド
+/// ワークスペースを開き（既に開いている場合はそのまま）、以降のコマンドで使うIDを`workspace.id`で返す
 ///
 /// # 引数
-/// * `app_handle` - Tauriアプリケーションハンドル
+/// * `window` - 呼び出し元のウィンドウ（ワークスペースのイベントの送信先）
 /// * `state` - アプリケーション状態
 /// * `data_path` - 読み込むデータファイルのパス
 ///
@@ -233,372 +619,1002 @@ struct SaveResult {
 /// 成功時はTablePayload、失敗時はエラーメッセージ
 #[tauri::command]
 async fn load_table(
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+    data_path: String,
+) -> Result<TablePayload, WorkspaceError> {
+    let data_path = PathBuf::from(data_path);
+    if !data_path.exists() {
+        return Err(missing_workspace(&data_path));
+    }
+
+    let storage = state.open_workspace(window.app_handle(), window.label(), data_path)?;
+    build_table_payload(storage.as_ref())
+}
+
+/// 呼び出し元のウィンドウでワークスペースを閉じるTauriコマンド
+/// 他のウィンドウが同じワークスペースを表示していない場合は、ファイル監視も停止する
+///
+/// # 引数
+/// * `window` - 呼び出し元のウィンドウ
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+///
+/// # 戻り値
+/// ワークスペースを閉じた場合はtrue、他のウィンドウが表示している場合や開かれていなかった場合はfalse
+#[tauri::command]
+async fn close_workspace(
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<bool, WorkspaceError> {
+    Ok(state.workspaces.close(&workspace_id, window.label()))
+}
+
+/// テーブルを新しいウィンドウで開くTauriコマンド
+/// 新しいウィンドウはワークスペースに結び付けられ、同じファイルを開いている他のウィンドウと監視を共有する
+///
+/// # 引数
+/// * `app_handle` - Tauriアプリケーションハンドル
+/// * `state` - アプリケーション状態
+/// * `data_path` - 開くデータファイルのパス
+///
+/// # 戻り値
+/// 成功時は作成したウィンドウのラベル、失敗時はエラーメッセージ
+#[tauri::command]
+async fn open_table_window(
     app_handle: AppHandle,
     state: State<'_, AppState>,
     data_path: String,
-) -> Result<TablePayload, String> {
+) -> Result<String, WorkspaceError> {
     let data_path = PathBuf::from(data_path);
     if !data_path.exists() {
-        return Err("指定されたデータファイルが存在しません".to_string());
+        return Err(missing_workspace(&data_path));
     }
 
-    let schema_path = state.set_workspace(&app_handle, data_path.clone())?;
-    build_table_payload(&data_path, &schema_path)
+    let label = format!("{TABLE_WINDOW_PREFIX}{}", nanoid::nanoid!(10));
+    let storage = state.open_workspace(&app_handle, &label, data_path)?;
+    let id = workspace_id(storage.as_ref());
+    let title = storage
+        .read_schema()
+        .map(|schema| schema.table_name)
+        .unwrap_or_else(|_| storage.data_path().to_string_lossy().into_owned());
+
+    // フロントエンドはクエリのワークスペースIDで読み込む
+    let url = WebviewUrl::App(format!("index.html?workspace={id}").into());
+    if let Err(err) = WebviewWindowBuilder::new(&app_handle, &label, url)
+        .title(title)
+        .inner_size(800.0, 600.0)
+        .build()
+    {
+        state.workspaces.close(&id, &label);
+        return Err(WorkspaceError::new(
+            ErrorCode::Failed,
+            "error.window_failed",
+            tr!("error.window_failed"),
+        )
+        .with_source(&err));
+    }
+    Ok(label)
 }
 
 /// テーブルデータを保存するTauriコマンド
 ///
 /// # 引数
 /// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
 /// * `payload` - 保存するデータとスキーマ
 ///
 /// # 戻り値
-/// 成功時は保存結果、失敗時はエラーメッセージ
+/// 成功時は保存結果、読み込み後に他で更新されていた場合は競合エラー、失敗時はエラーメッセージ
 #[tauri::command]
 async fn save_table(
     state: State<'_, AppState>,
+    workspace_id: String,
     payload: SavePayload,
-) -> Result<SaveResult, String> {
-    let (data_path, schema_path) = state.paths()?;
-    let (mut data, mut schema) = (payload.data, payload.schema);
-    let now = Utc::now();
-
-    // 行データの正規化（ID、タイムスタンプ、順序の更新）
-    let row_count = normalise_rows(&mut data, now.to_rfc3339());
-    // スキーマメタデータの更新
-    update_schema_metadata(&mut schema, row_count, &now.to_rfc3339());
-
-    // バックアップを作成してからファイルに書き込む
-    write_with_backup(
-        &data_path,
-        serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?,
-    )?;
-    write_with_backup(
-        &schema_path,
-        serde_json::to_string_pretty(&schema).map_err(|e| e.to_string())?,
-    )?;
+) -> Result<SaveResult, WorkspaceError> {
+    state
+        .write(&workspace_id, move |storage| {
+            ensure_revision(storage, payload.revision.as_deref())?;
+            save_workspace(storage, payload.data, payload.schema)
+        })
+        .await
+}
 
-    Ok(SaveResult {
-        row_count,
-        updated_at: now.to_rfc3339(),
+/// 行・カラム単位の差分操作を保存済みのワークスペースに適用するTauriコマンド
+/// 全操作を適用・検証してから1度だけ書き込むため、途中で失敗した場合は何も保存されない
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `operations` - 適用する操作（先頭から順に適用）
+/// * `revision` - 読み込み時のリビジョン（省略時は競合を確認しない）
+///
+/// # 戻り値
+/// 成功時は適用結果、読み込み後に他で更新されていた場合は競合エラー、失敗時はエラーメッセージ
+#[tauri::command]
+async fn apply_changes(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    operations: Vec<ChangeOperation>,
+    revision: Option<String>,
+) -> Result<ApplyResult, WorkspaceError> {
+    state
+        .write(&workspace_id, move |storage| {
+            ensure_revision(storage, revision.as_deref())?;
+
+            let previous_rows = storage.read_rows()?;
+            let previous_schema = storage.read_schema()?;
+            let (mut data, mut schema) = (previous_rows.clone(), previous_schema.clone());
+            let now = Utc::now().to_rfc3339();
+
+            let outcome = apply_operations(&mut data, &mut schema, &operations, &now)?;
+            let saved = commit_workspace(
+                storage,
+                Some((&previous_rows, &previous_schema)),
+                data,
+                schema,
+                (HistoryAction::Edit, None),
+                &now,
+            )?;
+
+            Ok(ApplyResult {
+                row_count: saved.row_count,
+                updated_at: saved.updated_at,
+                changed_ids: outcome.changed_ids,
+                removed_ids: outcome.removed_ids,
+                schema_changed: outcome.schema_changed,
+                issues: saved.issues,
+                revision: saved.revision,
+            })
+        })
+        .await
+}
+
+/// 保存されている行を絞り込み・並べ替えて、指定されたページだけを返すTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `query` - 絞り込み条件、並べ替えのキー、offset/limit
+///
+/// # 戻り値
+/// 成功時は指定ページの行と一致した行の総数、失敗時はエラーメッセージ
+#[tauri::command]
+async fn query_rows(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    query: RowQuery,
+) -> Result<QueryResult, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    let revision = storage.revision()?;
+    let (rows, schema, _) = read_evaluated(storage.as_ref())?;
+
+    let page = run_query(&rows, &schema, &query)?;
+    Ok(QueryResult {
+        rows: page.rows,
+        total: page.total,
+        offset: query.offset,
+        revision,
     })
 }
 
-/// 現在のワークスペースのデータを再読み込みするTauriコマンド
+/// 保存されている行をグループ化して集計するTauriコマンド
+/// 絞り込み条件は query_rows と同じ式を使う
 ///
 /// # 引数
 /// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `query` - 絞り込み条件、グループ化するカラム、集計の一覧
 ///
 /// # 戻り値
-/// 成功時はTablePayload、失敗時はエラーメッセージ
+/// 成功時はグループごとの集計結果、失敗時はエラーメッセージ
 #[tauri::command]
-async fn fetch_workspace(state: State<'_, AppState>) -> Result<TablePayload, String> {
-    let (data_path, schema_path) = state.paths()?;
-    build_table_payload(&data_path, &schema_path)
+async fn aggregate_rows(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    query: AggregateQuery,
+) -> Result<AggregateResult, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    let revision = storage.revision()?;
+    let (rows, schema, _) = read_evaluated(storage.as_ref())?;
+
+    let outcome = run_aggregate(&rows, &schema, &query)?;
+    Ok(AggregateResult {
+        groups: outcome.groups,
+        total: outcome.total,
+        revision,
+    })
 }
 
-/// 新しいワークスペースを作成するTauriコマンド
+/// 読み込み時の内容を基準に、自分の変更と現在保存されている内容を統合するTauriコマンド
+/// 行は_id、セルはカラムIDで対応付け、片方だけが変更した値は自動で採用する
+/// 統合結果は保存しないため、確認後に返されたリビジョンを付けて save_table で保存する
 ///
 /// # 引数
-/// * `app_handle` - Tauriアプリケーションハンドル
 /// * `state` - アプリケーション状態
-/// * `path` - 新しいデータファイルのパス
+/// * `workspace_id` - ワークスペースのID
+/// * `base` - 読み込み時（または最後に保存した時）の行データ
+/// * `local` - 編集中の行データ
 ///
 /// # 戻り値
-/// 成功時はTablePayload、失敗時はエラーメッセージ
+/// 成功時は統合結果と競合の一覧、失敗時はエラーメッセージ
 #[tauri::command]
-async fn create_workspace(
-    app_handle: AppHandle,
+async fn merge_workspace(
     state: State<'_, AppState>,
-    path: String,
-) -> Result<TablePayload, String> {
-    let mut data_path = PathBuf::from(path.trim());
-    if data_path.to_string_lossy().trim().is_empty() {
-        return Err("ファイルパスを指定してください".into());
-    }
+    workspace_id: String,
+    base: Vec<Value>,
+    local: Vec<Value>,
+) -> Result<MergeResult, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    // 読み込みの途中で更新された場合に競合を見逃さないよう、先にリビジョンを取得する
+    let revision = storage.revision()?;
+    let remote = storage.read_rows()?;
+    let schema = storage.read_schema()?;
 
-    // .json拡張子がない場合は追加
-    if data_path.extension().and_then(|ext| ext.to_str()) != Some("json") {
-        data_path.set_extension("json");
-    }
+    let outcome = merge_rows(&base, &local, &remote);
+    Ok(MergeResult {
+        data: outcome.rows,
+        schema,
+        conflicts: outcome.conflicts,
+        revision,
+    })
+}
 
-    let stem = data_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .ok_or_else(|| "ファイル名を取得できません".to_string())?
-        .trim();
+/// ワークスペースのバックアップを新しい順に一覧するTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+///
+/// # 戻り値
+/// 成功時はバックアップの一覧（行数を含む）、失敗時はエラーメッセージ
+#[tauri::command]
+async fn list_backups(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<Vec<BackupInfo>, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    storage::list_backups(storage.as_ref())
+}
 
-    if stem.is_empty() {
-        return Err("ファイル名を入力してください".into());
-    }
+/// バックアップをワークスペースの内容として復元するTauriコマンド
+/// 復元前の内容もバックアップされるため、別のバックアップを復元して元に戻せる
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `id` - 復元するバックアップのID
+///
+/// # 戻り値
+/// 成功時は復元後のTablePayload、失敗時はエラーメッセージ
+#[tauri::command]
+async fn restore_backup(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    id: String,
+) -> Result<TablePayload, WorkspaceError> {
+    state
+        .write(&workspace_id, move |storage| {
+            restore_with_history(storage, &id)?;
+            build_table_payload(storage)
+        })
+        .await
+}
 
-    // スキーマファイルのパスを生成（data.json → data.schema.json）
-    let schema_path = data_path
-        .parent()
-        .ok_or_else(|| "保存先フォルダを取得できません".to_string())?
-        .join(format!("{stem}.schema.json"));
+/// 現在保存されている内容を名前付きのスナップショットとして保存するTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `name` - スナップショット名
+///
+/// # 戻り値
+/// 成功時は作成したスナップショットの情報、失敗時はエラーメッセージ
+#[tauri::command]
+async fn create_snapshot(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    name: String,
+) -> Result<SnapshotInfo, WorkspaceError> {
+    state
+        .write(&workspace_id, move |storage| {
+            storage::create_snapshot(storage, &name)
+        })
+        .await
+}
 
-    // 親ディレクトリが存在しない場合は作成
-    if let Some(parent) = data_path.parent() {
-        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
-    }
+/// ワークスペースのスナップショットを新しい順に一覧するTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+///
+/// # 戻り値
+/// 成功時はスナップショットの一覧（行数を含む）、失敗時はエラーメッセージ
+#[tauri::command]
+async fn list_snapshots(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<Vec<SnapshotInfo>, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    storage::list_snapshots(storage.as_ref())
+}
+
+/// スナップショットを削除するTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `name` - スナップショット名
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、失敗時はエラーメッセージ
+#[tauri::command]
+async fn delete_snapshot(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    name: String,
+) -> Result<(), WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    storage::delete_snapshot(storage.as_ref(), &name)
+}
 
-    // ファイルが既に存在する場合はエラー
-    if data_path.exists() || schema_path.exists() {
-        return Err("同名のファイルが既に存在します".into());
+/// 2つのスナップショット、またはスナップショットと現在のファイルを比較するTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `from` - 比較元のスナップショット名
+/// * `to` - 比較先のスナップショット名（省略時は現在のファイル）
+///
+/// # 戻り値
+/// 成功時は比較結果、失敗時はエラーメッセージ
+#[tauri::command]
+async fn diff_snapshots(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    from: String,
+    to: Option<String>,
+) -> Result<TableDiff, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    build_diff(storage.as_ref(), &from, to.as_deref())
+}
+
+/// 比較結果をJSONファイルに書き出すTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `from` - 比較元のスナップショット名
+/// * `to` - 比較先のスナップショット名（省略時は現在のファイル）
+/// * `target_path` - 出力先のパス
+///
+/// # 戻り値
+/// 成功時は出力先と差分のある行数、失敗時はエラーメッセージ
+#[tauri::command]
+async fn export_diff(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    from: String,
+    to: Option<String>,
+    target_path: String,
+) -> Result<ExportResult, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    let diff = build_diff(storage.as_ref(), &from, to.as_deref())?;
+    let target = PathBuf::from(&target_path);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|err| WorkspaceError::io(err, parent))?;
     }
+    fs::write(
+        &target,
+        serde_json::to_string_pretty(&diff).map_err(|err| WorkspaceError::json(err, &target))?,
+    )
+    .map_err(|err| WorkspaceError::io(err, &target))?;
 
-    // 空のデータファイルとデフォルトスキーマを作成
-    ensure_data_files(&data_path, &schema_path)?;
-    state.set_workspace(&app_handle, data_path.clone())?;
-    build_table_payload(&data_path, &schema_path)
+    Ok(ExportResult {
+        path: target_path,
+        row_count: diff.summary.added_rows + diff.summary.removed_rows + diff.summary.changed_rows,
+    })
 }
 
-/// 行データを正規化する（ID、タイムスタンプ、順序の追加・更新）
+/// 開いているワークスペースを読み書きするローカルAPIを開始するTauriコマンド
+/// 127.0.0.1でのみ待ち受け、返したトークンを`Authorization: Bearer <token>`で要求する
+/// 既に起動している場合は、起動中の接続先をそのまま返す
 ///
 /// # 引数
-/// * `rows` - 正規化する行データの可変参照
-/// * `timestamp` - 更新タイムスタンプ
+/// * `app_handle` - Tauriアプリケーションハンドル
+/// * `state` - アプリケーション状態
+/// * `port` - 待ち受けるポート（省略時は8765、0の場合は空いているポート）
 ///
 /// # 戻り値
-/// 行数
-fn normalise_rows(rows: &mut [Value], timestamp: String) -> usize {
-    rows.iter_mut().enumerate().for_each(|(index, row)| {
-        if let Value::Object(ref mut obj) = row {
-            // _idが存在しない場合は生成して追加
-            let id_entry = obj.entry("_id".to_string());
-            if matches!(id_entry, serde_json::map::Entry::Vacant(_)) {
-                let id = nanoid::nanoid!(10);
-                obj.insert("_id".into(), Value::String(format!("row_{id}")));
-            }
+/// 成功時は接続先のURLとトークン、ポートを使用できない場合はエラー
+#[tauri::command]
+async fn start_api_server(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    port: Option<u16>,
+) -> Result<ApiServerInfo, WorkspaceError> {
+    let mut api = state.api.lock();
+    if let Some(server) = api.as_ref() {
+        return Ok(server.info().clone());
+    }
+    let server = ApiServer::start(app_handle, port.unwrap_or(DEFAULT_API_PORT))?;
+    let info = server.info().clone();
+    *api = Some(server);
+    Ok(info)
+}
 
-            // _createdが存在しない場合のみ追加（作成日時は不変）
-            if !obj.contains_key("_created") {
-                obj.insert("_created".into(), Value::String(timestamp.clone()));
-            }
-            // _updatedは常に最新のタイムスタンプで更新
-            obj.insert("_updated".into(), Value::String(timestamp.clone()));
-
-            // _orderが存在しない場合は追加、または無効な値の場合は修正
-            if !obj.contains_key("_order") {
-                obj.insert(
-                    "_order".into(),
-                    Value::Number(serde_json::Number::from(index as u64)),
-                );
-            } else if let Some(order_value) = obj.get_mut("_order") {
-                if let Value::Number(_) = order_value {
-                    // ユーザー定義の数値をそのまま保持
-                } else {
-                    *order_value = Value::Number(serde_json::Number::from(index as u64));
-                }
-            }
-        }
-    });
+/// ローカルAPIを停止するTauriコマンド
+/// 新しい接続の受け付けをやめ、処理中のリクエストが終わってから戻る（送信を止めたクライアントは待ち続けない）
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+///
+/// # 戻り値
+/// 停止した場合はtrue、起動していなかった場合はfalse
+#[tauri::command]
+async fn stop_api_server(state: State<'_, AppState>) -> Result<bool, WorkspaceError> {
+    let Some(server) = state.api.lock().take() else {
+        return Ok(false);
+    };
+    // 破棄すると処理中のリクエストが終わるまで待つため、ロックの外のブロッキング処理用のスレッドで破棄する
+    tauri::async_runtime::spawn_blocking(move || drop(server))
+        .await
+        .map_err(|err| {
+            WorkspaceError::new(
+                ErrorCode::Failed,
+                "error.task_failed",
+                tr!("error.task_failed"),
+            )
+            .with_source(&err)
+        })?;
+    Ok(true)
+}
 
-    rows.len()
+/// 起動中のローカルAPIの接続先を返すTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+///
+/// # 戻り値
+/// 起動中の場合は接続先のURLとトークン、起動していない場合はNone
+#[tauri::command]
+async fn get_api_server(
+    state: State<'_, AppState>,
+) -> Result<Option<ApiServerInfo>, WorkspaceError> {
+    Ok(state
+        .api
+        .lock()
+        .as_ref()
+        .map(|server| server.info().clone()))
 }
 
-/// スキーマのメタデータを更新する
+/// フォルダ内のテーブル（スキーマファイルのあるデータファイルとSQLiteファイル）を一覧するTauriコマンド
 ///
 /// # 引数
-/// * `schema` - 更新するスキーマの可変参照
-/// * `row_count` - 行数
-/// * `updated_at` - 更新日時
-fn update_schema_metadata(schema: &mut Value, row_count: usize, updated_at: &str) {
-    // metadataが存在する場合は更新
-    if let Some(metadata) = schema
-        .get_mut("metadata")
-        .and_then(|value| value.as_object_mut())
-    {
-        metadata.insert("row_count".into(), json!(row_count));
-        metadata.insert("updated_at".into(), json!(updated_at));
-    } else {
-        // metadataが存在しない場合は新規作成
-        schema.as_object_mut().map(|object| {
-            object.insert(
-                "metadata".into(),
-                json!({
-                    "row_count": row_count,
-                    "updated_at": updated_at,
-                }),
-            );
-        });
+/// * `folder` - 一覧するフォルダ
+///
+/// # 戻り値
+/// 成功時はテーブル名・行数・更新日時を含む一覧、失敗時はエラーメッセージ
+#[tauri::command]
+async fn list_tables(folder: String) -> Result<Vec<CatalogEntry>, WorkspaceError> {
+    let folder = PathBuf::from(folder);
+    if !folder.is_dir() {
+        return Err(WorkspaceError::new(
+            ErrorCode::NotFound,
+            "error.folder_not_found",
+            tr!("error.folder_not_found", path = folder.display()),
+        )
+        .with_path(&folder));
     }
+    storage::list_tables(&folder)
+}
+
+/// スナップショット同士、またはスナップショットと現在のファイルの比較結果を作る
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `from` - 比較元のスナップショット名
+/// * `to` - 比較先のスナップショット名（Noneの場合は現在のファイル）
+fn build_diff(
+    storage: &dyn Storage,
+    from: &str,
+    to: Option<&str>,
+) -> Result<TableDiff, WorkspaceError> {
+    let (from_rows, from_schema) = read_snapshot(storage, from)?;
+    let (to_label, (to_rows, to_schema)) = match to {
+        Some(name) => (name, read_snapshot(storage, name)?),
+        None => (
+            CURRENT_LABEL,
+            (storage.read_rows()?, storage.read_schema()?),
+        ),
+    };
+    Ok(diff_tables(
+        (from, &from_rows, &from_schema),
+        (to_label, &to_rows, &to_schema),
+    ))
 }
 
-/// ファイルをバックアップしてから書き込む
-/// 既存ファイルは.bakとして保存され、一時ファイル経由で安全に書き込まれる
+/// 直前の変更を元に戻すTauriコマンド
+/// 履歴ファイルに記録された変更をセル単位で書き戻すため、アプリを再起動した後も使える
 ///
 /// # 引数
-/// * `path` - 書き込み先のファイルパス
-/// * `contents` - 書き込む内容
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `revision` - 読み込み時のリビジョン（省略時は競合を確認しない）
 ///
 /// # 戻り値
-/// 成功時は`Ok(())`、失敗時はエラーメッセージ
-fn write_with_backup(path: &Path, contents: String) -> Result<(), String> {
-    // 親ディレクトリが存在しない場合は作成
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
-    }
+/// 成功時は元に戻した後の内容、読み込み後に他で更新されていた場合は競合エラー、失敗時はエラーメッセージ
+#[tauri::command]
+async fn undo(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    revision: Option<String>,
+) -> Result<HistoryResult, WorkspaceError> {
+    state
+        .write(&workspace_id, move |storage| {
+            step_history(storage, revision.as_deref(), Direction::Backward)
+        })
+        .await
+}
 
-    // 既存ファイルがあればバックアップを作成
-    if path.exists() {
-        let backup_path = path.with_extension("json.bak");
-        fs::copy(path, &backup_path).map_err(|err| err.to_string())?;
-    }
+/// 元に戻した変更をやり直すTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `revision` - 読み込み時のリビジョン（省略時は競合を確認しない）
+///
+/// # 戻り値
+/// 成功時はやり直した後の内容、読み込み後に他で更新されていた場合は競合エラー、失敗時はエラーメッセージ
+#[tauri::command]
+async fn redo(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    revision: Option<String>,
+) -> Result<HistoryResult, WorkspaceError> {
+    state
+        .write(&workspace_id, move |storage| {
+            step_history(storage, revision.as_deref(), Direction::Forward)
+        })
+        .await
+}
 
-    // 一時ファイルに書き込んでからリネーム（アトミック操作）
-    let tmp_path = path.with_extension("json.tmp");
-    let mut file = File::create(&tmp_path).map_err(|err| err.to_string())?;
-    file.write_all(contents.as_bytes())
-        .map_err(|err| err.to_string())?;
-    file.flush().map_err(|err| err.to_string())?;
+/// 変更履歴を新しい順に取得するTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `row_id` - 指定した場合はその行の変更のみ
+/// * `column_id` - 指定した場合はそのカラムの変更のみ
+/// * `limit` - 返す件数の上限（省略時は200件）
+///
+/// # 戻り値
+/// 成功時は変更の一覧と元に戻す・やり直すの可否、失敗時はエラーメッセージ
+#[tauri::command]
+async fn fetch_history(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    row_id: Option<String>,
+    column_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<HistoryPayload, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    let log = read_history(&history_path(storage.data_path()))?;
+    let (undo, redo) = undo_stacks(&log.entries);
+    Ok(HistoryPayload {
+        items: history_items(
+            &log.entries,
+            row_id.as_deref(),
+            column_id.as_deref(),
+            limit.unwrap_or(DEFAULT_HISTORY_LIMIT),
+        ),
+        can_undo: !undo.is_empty(),
+        can_redo: !redo.is_empty(),
+        skipped: log.skipped,
+    })
+}
 
-    fs::rename(&tmp_path, path).map_err(|err| err.to_string())
+/// 履歴を1つ元に戻す、またはやり直して保存する
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ（書き込みロックを取得した状態で呼び出す）
+/// * `revision` - 読み込み時のリビジョン
+/// * `direction` - 元に戻す（Backward）か、やり直す（Forward）か
+///
+/// # 戻り値
+/// 成功時は適用結果、失敗時は競合エラーまたはエラーメッセージ
+fn step_history(
+    storage: &dyn Storage,
+    revision: Option<&str>,
+    direction: Direction,
+) -> Result<HistoryResult, WorkspaceError> {
+    ensure_revision(storage, revision)?;
+
+    let path = history_path(storage.data_path());
+    let entries = read_history(&path)?.entries;
+    let (undo, redo) = undo_stacks(&entries);
+    let (target, action) = match direction {
+        Direction::Backward => (undo.last(), HistoryAction::Undo),
+        Direction::Forward => (redo.last(), HistoryAction::Redo),
+    };
+    let target = *target.ok_or_else(|| match direction {
+        Direction::Backward => {
+            WorkspaceError::invalid_input("history.nothing_to_undo", tr!("history.nothing_to_undo"))
+        }
+        Direction::Forward => {
+            WorkspaceError::invalid_input("history.nothing_to_redo", tr!("history.nothing_to_redo"))
+        }
+    })?;
+    let change_set = entries
+        .iter()
+        .find(|entry| entry.seq == target)
+        .ok_or_else(|| {
+            WorkspaceError::new(
+                ErrorCode::NotFound,
+                "history.not_found",
+                tr!("history.not_found", id = target),
+            )
+            .with_path(&path)
+        })?;
+
+    let previous_rows = storage.read_rows()?;
+    let previous_schema = storage.read_schema()?;
+    let (mut data, mut schema) = (previous_rows.clone(), previous_schema.clone());
+    let now = Utc::now().to_rfc3339();
+
+    apply_change_set(&mut data, &mut schema, change_set, direction, &now);
+    // その後のスキーマ変更などで型が合わなくなった値は書き込まない
+    commit_workspace(
+        storage,
+        Some((&previous_rows, &previous_schema)),
+        data,
+        schema,
+        (action, Some(target)),
+        &now,
+    )?;
+
+    let (undo, redo) = undo_stacks(&read_history(&path)?.entries);
+    Ok(HistoryResult {
+        table: build_table_payload(storage)?,
+        target,
+        can_undo: !undo.is_empty(),
+        can_redo: !redo.is_empty(),
+    })
+}
+
+/// ワークスペースのバックアップ保持ルールを取得するTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+///
+/// # 戻り値
+/// 成功時は保持ルール、失敗時はエラーメッセージ
+#[tauri::command]
+async fn get_backup_policy(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<BackupPolicy, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    read_policy(storage.as_ref())
 }
 
-/// データファイルとスキーマファイルが存在することを保証する
-/// 存在しない場合は空のデータファイルとデフォルトスキーマを作成
+/// ワークスペースのバックアップ保持ルールを変更するTauriコマンド
+/// 保存後、新しいルールに該当しないバックアップは削除される
 ///
 /// # 引数
-/// * `data_path` - データファイルのパス
-/// * `schema_path` - スキーマファイルのパス
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `policy` - 保持ルール
 ///
 /// # 戻り値
 /// 成功時は`Ok(())`、失敗時はエラーメッセージ
-fn ensure_data_files(data_path: &Path, schema_path: &Path) -> Result<(), String> {
-    // 親ディレクトリが存在しない場合は作成
-    if let Some(parent) = data_path.parent() {
-        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
-    }
+#[tauri::command]
+async fn set_backup_policy(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    policy: BackupPolicy,
+) -> Result<(), WorkspaceError> {
+    state
+        .write(&workspace_id, move |storage| write_policy(storage, &policy))
+        .await
+}
 
-    // データファイルが存在しない場合は空の配列を作成
-    if !data_path.exists() {
-        fs::write(data_path, "[]").map_err(|err| err.to_string())?;
-    }
+/// ワークスペースのデータを再読み込みするTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+///
+/// # 戻り値
+/// 成功時はTablePayload、失敗時はエラーメッセージ
+#[tauri::command]
+async fn fetch_workspace(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<TablePayload, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    build_table_payload(storage.as_ref())
+}
 
-    // スキーマファイルが存在しない場合はデフォルトスキーマを作成
-    if !schema_path.exists() {
-        let now = Utc::now().to_rfc3339();
-        let default_schema = json!({
-            "version": "1.0",
-            "table_name": data_path
-                .file_stem()
-                .and_then(|stem| stem.to_str())
-                .unwrap_or("Untitled"),
-            "columns": [
-                { "id": "_id", "name": "ID", "type": "text", "hidden": true, "system": true },
-            ],
-            "metadata": {
-                "created_at": now,
-                "updated_at": now,
-                "row_count": 0
-            },
-            "extensions": {
-                "available_types": ["text", "number", "checkbox", "multiselect", "relation"],
-                "future": "拡張型を追加できる設計とする"
-            }
-        });
-        fs::write(
-            schema_path,
-            serde_json::to_string_pretty(&default_schema).map_err(|err| err.to_string())?,
-        )
-        .map_err(|err| err.to_string())?;
-    }
+/// 新しいワークスペースを作成するTauriコマンド
+///
+/// # 引数
+/// * `window` - 呼び出し元のウィンドウ（ワークスペースのイベントの送信先）
+/// * `state` - アプリケーション状態
+/// * `path` - 新しいデータファイルのパス
+///
+/// # 戻り値
+/// 成功時はTablePayload、失敗時はエラーメッセージ
+#[tauri::command]
+async fn create_workspace(
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<TablePayload, WorkspaceError> {
+    let (data_path, schema_path) = prepare_new_workspace(&path)?;
 
-    Ok(())
+    // 空のデータファイルとデフォルトスキーマを作成
+    ensure_data_files(&data_path, &schema_path)?;
+    let storage = state.open_workspace(window.app_handle(), window.label(), data_path)?;
+    build_table_payload(storage.as_ref())
 }
 
-/// データファイルを読み込む
+/// CSV/TSVファイルを取り込んで新しいワークスペースを作成するTauriコマンド
 ///
 /// # 引数
-/// * `path` - データファイルのパス
+/// * `window` - 呼び出し元のウィンドウ（ワークスペースのイベントの送信先）
+/// * `state` - アプリケーション状態
+/// * `source_path` - 取り込むCSV/TSVファイルのパス
+/// * `path` - 新しいデータファイルのパス
+/// * `options` - 区切り文字・見出し行・文字コードの指定
 ///
 /// # 戻り値
-/// 成功時はJSON配列、失敗時はエラーメッセージ
-fn read_data_file(path: &Path) -> Result<Vec<Value>, String> {
-    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
-    let value: Value = serde_json::from_str(&contents).map_err(|err| err.to_string())?;
-    match value {
-        Value::Array(array) => Ok(array),
-        _ => Err("データファイルの形式が正しくありません".to_string()),
-    }
+/// 成功時はTablePayload、失敗時はエラーメッセージ
+#[tauri::command]
+async fn import_delimited(
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+    source_path: String,
+    path: String,
+    options: Option<DelimitedImportOptions>,
+) -> Result<TablePayload, WorkspaceError> {
+    let imported = read_delimited_file(Path::new(&source_path), &options.unwrap_or_default())?;
+    create_workspace_from_import(&window, &state, &path, imported)
 }
 
-/// スキーマファイルを読み込む
+/// 現在のワークスペースをCSV/TSVファイルに書き出すTauriコマンド
 ///
 /// # 引数
-/// * `path` - スキーマファイルのパス
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `target_path` - 出力先のパス
+/// * `options` - 区切り文字・出力する列・BOMの指定
 ///
 /// # 戻り値
-/// 成功時はJSONオブジェクト、失敗時はエラーメッセージ
-fn read_schema_file(path: &Path) -> Result<Value, String> {
-    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
-    serde_json::from_str(&contents).map_err(|err| err.to_string())
+/// 成功時は書き出し結果、失敗時はエラーメッセージ
+#[tauri::command]
+async fn export_delimited(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    target_path: String,
+    options: Option<DelimitedExportOptions>,
+) -> Result<ExportResult, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    let (data, schema, _) = read_evaluated(storage.as_ref())?;
+
+    let path = PathBuf::from(target_path);
+    let row_count = write_delimited_file(&path, &schema, &data, &options.unwrap_or_default())?;
+    Ok(ExportResult {
+        path: path.to_string_lossy().into_owned(),
+        row_count,
+    })
 }
 
-/// データファイルパスからスキーマファイルパスを生成する
-/// 例: data.json → data.schema.json
+/// Excelファイルのシートを取り込んで新しいワークスペースを作成するTauriコマンド
 ///
 /// # 引数
-/// * `data_path` - データファイルのパス
+/// * `window` - 呼び出し元のウィンドウ（ワークスペースのイベントの送信先）
+/// * `state` - アプリケーション状態
+/// * `source_path` - 取り込む.xlsxファイルのパス
+/// * `path` - 新しいデータファイルのパス
+/// * `options` - シート・見出し行の指定
 ///
 /// # 戻り値
-/// 成功時はスキーマファイルのパス、失敗時はエラーメッセージ
-fn schema_path_for(data_path: &Path) -> Result<PathBuf, String> {
-    let stem = data_path
-        .file_stem()
-        .and_then(|stem| stem.to_str())
-        .ok_or_else(|| "データファイル名を取得できません".to_string())?;
+/// 成功時はTablePayload、失敗時はエラーメッセージ
+#[tauri::command]
+async fn import_xlsx(
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+    source_path: String,
+    path: String,
+    options: Option<XlsxImportOptions>,
+) -> Result<TablePayload, WorkspaceError> {
+    let imported = read_xlsx_file(Path::new(&source_path), &options.unwrap_or_default())?;
+    create_workspace_from_import(&window, &state, &path, imported)
+}
 
-    let parent = data_path
-        .parent()
-        .ok_or_else(|| "親ディレクトリを取得できません".to_string())?;
+/// 現在のワークスペースをExcelファイルに書き出すTauriコマンド
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `workspace_id` - ワークスペースのID
+/// * `target_path` - 出力先の.xlsxファイルのパス
+/// * `options` - 出力する列の指定
+///
+/// # 戻り値
+/// 成功時は書き出し結果、失敗時はエラーメッセージ
+#[tauri::command]
+async fn export_xlsx(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    target_path: String,
+    options: Option<XlsxExportOptions>,
+) -> Result<ExportResult, WorkspaceError> {
+    let storage = state.storage(&workspace_id)?;
+    let (data, schema, _) = read_evaluated(storage.as_ref())?;
 
-    Ok(parent.join(format!("{stem}.schema.json")))
+    let path = PathBuf::from(target_path);
+    let row_count = write_xlsx_file(&path, &schema, &data, &options.unwrap_or_default())?;
+    Ok(ExportResult {
+        path: path.to_string_lossy().into_owned(),
+        row_count,
+    })
 }
 
-/// テーブルペイロードを構築する
-/// データとスキーマを読み込み、ワークスペース情報と共にまとめる
+/// 取り込んだテーブルから新しいワークスペースを作成する
 ///
 /// # 引数
-/// * `data_path` - データファイルのパス
-/// * `schema_path` - スキーマファイルのパス
+/// * `window` - 呼び出し元のウィンドウ（ワークスペースのイベントの送信先）
+/// * `state` - アプリケーション状態
+/// * `path` - 新しいデータファイルのパス
+/// * `imported` - 取り込んだカラムと行
 ///
 /// # 戻り値
 /// 成功時はTablePayload、失敗時はエラーメッセージ
-fn build_table_payload(data_path: &Path, schema_path: &Path) -> Result<TablePayload, String> {
-    let data = read_data_file(data_path)?;
-    let schema = read_schema_file(schema_path)?;
-
-    let workspace = WorkspaceInfo {
-        data_path: data_path.to_string_lossy().into_owned(),
-        schema_path: schema_path.to_string_lossy().into_owned(),
-        folder: data_path
-            .parent()
-            .map(|p| p.to_string_lossy().into_owned())
-            .unwrap_or_default(),
-    };
+fn create_workspace_from_import(
+    window: &WebviewWindow,
+    state: &AppState,
+    path: &str,
+    imported: ImportedTable,
+) -> Result<TablePayload, WorkspaceError> {
+    let (data_path, _) = write_imported_workspace(path, imported)?;
+    let storage = state.open_workspace(window.app_handle(), window.label(), data_path)?;
+    build_table_payload(storage.as_ref())
+}
 
-    Ok(TablePayload {
-        data,
-        schema,
-        workspace,
+/// ワークスペースをJSONとSQLiteの間で変換するTauriコマンド
+/// 変換元は変更せず、変換先に新しいワークスペースを作成する
+///
+/// # 引数
+/// * `source_path` - 変換元のデータファイルのパス
+/// * `target` - 変換先のストレージの種類
+/// * `target_path` - 変換先のパス（省略時は拡張子だけを変えたパス）
+///
+/// # 戻り値
+/// 成功時は変換結果、失敗時はエラーメッセージ
+#[tauri::command]
+async fn convert_workspace(
+    source_path: String,
+    target: StorageKind,
+    target_path: Option<String>,
+) -> Result<ConvertResult, WorkspaceError> {
+    let source_path = PathBuf::from(source_path);
+    let source = open_storage(&source_path)?;
+    if !source.exists() {
+        return Err(missing_workspace(&source_path));
+    }
+    if source.kind() == target {
+        return Err(WorkspaceError::invalid_input(
+            "error.same_storage_kind",
+            tr!("error.same_storage_kind"),
+        ));
+    }
+
+    let target_path = target_path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_conversion_path(&source_path, target));
+    let destination = open_storage_as(target, &target_path)?;
+    if destination.exists() {
+        return Err(WorkspaceError::new(
+            ErrorCode::AlreadyExists,
+            "error.already_exists",
+            tr!("error.already_exists"),
+        )
+        .with_path(destination.data_path()));
+    }
+
+    let rows = source.read_rows()?;
+    let schema = source.read_schema()?;
+    destination.ensure()?;
+    destination.write_table(&rows, &schema)?;
+
+    Ok(ConvertResult {
+        data_path: destination.data_path().to_string_lossy().into_owned(),
+        schema_path: destination.schema_path().to_string_lossy().into_owned(),
+        storage: target,
+        row_count: rows.len(),
     })
 }
 
+/// バックエンドのメッセージの言語を返すTauriコマンド
+///
+/// # 戻り値
+/// 現在の言語
+#[tauri::command]
+async fn get_locale() -> Result<Locale, WorkspaceError> {
+    Ok(i18n::current_locale())
+}
+
+/// バックエンドのメッセージ（エラーや検証結果）の言語を切り替えるTauriコマンド
+/// 以降に返すメッセージから切り替わり、すべてのウィンドウで共通になる
+///
+/// # 引数
+/// * `locale` - 言語（"ja" または "en"）
+///
+/// # 戻り値
+/// 切り替えた言語
+#[tauri::command]
+async fn set_locale(locale: Locale) -> Result<Locale, WorkspaceError> {
+    i18n::set_locale(locale);
+    Ok(locale)
+}
+
 /// Tauriアプリケーションのエントリーポイント
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // メッセージの言語は、set_localeで切り替えるまでOSの言語設定に従う
+    i18n::set_locale(i18n::detect_locale());
+
     tauri::Builder::default()
         .manage(AppState::default())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .on_window_event(|window, event| {
+            // 閉じたウィンドウだけが表示していたワークスペースの監視を停止する
+            if let WindowEvent::Destroyed = event {
+                window
+                    .state::<AppState>()
+                    .workspaces
+                    .release_window(window.label());
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             load_table,
+            close_workspace,
+            open_table_window,
             save_table,
+            apply_changes,
+            merge_workspace,
+            query_rows,
+            aggregate_rows,
             fetch_workspace,
-            create_workspace
+            create_workspace,
+            import_delimited,
+            export_delimited,
+            import_xlsx,
+            export_xlsx,
+            convert_workspace,
+            list_backups,
+            restore_backup,
+            get_backup_policy,
+            set_backup_policy,
+            undo,
+            redo,
+            fetch_history,
+            create_snapshot,
+            list_snapshots,
+            delete_snapshot,
+            diff_snapshots,
+            export_diff,
+            list_tables,
+            get_locale,
+            set_locale,
+            start_api_server,
+            stop_api_server,
+            get_api_server
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,464 @@
+// 行データのグループ化と集計（aggregate_rows）の処理
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+
+use crate::query::{cell, compare_for_sort, ensure_column, Filter};
+use crate::schema::{ColumnType, TableSchema};
+use crate::validation::is_empty_value;
+use crate::{tr, WorkspaceError};
+
+/// 集計関数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateFunction {
+    /// 件数（カラム指定時は未入力でない値の件数）
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    /// 異なる値の数（multiselectはタグごとに数える）
+    DistinctCount,
+}
+
+impl AggregateFunction {
+    /// 関数名（結果のキーとエラーメッセージ用）
+    fn name(self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Avg => "avg",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::DistinctCount => "distinct_count",
+        }
+    }
+
+    /// 数値として計算する関数か
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            AggregateFunction::Sum
+                | AggregateFunction::Avg
+                | AggregateFunction::Min
+                | AggregateFunction::Max
+        )
+    }
+}
+
+/// 1つの集計
+#[derive(Debug, Clone, Deserialize)]
+pub struct Aggregation {
+    pub function: AggregateFunction,
+    /// 集計するカラム（count の場合は省略でき、その場合は行数）
+    #[serde(default)]
+    pub column: Option<String>,
+    /// 結果のキー（省略時は "sum_price" のように関数名とカラムIDから作る）
+    #[serde(default)]
+    pub alias: Option<String>,
+}
+
+impl Aggregation {
+    /// 結果のキー
+    fn key(&self) -> String {
+        match (&self.alias, &self.column) {
+            (Some(alias), _) => alias.clone(),
+            (None, Some(column)) => format!("{}_{column}", self.function.name()),
+            (None, None) => self.function.name().to_string(),
+        }
+    }
+}
+
+/// aggregate_rows の集計条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AggregateQuery {
+    /// 集計前の絞り込み条件（query_rows と同じ式）
+    pub filter: Option<Filter>,
+    /// グループ化するカラム（省略時は全体を1グループとして集計）
+    pub group_by: Vec<String>,
+    pub aggregations: Vec<Aggregation>,
+}
+
+/// 1グループの集計結果
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregateGroup {
+    /// グループ化したカラムIDとその値（multiselectはタグ1つ）
+    pub key: Map<String, Value>,
+    /// グループに含まれる行数
+    pub count: usize,
+    /// 集計のキーと結果
+    pub values: Map<String, Value>,
+}
+
+/// 集計結果
+#[derive(Debug, Clone, Default)]
+pub struct AggregateOutcome {
+    /// グループの値の昇順（未入力のグループは末尾）
+    pub groups: Vec<AggregateGroup>,
+    /// 絞り込み条件に一致した行数
+    pub total: usize,
+}
+
+/// 1グループ・1集計の途中経過
+#[derive(Debug, Default)]
+struct Accumulator {
+    count: usize,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+    distinct: HashSet<String>,
+}
+
+/// 行データを絞り込み、グループごとに集計する
+/// multiselectのカラムでグループ化した場合、行はタグごとのグループに1回ずつ数える
+///
+/// # 引数
+/// * `rows` - 保存されている行データ
+/// * `schema` - スキーマ
+/// * `query` - 集計条件
+///
+/// # 戻り値
+/// 成功時は集計結果、条件が不正な場合はエラー
+pub fn run_aggregate(
+    rows: &[Value],
+    schema: &TableSchema,
+    query: &AggregateQuery,
+) -> Result<AggregateOutcome, WorkspaceError> {
+    validate(schema, query)?;
+
+    let matched: Vec<&Value> = rows
+        .iter()
+        .filter(|row| {
+            query
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(row))
+        })
+        .collect();
+
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut groups: Vec<(Vec<Value>, usize, Vec<Accumulator>)> = Vec::new();
+    for row in &matched {
+        for key in group_keys(row, &query.group_by) {
+            let signature = Value::Array(key.clone()).to_string();
+            let position = *positions.entry(signature).or_insert_with(|| {
+                groups.push((
+                    key,
+                    0,
+                    query
+                        .aggregations
+                        .iter()
+                        .map(|_| Accumulator::default())
+                        .collect(),
+                ));
+                groups.len() - 1
+            });
+            let (_, count, accumulators) = &mut groups[position];
+            *count += 1;
+            for (aggregation, accumulator) in query.aggregations.iter().zip(accumulators) {
+                accumulate(accumulator, aggregation, row);
+            }
+        }
+    }
+
+    groups.sort_by(|(a, _, _), (b, _, _)| compare_keys(a, b));
+
+    let groups = groups
+        .into_iter()
+        .map(|(key, count, accumulators)| AggregateGroup {
+            key: query.group_by.iter().cloned().zip(key).collect(),
+            count,
+            values: query
+                .aggregations
+                .iter()
+                .zip(accumulators)
+                .map(|(aggregation, accumulator)| {
+                    (aggregation.key(), finish(aggregation, &accumulator, count))
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(AggregateOutcome {
+        groups,
+        total: matched.len(),
+    })
+}
+
+/// 集計条件のカラムと型を確認する
+fn validate(schema: &TableSchema, query: &AggregateQuery) -> Result<(), WorkspaceError> {
+    if let Some(filter) = &query.filter {
+        filter.validate(schema)?;
+    }
+    for column in &query.group_by {
+        ensure_column(schema, column)?;
+    }
+    let mut keys = HashSet::new();
+    for aggregation in &query.aggregations {
+        if !keys.insert(aggregation.key()) {
+            return Err(WorkspaceError::invalid_input(
+                "aggregate.duplicate_key",
+                tr!("aggregate.duplicate_key", key = aggregation.key()),
+            ));
+        }
+        let Some(column) = &aggregation.column else {
+            if aggregation.function != AggregateFunction::Count {
+                return Err(WorkspaceError::invalid_input(
+                    "aggregate.column_required",
+                    tr!(
+                        "aggregate.column_required",
+                        function = aggregation.function.name()
+                    ),
+                ));
+            }
+            continue;
+        };
+        ensure_column(schema, column)?;
+        if aggregation.function.is_numeric() {
+            let column_type = schema
+                .columns
+                .iter()
+                .find(|existing| &existing.id == column)
+                .map(|existing| existing.column_type);
+            if !matches!(
+                column_type,
+                Some(ColumnType::Number) | Some(ColumnType::Checkbox)
+            ) {
+                let mut error = WorkspaceError::invalid_input(
+                    "aggregate.numeric_only",
+                    tr!(
+                        "aggregate.numeric_only",
+                        function = aggregation.function.name(),
+                        column = column
+                    ),
+                );
+                error.context.column_id = Some(column.clone());
+                return Err(error);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 行が属するグループのキーを求める
+/// multiselectの値はタグごとに別のグループとし（同じタグは1回だけ）、複数カラムの場合は組み合わせとする
+fn group_keys(row: &Value, columns: &[String]) -> Vec<Vec<Value>> {
+    let mut keys = vec![Vec::new()];
+    for column in columns {
+        let values: Vec<Value> = match cell(row, column) {
+            Value::Array(items) if !items.is_empty() => {
+                let mut unique: Vec<Value> = Vec::new();
+                for item in items {
+                    if !unique.contains(item) {
+                        unique.push(item.clone());
+                    }
+                }
+                unique
+            }
+            value if is_empty_value(value) => vec![Value::Null],
+            value => vec![value.clone()],
+        };
+        keys = keys
+            .into_iter()
+            .flat_map(|key| {
+                values.iter().map(move |value| {
+                    let mut key = key.clone();
+                    key.push(value.clone());
+                    key
+                })
+            })
+            .collect();
+    }
+    keys
+}
+
+/// 1行の値を途中経過に加える
+fn accumulate(accumulator: &mut Accumulator, aggregation: &Aggregation, row: &Value) {
+    let Some(column) = &aggregation.column else {
+        accumulator.count += 1;
+        return;
+    };
+    let value = cell(row, column);
+    if is_empty_value(value) {
+        return;
+    }
+    match aggregation.function {
+        AggregateFunction::Count => accumulator.count += 1,
+        AggregateFunction::DistinctCount => match value {
+            Value::Array(items) => accumulator
+                .distinct
+                .extend(items.iter().map(Value::to_string)),
+            other => {
+                accumulator.distinct.insert(other.to_string());
+            }
+        },
+        _ => {
+            // checkbox は true を 1、false を 0 として計算する
+            let number = match value {
+                Value::Number(number) => number.as_f64(),
+                Value::Bool(flag) => Some(if *flag { 1.0 } else { 0.0 }),
+                _ => None,
+            };
+            if let Some(number) = number {
+                accumulator.count += 1;
+                accumulator.sum += number;
+                accumulator.min = Some(accumulator.min.map_or(number, |min| min.min(number)));
+                accumulator.max = Some(accumulator.max.map_or(number, |max| max.max(number)));
+            }
+        }
+    }
+}
+
+/// 途中経過から集計結果の値を作る
+fn finish(aggregation: &Aggregation, accumulator: &Accumulator, rows: usize) -> Value {
+    match aggregation.function {
+        AggregateFunction::Count if aggregation.column.is_none() => Value::from(rows),
+        AggregateFunction::Count => Value::from(accumulator.count),
+        AggregateFunction::DistinctCount => Value::from(accumulator.distinct.len()),
+        AggregateFunction::Sum => number_value(accumulator.sum),
+        AggregateFunction::Avg if accumulator.count == 0 => Value::Null,
+        AggregateFunction::Avg => number_value(accumulator.sum / accumulator.count as f64),
+        AggregateFunction::Min => accumulator.min.map_or(Value::Null, number_value),
+        AggregateFunction::Max => accumulator.max.map_or(Value::Null, number_value),
+    }
+}
+
+/// グループのキーを比較する（未入力のグループは末尾）
+fn compare_keys(a: &[Value], b: &[Value]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(left, right)| match (left.is_null(), right.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => compare_for_sort(left, right),
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// 小数を JSON の数値に変換する（整数値は整数として返す）
+pub fn number_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Value::Number(Number::from(number as i64))
+    } else {
+        Number::from_f64(number)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::ColumnDefinition;
+    use serde_json::json;
+
+    fn schema() -> TableSchema {
+        let mut schema = TableSchema::new("test", "2024-01-01T00:00:00Z");
+        schema.columns.extend([
+            ColumnDefinition::new("category", "Category", ColumnType::Text),
+            ColumnDefinition::new("price", "Price", ColumnType::Number),
+            ColumnDefinition::new("done", "Done", ColumnType::Checkbox),
+            ColumnDefinition::new("tags", "Tags", ColumnType::Multiselect),
+        ]);
+        schema
+    }
+
+    fn rows() -> Vec<Value> {
+        vec![
+            json!({"_id": "a", "category": "fruit", "price": 100, "done": true, "tags": ["x", "y"]}),
+            json!({"_id": "b", "category": "fruit", "price": 150, "done": false, "tags": ["x"]}),
+            json!({"_id": "c", "category": "veg", "price": 80, "tags": []}),
+            json!({"_id": "d", "done": true}),
+        ]
+    }
+
+    fn aggregate(query: Value) -> Result<AggregateOutcome, WorkspaceError> {
+        run_aggregate(&rows(), &schema(), &serde_json::from_value(query).unwrap())
+    }
+
+    #[test]
+    fn groups_and_aggregates_with_empty_group_last() {
+        let outcome = aggregate(json!({
+            "group_by": ["category"],
+            "aggregations": [
+                {"function": "count"},
+                {"function": "sum", "column": "price"},
+                {"function": "avg", "column": "price", "alias": "average"},
+                {"function": "min", "column": "price"},
+                {"function": "max", "column": "price"},
+                {"function": "sum", "column": "done"},
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(outcome.total, 4);
+        let keys: Vec<&Value> = outcome.groups.iter().map(|g| &g.key["category"]).collect();
+        assert_eq!(keys, [&json!("fruit"), &json!("veg"), &Value::Null]);
+
+        let fruit = &outcome.groups[0];
+        assert_eq!(fruit.count, 2);
+        assert_eq!(
+            Value::Object(fruit.values.clone()),
+            json!({"count": 2, "sum_price": 250, "average": 125, "min_price": 100,
+                   "max_price": 150, "sum_done": 1})
+        );
+        // 値のないグループの平均・最小値はnull、合計は0
+        let empty = &outcome.groups[2].values;
+        assert_eq!(
+            (&empty["average"], &empty["min_price"], &empty["sum_price"]),
+            (&Value::Null, &Value::Null, &json!(0))
+        );
+    }
+
+    #[test]
+    fn multiselect_groups_count_each_tag_once() {
+        let outcome = aggregate(json!({
+            "filter": {"op": "equals", "column": "category", "value": "fruit"},
+            "group_by": ["tags"],
+            "aggregations": [{"function": "count"}, {"function": "distinct_count", "column": "tags"}]
+        }))
+        .unwrap();
+
+        assert_eq!(outcome.total, 2);
+        let counts: Vec<(&Value, usize)> = outcome
+            .groups
+            .iter()
+            .map(|group| (&group.key["tags"], group.count))
+            .collect();
+        assert_eq!(counts, [(&json!("x"), 2), (&json!("y"), 1)]);
+        assert_eq!(outcome.groups[0].values["distinct_count_tags"], 2);
+    }
+
+    #[test]
+    fn rejects_invalid_aggregations() {
+        let cases = [
+            (
+                json!({"aggregations": [{"function": "sum"}]}),
+                "aggregate.column_required",
+            ),
+            (
+                json!({"aggregations": [{"function": "avg", "column": "category"}]}),
+                "aggregate.numeric_only",
+            ),
+            (
+                json!({"aggregations": [{"function": "count"}, {"function": "count"}]}),
+                "aggregate.duplicate_key",
+            ),
+            (
+                json!({"group_by": ["missing"], "aggregations": []}),
+                "column.not_found",
+            ),
+        ];
+        for (query, key) in cases {
+            let error = aggregate(query).unwrap_err();
+            assert_eq!(error.message_key, key);
+            assert_eq!(error.code, crate::ErrorCode::InvalidInput);
+        }
+    }
+}
@@ -0,0 +1,441 @@
+// 行・カラム単位の差分操作（apply_changes）を適用する処理
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+
+use crate::rows::{find_row, row_id_of};
+use crate::schema::{ColumnDefinition, TableSchema};
+use crate::{new_row_id, tr, ErrorCode, WorkspaceError};
+
+/// 1つの差分操作
+/// 行は_idで指定する
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ChangeOperation {
+    /// 行を挿入する（index省略時は末尾）
+    InsertRow {
+        row: Map<String, Value>,
+        #[serde(default)]
+        index: Option<usize>,
+    },
+    /// 行のセルを更新する（nullを指定したセルは削除する）
+    UpdateCells {
+        id: String,
+        cells: Map<String, Value>,
+    },
+    /// 行を削除する
+    DeleteRow { id: String },
+    /// 行を指定の位置へ移動する
+    MoveRow { id: String, index: usize },
+    /// カラムを追加する（index省略時は末尾、defaultを指定すると既存行に値を設定する）
+    AddColumn {
+        column: Box<ColumnDefinition>,
+        #[serde(default)]
+        index: Option<usize>,
+        #[serde(default)]
+        default: Option<Value>,
+    },
+    /// カラムの表示名を変更する
+    RenameColumn { id: String, name: String },
+    /// カラムを削除する（全行から値も削除する）
+    RemoveColumn { id: String },
+}
+
+impl ChangeOperation {
+    /// 操作名（エラーメッセージ用）
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeOperation::InsertRow { .. } => "insert_row",
+            ChangeOperation::UpdateCells { .. } => "update_cells",
+            ChangeOperation::DeleteRow { .. } => "delete_row",
+            ChangeOperation::MoveRow { .. } => "move_row",
+            ChangeOperation::AddColumn { .. } => "add_column",
+            ChangeOperation::RenameColumn { .. } => "rename_column",
+            ChangeOperation::RemoveColumn { .. } => "remove_column",
+        }
+    }
+}
+
+/// 差分操作を適用した結果
+#[derive(Debug, Default)]
+pub struct ChangeOutcome {
+    /// 挿入または更新された行の_id
+    pub changed_ids: Vec<String>,
+    /// 削除された行の_id
+    pub removed_ids: Vec<String>,
+    /// スキーマが変更されたか
+    pub schema_changed: bool,
+}
+
+/// 差分操作を行データとスキーマに順番に適用する
+/// 途中で失敗した場合は呼び出し側で結果を破棄すること（保存前に全操作を検証するため）
+///
+/// # 引数
+/// * `rows` - 行データの可変参照
+/// * `schema` - スキーマの可変参照
+/// * `operations` - 適用する操作
+/// * `timestamp` - 変更された行に設定する更新日時
+///
+/// # 戻り値
+/// 成功時は変更内容、失敗時は何番目の操作かを含むエラー
+pub fn apply_operations(
+    rows: &mut Vec<Value>,
+    schema: &mut TableSchema,
+    operations: &[ChangeOperation],
+    timestamp: &str,
+) -> Result<ChangeOutcome, WorkspaceError> {
+    let mut outcome = ChangeOutcome::default();
+    let mut changed = HashSet::new();
+    let mut reordered = false;
+
+    for (step, operation) in operations.iter().enumerate() {
+        let fail = |code: ErrorCode, message: String| {
+            WorkspaceError::new(
+                code,
+                "changes.failed",
+                tr!(
+                    "changes.failed",
+                    step = step + 1,
+                    operation = operation.name(),
+                    message = message
+                ),
+            )
+        };
+        match operation {
+            ChangeOperation::InsertRow { row, index } => {
+                let mut row = row.clone();
+                let id = match row.get("_id") {
+                    Some(Value::String(id)) if !id.is_empty() => id.clone(),
+                    None => {
+                        let id = new_row_id();
+                        row.insert("_id".into(), Value::String(id.clone()));
+                        id
+                    }
+                    Some(_) => {
+                        return Err(fail(ErrorCode::InvalidInput, tr!("validation.invalid_id")))
+                    }
+                };
+                if find_row(rows, &id).is_some() {
+                    return Err(fail(
+                        ErrorCode::InvalidInput,
+                        tr!("validation.duplicate_id", id = id),
+                    ));
+                }
+                row.entry("_created")
+                    .or_insert_with(|| Value::String(timestamp.to_string()));
+                let position = index.unwrap_or(rows.len()).min(rows.len());
+                rows.insert(position, Value::Object(row));
+                reordered = true;
+                changed.insert(id);
+            }
+            ChangeOperation::UpdateCells { id, cells } => {
+                let position = find_row(rows, id).ok_or_else(|| {
+                    fail(ErrorCode::NotFound, tr!("changes.row_not_found", id = id))
+                })?;
+                let object = rows[position].as_object_mut().ok_or_else(|| {
+                    fail(
+                        ErrorCode::InvalidInput,
+                        tr!("changes.row_not_object", id = id),
+                    )
+                })?;
+                for (key, value) in cells {
+                    if key.starts_with('_') {
+                        return Err(fail(
+                            ErrorCode::InvalidInput,
+                            tr!("changes.system_column_update", column = key),
+                        ));
+                    }
+                    if value.is_null() {
+                        object.remove(key);
+                    } else {
+                        object.insert(key.clone(), value.clone());
+                    }
+                }
+                changed.insert(id.clone());
+            }
+            ChangeOperation::DeleteRow { id } => {
+                let position = find_row(rows, id).ok_or_else(|| {
+                    fail(ErrorCode::NotFound, tr!("changes.row_not_found", id = id))
+                })?;
+                rows.remove(position);
+                changed.remove(id);
+                outcome.removed_ids.push(id.clone());
+                reordered = true;
+            }
+            ChangeOperation::MoveRow { id, index } => {
+                let position = find_row(rows, id).ok_or_else(|| {
+                    fail(ErrorCode::NotFound, tr!("changes.row_not_found", id = id))
+                })?;
+                let row = rows.remove(position);
+                let target = (*index).min(rows.len());
+                rows.insert(target, row);
+                reordered = true;
+                changed.insert(id.clone());
+            }
+            ChangeOperation::AddColumn {
+                column,
+                index,
+                default,
+            } => {
+                if schema
+                    .columns
+                    .iter()
+                    .any(|existing| existing.id == column.id)
+                {
+                    return Err(fail(
+                        ErrorCode::InvalidInput,
+                        tr!("schema.column_id_duplicate", id = column.id),
+                    ));
+                }
+                let position = index
+                    .unwrap_or(schema.columns.len())
+                    .min(schema.columns.len());
+                schema.columns.insert(position, column.as_ref().clone());
+                if let Some(default) = default.as_ref().filter(|value| !value.is_null()) {
+                    for row in rows.iter_mut() {
+                        if let Value::Object(object) = row {
+                            if !object.contains_key(&column.id) {
+                                object.insert(column.id.clone(), default.clone());
+                                if let Some(id) = row_id_of(object) {
+                                    changed.insert(id.to_string());
+                                }
+                            }
+                        }
+                    }
+                }
+                outcome.schema_changed = true;
+            }
+            ChangeOperation::RenameColumn { id, name } => {
+                let column = schema
+                    .columns
+                    .iter_mut()
+                    .find(|column| &column.id == id)
+                    .ok_or_else(|| {
+                        fail(ErrorCode::NotFound, tr!("column.not_found", column = id))
+                    })?;
+                column.name = name.clone();
+                outcome.schema_changed = true;
+            }
+            ChangeOperation::RemoveColumn { id } => {
+                let position = schema
+                    .columns
+                    .iter()
+                    .position(|column| &column.id == id)
+                    .ok_or_else(|| {
+                        fail(ErrorCode::NotFound, tr!("column.not_found", column = id))
+                    })?;
+                if schema.columns[position].system {
+                    return Err(fail(
+                        ErrorCode::InvalidInput,
+                        tr!("changes.system_column_delete", column = id),
+                    ));
+                }
+                schema.columns.remove(position);
+                for row in rows.iter_mut() {
+                    if let Value::Object(object) = row {
+                        if object.remove(id).is_some() {
+                            if let Some(row_id) = row_id_of(object) {
+                                changed.insert(row_id.to_string());
+                            }
+                        }
+                    }
+                }
+                outcome.schema_changed = true;
+            }
+        }
+    }
+
+    // 並び順が変わった場合は_orderを振り直す
+    if reordered {
+        for (index, row) in rows.iter_mut().enumerate() {
+            if let Value::Object(object) = row {
+                object.insert("_order".into(), Value::Number(Number::from(index as u64)));
+            }
+        }
+    }
+
+    // 変更された行の更新日時を設定する（操作順を保つ）
+    for row in rows.iter_mut() {
+        if let Value::Object(object) = row {
+            if let Some(id) = row_id_of(object)
+                .filter(|id| changed.contains(*id))
+                .map(str::to_string)
+            {
+                object.insert("_updated".into(), Value::String(timestamp.to_string()));
+                outcome.changed_ids.push(id);
+            }
+        }
+    }
+
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rows::row_id;
+    use crate::schema::ColumnType;
+    use serde_json::json;
+
+    const NOW: &str = "2024-01-02T00:00:00Z";
+
+    /// 名前のカラムを持つスキーマと2行のデータ
+    fn table() -> (Vec<Value>, TableSchema) {
+        let mut schema = TableSchema::new("test", "2024-01-01T00:00:00Z");
+        schema
+            .columns
+            .push(ColumnDefinition::new("name", "Name", ColumnType::Text));
+        let rows = vec![
+            json!({"_id": "a", "_order": 0, "_updated": "old", "name": "A"}),
+            json!({"_id": "b", "_order": 1, "_updated": "old", "name": "B"}),
+        ];
+        (rows, schema)
+    }
+
+    /// JSONで書いた操作を読み込む
+    fn operations(value: Value) -> Vec<ChangeOperation> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn ids(rows: &[Value]) -> Vec<&str> {
+        rows.iter().filter_map(row_id).collect()
+    }
+
+    #[test]
+    fn insert_rows() {
+        let (mut rows, mut schema) = table();
+        let outcome = apply_operations(
+            &mut rows,
+            &mut schema,
+            &operations(json!([
+                {"op": "insert_row", "row": {"_id": "c", "name": "C"}, "index": 0},
+                {"op": "insert_row", "row": {"name": "D"}},
+            ])),
+            NOW,
+        )
+        .unwrap();
+
+        assert_eq!(ids(&rows)[..3], ["c", "a", "b"]);
+        let generated = ids(&rows)[3].to_string();
+        assert_eq!(outcome.changed_ids, vec!["c".to_string(), generated]);
+        assert_eq!(rows[0]["_created"], json!(NOW));
+        // 並び順が変わったため_orderを振り直す
+        let orders: Vec<&Value> = rows.iter().map(|row| &row["_order"]).collect();
+        assert_eq!(orders, vec![&json!(0), &json!(1), &json!(2), &json!(3)]);
+    }
+
+    #[test]
+    fn update_cells() {
+        let (mut rows, mut schema) = table();
+        let outcome = apply_operations(
+            &mut rows,
+            &mut schema,
+            &operations(json!([
+                {"op": "update_cells", "id": "b", "cells": {"name": null, "memo": "x"}},
+            ])),
+            NOW,
+        )
+        .unwrap();
+
+        assert_eq!(outcome.changed_ids, vec!["b".to_string()]);
+        assert!(outcome.removed_ids.is_empty());
+        assert_eq!(
+            rows[1],
+            json!({"_id": "b", "_order": 1, "_updated": NOW, "memo": "x"})
+        );
+        // 変更のない行の更新日時はそのまま
+        assert_eq!(rows[0]["_updated"], json!("old"));
+    }
+
+    #[test]
+    fn delete_rows() {
+        let (mut rows, mut schema) = table();
+        let outcome = apply_operations(
+            &mut rows,
+            &mut schema,
+            &operations(json!([
+                {"op": "update_cells", "id": "a", "cells": {"name": "A2"}},
+                {"op": "delete_row", "id": "a"},
+            ])),
+            NOW,
+        )
+        .unwrap();
+
+        assert_eq!(ids(&rows), vec!["b"]);
+        assert_eq!(outcome.removed_ids, vec!["a".to_string()]);
+        // 削除した行は変更された行に含めない
+        assert!(outcome.changed_ids.is_empty());
+        assert_eq!(rows[0]["_order"], json!(0));
+    }
+
+    #[test]
+    fn unknown_row_ids_are_rejected() {
+        for operation in [
+            json!({"op": "update_cells", "id": "x", "cells": {"name": "X"}}),
+            json!({"op": "delete_row", "id": "x"}),
+            json!({"op": "move_row", "id": "x", "index": 0}),
+        ] {
+            let (mut rows, mut schema) = table();
+            let error = apply_operations(
+                &mut rows,
+                &mut schema,
+                &operations(json!([
+                    {"op": "update_cells", "id": "a", "cells": {"name": "A2"}},
+                    operation,
+                ])),
+                NOW,
+            )
+            .unwrap_err();
+            assert_eq!(error.code, ErrorCode::NotFound);
+            assert_eq!(error.message_key, "changes.failed");
+        }
+    }
+
+    #[test]
+    fn invalid_rows_are_rejected() {
+        for operation in [
+            json!({"op": "insert_row", "row": {"_id": "a"}}),
+            json!({"op": "insert_row", "row": {"_id": 1}}),
+            json!({"op": "update_cells", "id": "a", "cells": {"_created": "now"}}),
+            json!({"op": "remove_column", "id": "_id"}),
+        ] {
+            let (mut rows, mut schema) = table();
+            let error =
+                apply_operations(&mut rows, &mut schema, &operations(json!([operation])), NOW)
+                    .unwrap_err();
+            assert_eq!(error.code, ErrorCode::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn column_operations_change_the_schema_and_rows() {
+        let (mut rows, mut schema) = table();
+        let outcome = apply_operations(
+            &mut rows,
+            &mut schema,
+            &operations(json!([
+                {
+                    "op": "add_column",
+                    "column": {"id": "price", "name": "Price", "type": "number"},
+                    "default": 0
+                },
+                {"op": "rename_column", "id": "price", "name": "Cost"},
+                {"op": "remove_column", "id": "name"},
+            ])),
+            NOW,
+        )
+        .unwrap();
+
+        assert!(outcome.schema_changed);
+        assert_eq!(outcome.changed_ids, vec!["a".to_string(), "b".to_string()]);
+        let columns: Vec<(&str, &str)> = schema
+            .columns
+            .iter()
+            .map(|column| (column.id.as_str(), column.name.as_str()))
+            .collect();
+        assert_eq!(columns, vec![("_id", "ID"), ("price", "Cost")]);
+        assert_eq!(rows[0]["price"], json!(0));
+        assert!(rows[0].get("name").is_none());
+    }
+}
@@ -0,0 +1,339 @@
+// CSV/TSV ファイルの読み込み・書き出し処理
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use encoding_rs::{SHIFT_JIS, UTF_8};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::schema::TableSchema;
+use crate::tabular::{build_from_text, cell_to_text, export_columns, ImportedTable};
+use crate::{tr, WorkspaceError};
+
+/// UTF-8 の BOM（Excel が文字コードを判別するために使用）
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// 入力ファイルの文字コード
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    ShiftJis,
+}
+
+/// CSV/TSV 取り込みのオプション
+#[derive(Debug, Clone, Deserialize)]
+pub struct DelimitedImportOptions {
+    /// 区切り文字（省略時は拡張子から判定: .tsv/.tab はタブ、それ以外はカンマ）
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// 1行目を見出しとして扱うか
+    #[serde(default = "default_has_header")]
+    pub has_header: bool,
+    /// 文字コード
+    #[serde(default)]
+    pub encoding: TextEncoding,
+}
+
+impl Default for DelimitedImportOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            has_header: default_has_header(),
+            encoding: TextEncoding::default(),
+        }
+    }
+}
+
+/// CSV/TSV 書き出しのオプション
+#[derive(Debug, Clone, Deserialize)]
+pub struct DelimitedExportOptions {
+    /// 区切り文字（省略時は拡張子から判定: .tsv/.tab はタブ、それ以外はカンマ）
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// 非表示カラムを含めるか
+    #[serde(default)]
+    pub include_hidden: bool,
+    /// システム列（_id等）を含めるか
+    #[serde(default)]
+    pub include_system: bool,
+    /// multiselect の値を連結する区切り文字列
+    #[serde(default = "default_list_separator")]
+    pub list_separator: String,
+    /// 先頭に UTF-8 の BOM を付けるか
+    #[serde(default)]
+    pub bom: bool,
+}
+
+impl Default for DelimitedExportOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            include_hidden: false,
+            include_system: false,
+            list_separator: default_list_separator(),
+            bom: false,
+        }
+    }
+}
+
+/// CSV/TSV ファイルを読み込み、カラム型を推定したテーブルに変換する
+///
+/// # 引数
+/// * `path` - 入力ファイルのパス
+/// * `options` - 取り込みオプション
+///
+/// # 戻り値
+/// 成功時は取り込んだテーブル、失敗時はエラー
+pub fn read_delimited_file(
+    path: &Path,
+    options: &DelimitedImportOptions,
+) -> Result<ImportedTable, WorkspaceError> {
+    let bytes = fs::read(path).map_err(|err| WorkspaceError::io(err, path))?;
+    let text = decode(&bytes, options.encoding).map_err(|err| err.with_path(path))?;
+    let delimiter = delimiter_byte(options.delimiter.unwrap_or_else(|| default_delimiter(path)))?;
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let mut records = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record.map_err(|err| {
+            let mut error = WorkspaceError::invalid_input(
+                "delimited.record",
+                tr!("delimited.record", row = index + 1, error = err),
+            )
+            .with_path(path);
+            error.context.line = Some(index + 1);
+            error.with_source(&err)
+        })?;
+        records.push(record.iter().map(str::to_string).collect::<Vec<_>>());
+    }
+
+    let headers = if options.has_header && !records.is_empty() {
+        Some(records.remove(0))
+    } else {
+        None
+    };
+
+    Ok(build_from_text(headers, records))
+}
+
+/// テーブルを CSV/TSV ファイルに書き出す
+/// 見出しにはカラムの表示名を使い、列はスキーマの並び順で出力する
+///
+/// # 引数
+/// * `path` - 出力先のパス
+/// * `schema` - スキーマ
+/// * `rows` - 行データ
+/// * `options` - 書き出しオプション
+///
+/// # 戻り値
+/// 成功時は書き出した行数、失敗時はエラー
+pub fn write_delimited_file(
+    path: &Path,
+    schema: &TableSchema,
+    rows: &[Value],
+    options: &DelimitedExportOptions,
+) -> Result<usize, WorkspaceError> {
+    let delimiter = delimiter_byte(options.delimiter.unwrap_or_else(|| default_delimiter(path)))?;
+    let columns = export_columns(schema, options.include_hidden, options.include_system);
+
+    let mut buffer = Vec::new();
+    if options.bom {
+        buffer.extend_from_slice(UTF8_BOM);
+    }
+
+    {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(&mut buffer);
+        let write_error = |err: csv::Error| WorkspaceError::io(err.into(), path);
+        writer
+            .write_record(columns.iter().map(|column| column.name.as_str()))
+            .map_err(write_error)?;
+        for row in rows {
+            writer
+                .write_record(columns.iter().map(|column| {
+                    cell_to_text(
+                        row.get(&column.id).unwrap_or(&Value::Null),
+                        &options.list_separator,
+                    )
+                }))
+                .map_err(write_error)?;
+        }
+        writer
+            .flush()
+            .map_err(|err| WorkspaceError::io(err, path))?;
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| WorkspaceError::io(err, parent))?;
+    }
+    let mut file = File::create(path).map_err(|err| WorkspaceError::io(err, path))?;
+    file.write_all(&buffer)
+        .and_then(|()| file.flush())
+        .map_err(|err| WorkspaceError::io(err, path))?;
+
+    Ok(rows.len())
+}
+
+/// 拡張子から既定の区切り文字を決める
+pub fn default_delimiter(path: &Path) -> char {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase)
+        .as_deref()
+    {
+        Some("tsv") | Some("tab") => '\t',
+        _ => ',',
+    }
+}
+
+/// 区切り文字を1バイトの値に変換する
+pub fn delimiter_byte(delimiter: char) -> Result<u8, WorkspaceError> {
+    if delimiter.is_ascii() && delimiter != '"' && delimiter != '\n' && delimiter != '\r' {
+        Ok(delimiter as u8)
+    } else {
+        Err(WorkspaceError::invalid_input(
+            "delimited.invalid_delimiter",
+            tr!(
+                "delimited.invalid_delimiter",
+                delimiter = format!("{delimiter:?}")
+            ),
+        ))
+    }
+}
+
+/// バイト列を指定の文字コードで文字列に変換する
+fn decode(bytes: &[u8], encoding: TextEncoding) -> Result<String, WorkspaceError> {
+    let encoding = match encoding {
+        TextEncoding::Utf8 => UTF_8,
+        TextEncoding::ShiftJis => SHIFT_JIS,
+    };
+    // decode は BOM を検出して取り除く
+    let (text, _, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        return Err(WorkspaceError::invalid_input(
+            "delimited.decode",
+            tr!("delimited.decode", encoding = encoding.name()),
+        ));
+    }
+    Ok(text.into_owned())
+}
+
+fn default_has_header() -> bool {
+    true
+}
+
+fn default_list_separator() -> String {
+    ", ".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{ColumnDefinition, ColumnType};
+    use crate::ErrorCode;
+    use serde_json::json;
+
+    fn schema() -> TableSchema {
+        let mut schema = TableSchema::new("test", "2024-01-01T00:00:00Z");
+        schema.columns.extend([
+            ColumnDefinition::new("name", "Name", ColumnType::Text),
+            ColumnDefinition::new("qty", "Qty", ColumnType::Number),
+            ColumnDefinition::new("done", "Done", ColumnType::Checkbox),
+            ColumnDefinition::new("code", "Code", ColumnType::Text),
+        ]);
+        schema
+    }
+
+    fn rows() -> Vec<Value> {
+        vec![
+            json!({"_id": "a", "name": "カンマ, \"引用符\"\n改行", "qty": 1.5, "done": true, "code": "007"}),
+            json!({"_id": "b", "name": "Plain", "qty": -2, "done": false, "code": "A-1"}),
+            json!({"_id": "c", "name": null, "qty": null, "done": null, "code": null}),
+        ]
+    }
+
+    /// 書き出した行から比較しないシステム列を除く
+    fn without_system(rows: &[Value]) -> Vec<Value> {
+        rows.iter()
+            .map(|row| {
+                let mut row = row.clone();
+                row.as_object_mut().unwrap().remove("_id");
+                row
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_csv_and_tsv() {
+        let dir = tempfile::tempdir().unwrap();
+        for (file_name, bom) in [("out.csv", true), ("out.tsv", false)] {
+            let path = dir.path().join(file_name);
+            let options = DelimitedExportOptions {
+                bom,
+                ..DelimitedExportOptions::default()
+            };
+            assert_eq!(
+                write_delimited_file(&path, &schema(), &rows(), &options).unwrap(),
+                3
+            );
+            let bytes = fs::read(&path).unwrap();
+            assert_eq!(bytes.starts_with(UTF8_BOM), bom);
+
+            let imported = read_delimited_file(&path, &DelimitedImportOptions::default()).unwrap();
+            let columns: Vec<(&str, &str, ColumnType)> = imported
+                .columns
+                .iter()
+                .map(|column| (column.id.as_str(), column.name.as_str(), column.column_type))
+                .collect();
+            assert_eq!(
+                columns,
+                [
+                    ("name", "Name", ColumnType::Text),
+                    ("qty", "Qty", ColumnType::Number),
+                    ("done", "Done", ColumnType::Checkbox),
+                    ("code", "Code", ColumnType::Text),
+                ]
+            );
+            // 空のセルは、文字列は空文字列・チェックボックスはfalseとして取り込まれる
+            let mut expected = without_system(&rows());
+            expected[2] = json!({"name": "", "qty": null, "done": false, "code": ""});
+            assert_eq!(imported.rows, expected, "{file_name}");
+        }
+    }
+
+    #[test]
+    fn reports_missing_files_and_bad_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.csv");
+        let error = read_delimited_file(&missing, &DelimitedImportOptions::default()).unwrap_err();
+        assert_eq!(error.code, ErrorCode::NotFound);
+        assert_eq!(
+            error.context.path,
+            Some(missing.to_string_lossy().into_owned())
+        );
+
+        let path = dir.path().join("invalid.csv");
+        fs::write(&path, b"name\n\xff\xfe\n").unwrap();
+        let error = read_delimited_file(&path, &DelimitedImportOptions::default()).unwrap_err();
+        assert_eq!(error.message_key, "delimited.decode");
+
+        let path = dir.path().join("valid.csv");
+        fs::write(&path, "name\nA\n").unwrap();
+        let options = DelimitedImportOptions {
+            delimiter: Some('"'),
+            ..DelimitedImportOptions::default()
+        };
+        let error = read_delimited_file(&path, &options).unwrap_err();
+        assert_eq!(error.message_key, "delimited.invalid_delimiter");
+    }
+}
@@ -0,0 +1,307 @@
+// 2つの時点のテーブル（スナップショットや現在のファイル）を_idで比較する処理
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::rows::{changed_cells, row_id};
+use crate::schema::{ColumnDefinition, ColumnType, TableSchema};
+
+/// 比較結果（JSONとして書き出せる）
+#[derive(Debug, Clone, Serialize)]
+pub struct TableDiff {
+    /// 比較元の名前（スナップショット名または "current"）
+    pub from: String,
+    /// 比較先の名前
+    pub to: String,
+    pub summary: DiffSummary,
+    /// 比較先にだけある行
+    pub added_rows: Vec<Value>,
+    /// 比較元にだけある行
+    pub removed_rows: Vec<Value>,
+    /// 両方にある行で値が異なるセル
+    pub changed_cells: Vec<CellDiff>,
+    pub schema: SchemaDiff,
+}
+
+/// 比較結果の件数
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiffSummary {
+    pub added_rows: usize,
+    pub removed_rows: usize,
+    /// 値が異なるセルを含む行の数
+    pub changed_rows: usize,
+    pub changed_cells: usize,
+}
+
+/// 値が異なるセル
+#[derive(Debug, Clone, Serialize)]
+pub struct CellDiff {
+    pub row_id: String,
+    pub column_id: String,
+    /// 比較元の値（存在しない場合はnull）
+    pub before: Value,
+    /// 比較先の値（存在しない場合はnull）
+    pub after: Value,
+}
+
+/// スキーマの違い（カラムはIDで対応付ける）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SchemaDiff {
+    /// テーブル名が変わった場合の変更前後の名前
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table_renamed: Option<TableRename>,
+    pub added_columns: Vec<ColumnDefinition>,
+    pub removed_columns: Vec<ColumnDefinition>,
+    pub renamed_columns: Vec<ColumnRename>,
+    pub retyped_columns: Vec<ColumnRetype>,
+}
+
+/// テーブル名の変更
+#[derive(Debug, Clone, Serialize)]
+pub struct TableRename {
+    pub before: String,
+    pub after: String,
+}
+
+/// 表示名が変わったカラム
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnRename {
+    pub column_id: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// 型が変わったカラム
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnRetype {
+    pub column_id: String,
+    pub before: ColumnType,
+    pub after: ColumnType,
+}
+
+/// 2つの時点の行データとスキーマを比較する
+/// 行は_idで対応付け、_idのない行は比較しない
+///
+/// # 引数
+/// * `from` - 比較元の名前と行データ・スキーマ
+/// * `to` - 比較先の名前と行データ・スキーマ
+///
+/// # 戻り値
+/// 比較結果
+pub fn diff_tables(
+    (from, from_rows, from_schema): (&str, &[Value], &TableSchema),
+    (to, to_rows, to_schema): (&str, &[Value], &TableSchema),
+) -> TableDiff {
+    let from_index: HashMap<&str, &Value> = from_rows
+        .iter()
+        .filter_map(|row| row_id(row).map(|id| (id, row)))
+        .collect();
+    let to_ids: HashSet<&str> = to_rows.iter().filter_map(row_id).collect();
+
+    let mut added_rows = Vec::new();
+    let mut changed_cells = Vec::new();
+    let mut changed_rows = 0;
+    for row in to_rows {
+        let Some(id) = row_id(row) else {
+            continue;
+        };
+        match from_index.get(id) {
+            None => added_rows.push(row.clone()),
+            Some(previous) => {
+                let cells = diff_cells(id, previous, row);
+                if !cells.is_empty() {
+                    changed_rows += 1;
+                    changed_cells.extend(cells);
+                }
+            }
+        }
+    }
+    let removed_rows: Vec<Value> = from_rows
+        .iter()
+        .filter(|row| row_id(row).is_some_and(|id| !to_ids.contains(id)))
+        .cloned()
+        .collect();
+
+    TableDiff {
+        from: from.to_string(),
+        to: to.to_string(),
+        summary: DiffSummary {
+            added_rows: added_rows.len(),
+            removed_rows: removed_rows.len(),
+            changed_rows,
+            changed_cells: changed_cells.len(),
+        },
+        added_rows,
+        removed_rows,
+        changed_cells,
+        schema: diff_schema(from_schema, to_schema),
+    }
+}
+
+/// スキーマのカラムをIDで対応付けて比較する
+fn diff_schema(from: &TableSchema, to: &TableSchema) -> SchemaDiff {
+    let from_columns: HashMap<&str, &ColumnDefinition> = from
+        .columns
+        .iter()
+        .map(|column| (column.id.as_str(), column))
+        .collect();
+    let to_ids: HashSet<&str> = to.columns.iter().map(|column| column.id.as_str()).collect();
+
+    let mut diff = SchemaDiff {
+        table_renamed: (from.table_name != to.table_name).then(|| TableRename {
+            before: from.table_name.clone(),
+            after: to.table_name.clone(),
+        }),
+        ..SchemaDiff::default()
+    };
+    for column in &to.columns {
+        let Some(previous) = from_columns.get(column.id.as_str()) else {
+            diff.added_columns.push(column.clone());
+            continue;
+        };
+        if previous.name != column.name {
+            diff.renamed_columns.push(ColumnRename {
+                column_id: column.id.clone(),
+                before: previous.name.clone(),
+                after: column.name.clone(),
+            });
+        }
+        if previous.column_type != column.column_type {
+            diff.retyped_columns.push(ColumnRetype {
+                column_id: column.id.clone(),
+                before: previous.column_type,
+                after: column.column_type,
+            });
+        }
+    }
+    diff.removed_columns = from
+        .columns
+        .iter()
+        .filter(|column| !to_ids.contains(column.id.as_str()))
+        .cloned()
+        .collect();
+    diff
+}
+
+/// 2つの行で値が異なるセルを求める（nullはキーがないものとして扱う）
+fn diff_cells(id: &str, before: &Value, after: &Value) -> Vec<CellDiff> {
+    changed_cells(before, after)
+        .into_iter()
+        .map(|(column_id, before, after)| CellDiff {
+            row_id: id.to_string(),
+            column_id,
+            before,
+            after,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// _id列と名前・価格のカラムを持つスキーマ
+    fn schema() -> TableSchema {
+        let mut schema = TableSchema::new("items", "2024-01-01T00:00:00Z");
+        schema
+            .columns
+            .push(ColumnDefinition::new("name", "Name", ColumnType::Text));
+        schema
+            .columns
+            .push(ColumnDefinition::new("price", "Price", ColumnType::Number));
+        schema
+    }
+
+    #[test]
+    fn rows_are_matched_by_id() {
+        let schema = schema();
+        let before = vec![
+            json!({"_id": "a", "_order": 0, "name": "A", "price": 1}),
+            json!({"_id": "b", "_order": 1, "name": "B", "price": 2}),
+            json!({"_id": "c", "_order": 2, "name": "C"}),
+        ];
+        let after = vec![
+            json!({"_id": "c", "_order": 0, "name": "C", "price": null}),
+            json!({"_id": "a", "_order": 1, "name": "A2", "price": 10}),
+            json!({"_id": "d", "_order": 2, "name": "D"}),
+            json!({"name": "no id"}),
+        ];
+        let diff = diff_tables(("before", &before, &schema), ("current", &after, &schema));
+
+        assert_eq!(
+            (diff.from.as_str(), diff.to.as_str()),
+            ("before", "current")
+        );
+        assert_eq!(diff.added_rows, vec![after[2].clone()]);
+        assert_eq!(diff.removed_rows, vec![before[1].clone()]);
+        // 並び順（_order）とnullのセルは変更として扱わない
+        let cells: Vec<(&str, &str, &Value, &Value)> = diff
+            .changed_cells
+            .iter()
+            .map(|cell| {
+                (
+                    cell.row_id.as_str(),
+                    cell.column_id.as_str(),
+                    &cell.before,
+                    &cell.after,
+                )
+            })
+            .collect();
+        assert_eq!(
+            cells,
+            vec![
+                ("a", "name", &json!("A"), &json!("A2")),
+                ("a", "price", &json!(1), &json!(10)),
+            ]
+        );
+        let summary = &diff.summary;
+        assert_eq!(
+            (
+                summary.added_rows,
+                summary.removed_rows,
+                summary.changed_rows,
+                summary.changed_cells
+            ),
+            (1, 1, 1, 2)
+        );
+        assert!(diff.schema.added_columns.is_empty());
+    }
+
+    #[test]
+    fn schema_changes_are_matched_by_column_id() {
+        let before = schema();
+        let mut after = schema();
+        after.table_name = "products".into();
+        after.columns.retain(|column| column.id != "price");
+        after.columns[1].name = "Title".into();
+        after.columns[1].column_type = ColumnType::Multiselect;
+        after
+            .columns
+            .push(ColumnDefinition::new("done", "Done", ColumnType::Checkbox));
+
+        let diff = diff_tables(("before", &[], &before), ("after", &[], &after)).schema;
+        let renamed = diff.table_renamed.unwrap();
+        assert_eq!(
+            (renamed.before.as_str(), renamed.after.as_str()),
+            ("items", "products")
+        );
+        assert_eq!(diff.added_columns[0].id, "done");
+        assert_eq!(diff.removed_columns[0].id, "price");
+        assert_eq!(
+            (
+                diff.renamed_columns[0].column_id.as_str(),
+                diff.renamed_columns[0].after.as_str()
+            ),
+            ("name", "Title")
+        );
+        assert_eq!(
+            (
+                diff.retyped_columns[0].before,
+                diff.retyped_columns[0].after
+            ),
+            (ColumnType::Text, ColumnType::Multiselect)
+        );
+    }
+}
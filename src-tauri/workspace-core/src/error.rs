@@ -0,0 +1,259 @@
+// 操作の失敗を表すエラー型（コマンドの戻り値としてそのままフロントエンドに送信できる）
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::validation::{summarise_errors, IssueSeverity, RowIssue};
+use crate::{tr, TablePayload};
+
+/// エラーの種類（フロントエンドが処理を分けるための、変わらないコード）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// ファイル・バックアップ・スナップショット等が見つからない
+    NotFound,
+    /// 同名のファイル等が既に存在する
+    AlreadyExists,
+    /// ファイルの読み書きに失敗した
+    Io,
+    /// JSONとして読み込めない
+    InvalidJson,
+    /// スキーマが不正
+    InvalidSchema,
+    /// 行データが不正（型の不一致や参照先のない_id等）
+    InvalidRows,
+    /// 引数や条件が不正
+    InvalidInput,
+    /// 読み込み後に別のウィンドウやプロセスがファイルを更新していた
+    Conflict,
+    /// 削除時の動作がrestrictのリレーション列から参照されているため削除できない
+    Restricted,
+    /// 指定されたワークスペースが開かれていない
+    WorkspaceNotLoaded,
+    /// ローカルAPIのトークンがない、または一致しない
+    Unauthorized,
+    /// SQLiteの操作に失敗した
+    Database,
+    /// その他の失敗
+    Failed,
+}
+
+/// エラーが起きた場所
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ErrorContext {
+    /// 対象のファイル
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// JSONの読み込みに失敗した行（1始まり）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// JSONの読み込みに失敗した列（1始まり）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// 問題のある行の_id（restrictの場合は参照している行）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row_id: Option<String>,
+    /// 問題のあるカラムID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column_id: Option<String>,
+}
+
+/// 操作の失敗
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceError {
+    pub code: ErrorCode,
+    /// メッセージカタログのキー（同じコードでも原因ごとに異なる）
+    pub message_key: &'static str,
+    /// 利用者向けの説明（作成時の言語）
+    pub message: String,
+    /// エラーが起きた場所（Resultを小さく保つためにBoxで持つ）
+    pub context: Box<ErrorContext>,
+    /// 原因となったエラーのメッセージ（直接の原因から順）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    /// 競合時に現在保存されている内容
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<Box<TablePayload>>,
+    /// 個々の問題（スキーマの検証で複数の問題が見つかった場合）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<WorkspaceError>,
+}
+
+impl WorkspaceError {
+    /// エラーを作成する
+    ///
+    /// # 引数
+    /// * `code` - エラーの種類
+    /// * `message_key` - メッセージカタログのキー
+    /// * `message` - 利用者向けの説明（通常は`tr!`でカタログから作る）
+    pub fn new(code: ErrorCode, message_key: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message_key,
+            message: message.into(),
+            context: Box::default(),
+            sources: Vec::new(),
+            snapshot: None,
+            details: Vec::new(),
+        }
+    }
+
+    /// 対象のファイルを設定する
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.context.path = Some(path.to_string_lossy().into_owned());
+        self
+    }
+
+    /// 問題のある行の_idを設定する
+    pub fn with_row(mut self, row_id: impl Into<String>) -> Self {
+        self.context.row_id = Some(row_id.into());
+        self
+    }
+
+    /// 問題のあるカラムIDを設定する
+    pub fn with_column(mut self, column_id: impl Into<String>) -> Self {
+        self.context.column_id = Some(column_id.into());
+        self
+    }
+
+    /// 原因となったエラーと、その原因をたどったメッセージを追加する
+    pub fn with_source(mut self, source: &(dyn StdError + 'static)) -> Self {
+        let mut current = Some(source);
+        while let Some(error) = current {
+            self.sources.push(error.to_string());
+            current = error.source();
+        }
+        self
+    }
+
+    /// ファイル操作のエラーから作成する
+    ///
+    /// # 引数
+    /// * `error` - ファイル操作のエラー
+    /// * `path` - 対象のファイル
+    pub fn io(error: io::Error, path: &Path) -> Self {
+        let (code, message_key) = match error.kind() {
+            io::ErrorKind::NotFound => (ErrorCode::NotFound, "error.file_not_found"),
+            io::ErrorKind::PermissionDenied => (ErrorCode::Io, "error.permission_denied"),
+            _ => (ErrorCode::Io, "error.io"),
+        };
+        Self::new(code, message_key, tr!(message_key, path = path.display()))
+            .with_path(path)
+            .with_source(&error)
+    }
+
+    /// JSONの読み込み・書き出しのエラーから作成する（読み込みの場合は行・列番号を含む）
+    ///
+    /// # 引数
+    /// * `error` - serde_json のエラー
+    /// * `path` - 対象のファイル
+    pub fn json(error: serde_json::Error, path: &Path) -> Self {
+        if error.line() == 0 {
+            return Self::new(
+                ErrorCode::InvalidJson,
+                "error.invalid_json",
+                tr!("error.invalid_json", path = path.display()),
+            )
+            .with_path(path)
+            .with_source(&error);
+        }
+        let message = tr!(
+            "error.invalid_json_at",
+            path = path.display(),
+            line = error.line(),
+            column = error.column()
+        );
+        let mut result =
+            Self::new(ErrorCode::InvalidJson, "error.invalid_json_at", message).with_path(path);
+        result.context.line = Some(error.line());
+        result.context.column = Some(error.column());
+        result.with_source(&error)
+    }
+
+    /// SQLiteのエラーから作成する
+    ///
+    /// # 引数
+    /// * `error` - rusqlite のエラー
+    /// * `path` - データベースファイル
+    pub fn database(error: rusqlite::Error, path: &Path) -> Self {
+        Self::new(
+            ErrorCode::Database,
+            "error.database",
+            tr!("error.database", path = path.display()),
+        )
+        .with_path(path)
+        .with_source(&error)
+    }
+
+    /// スキーマの検証で見つかった問題をまとめたエラーを作成する（最初の問題のカラムを含む）
+    ///
+    /// # 引数
+    /// * `message_key` - まとめたメッセージのキー（`{problems}`に個々のメッセージを埋め込む）
+    /// * `problems` - 個々の問題
+    pub fn invalid_schema(message_key: &'static str, problems: Vec<WorkspaceError>) -> Self {
+        let messages: Vec<&str> = problems
+            .iter()
+            .map(|problem| problem.message.as_str())
+            .collect();
+        let mut result = Self::new(
+            ErrorCode::InvalidSchema,
+            message_key,
+            tr!(message_key, problems = messages.join("; ")),
+        );
+        result.context.column_id = problems
+            .iter()
+            .find_map(|problem| problem.context.column_id.clone());
+        result.details = problems;
+        result
+    }
+
+    /// 引数や条件が不正な場合のエラーを作成する
+    pub fn invalid_input(message_key: &'static str, message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message_key, message)
+    }
+
+    /// 行データの検証エラーから作成する（最初のエラーの行とカラムを含む）
+    ///
+    /// # 引数
+    /// * `issues` - 検証結果
+    pub fn invalid_rows(issues: &[RowIssue]) -> Self {
+        let mut result = Self::new(
+            ErrorCode::InvalidRows,
+            "validation.invalid_rows",
+            summarise_errors(issues, 5),
+        );
+        if let Some(first) = issues
+            .iter()
+            .find(|issue| issue.severity == IssueSeverity::Error)
+        {
+            result.context.row_id = first.row_id.clone();
+            result.context.column_id = first.column_id.clone();
+        }
+        result
+    }
+
+    /// 読み込み後に他で更新されていた場合のエラーを作成する
+    ///
+    /// # 引数
+    /// * `snapshot` - 現在保存されている内容
+    pub fn conflict(snapshot: TablePayload) -> Self {
+        let mut result = Self::new(ErrorCode::Conflict, "error.conflict", tr!("error.conflict"));
+        result.snapshot = Some(Box::new(snapshot));
+        result
+    }
+}
+
+impl fmt::Display for WorkspaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        for source in &self.sources {
+            write!(f, ": {source}")?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for WorkspaceError {}
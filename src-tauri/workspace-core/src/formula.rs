@@ -0,0 +1,1582 @@
+// 計算列（formula）の式の解析・型検査・循環参照の検出・評価
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write as _};
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc};
+use serde_json::Value;
+
+use crate::aggregate::number_value;
+use crate::relation::{resolve_column, rollup_value, RelatedTable, RelatedTables};
+use crate::rows::row_id;
+use crate::schema::{ColumnDefinition, ColumnType, RollupFunction, TableSchema};
+use crate::{tr, ErrorCode, WorkspaceError};
+
+/// 式の値の型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormulaType {
+    Number,
+    Text,
+    Boolean,
+    /// 日時（日付のみの場合は0時）
+    Date,
+    /// 文字列の配列（multiselectのタグ、relationの_id）
+    List,
+    /// null（どの型とも組み合わせられる）
+    Any,
+}
+
+impl fmt::Display for FormulaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FormulaType::Number => "number",
+            FormulaType::Text => "text",
+            FormulaType::Boolean => "boolean",
+            FormulaType::Date => "date",
+            FormulaType::List => "list",
+            FormulaType::Any => "null",
+        })
+    }
+}
+
+/// 検査済みの計算列（依存する計算列より後に並ぶ）
+#[derive(Debug, Clone)]
+pub struct CompiledFormula {
+    pub column_id: String,
+    /// 式の結果の型
+    pub result_type: FormulaType,
+    expr: Expr,
+}
+
+/// 二項演算子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    /// 文字列の連結（&）
+    Concat,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Concat => "&",
+            BinaryOp::Eq => "=",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+        }
+    }
+}
+
+/// 式の構文木
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(f64),
+    Text(String),
+    Boolean(bool),
+    Null,
+    /// カラムの参照（解決済みのカラムID）
+    Column(String),
+    Negate(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// 組み込み関数の呼び出し（関数名は小文字）
+    Call(String, Vec<Expr>),
+    /// リレーション先の行のカラムの集計（count の場合はカラムなし）
+    Rollup {
+        function: RollupFunction,
+        relation: String,
+        column: Option<String>,
+        /// 参照先が別のワークスペースか（カラムは評価時に参照先のスキーマで解決する）
+        external: bool,
+    },
+}
+
+/// 評価中の値
+#[derive(Debug, Clone, PartialEq)]
+enum Datum {
+    Null,
+    Number(f64),
+    Text(String),
+    Boolean(bool),
+    Date(DateTime<Utc>),
+    List(Vec<String>),
+}
+
+/// スキーマの計算列の問題を列挙する（スキーマの検証で使用）
+///
+/// # 引数
+/// * `schema` - スキーマ
+///
+/// # 戻り値
+/// 構文・型・循環参照の問題（問題のあるカラムIDを含む。問題がなければ空）
+pub fn formula_problems(schema: &TableSchema) -> Vec<WorkspaceError> {
+    compile(schema).1
+}
+
+/// スキーマの計算列を検査し、依存関係の順に並べる
+///
+/// # 引数
+/// * `schema` - スキーマ
+///
+/// # 戻り値
+/// 成功時は評価順の計算列、問題がある場合は個々の問題を含むスキーマのエラー
+pub fn compile_formulas(schema: &TableSchema) -> Result<Vec<CompiledFormula>, WorkspaceError> {
+    let (compiled, problems) = compile(schema);
+    if problems.is_empty() {
+        Ok(compiled)
+    } else {
+        Err(WorkspaceError::invalid_schema("formula.invalid", problems))
+    }
+}
+
+/// 行データに計算列の値を設定する
+/// 計算列の値はファイルに保存せず、読み込みのたびにこの関数で求める
+///
+/// # 引数
+/// * `rows` - 行データ（計算列の値を上書きする）
+/// * `schema` - スキーマ
+/// * `related` - リレーション列の参照先（集計関数で使用）
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、計算列の式が不正な場合はスキーマのエラー
+pub fn evaluate_formulas(
+    rows: &mut [Value],
+    schema: &TableSchema,
+    related: &RelatedTables,
+) -> Result<(), WorkspaceError> {
+    let formulas = compile_formulas(schema)?;
+    if formulas.is_empty() {
+        return Ok(());
+    }
+    let context = Context {
+        columns: schema
+            .columns
+            .iter()
+            .map(|column| (column.id.as_str(), column))
+            .collect(),
+        formula_types: formulas
+            .iter()
+            .map(|formula| (formula.column_id.clone(), formula.result_type))
+            .collect(),
+        related: related
+            .iter()
+            .filter_map(|(column, table)| {
+                let table = table.as_ref().ok()?;
+                Some((column.as_str(), (table, id_index(&table.rows))))
+            })
+            .collect(),
+        now: Utc::now(),
+    };
+    let index = id_index(rows);
+
+    // 後の計算列やリレーション先の集計が前の計算列の値を使えるよう、列ごとに全行を評価する
+    for formula in &formulas {
+        let values: Vec<Value> = rows
+            .iter()
+            .map(|row| to_json(context.eval(&formula.expr, row, rows, &index)))
+            .collect();
+        for (row, value) in rows.iter_mut().zip(values) {
+            if let Value::Object(object) = row {
+                object.insert(formula.column_id.clone(), value);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 保存前に行データから計算列・lookup列・rollup列の値を取り除く
+///
+/// # 引数
+/// * `rows` - 行データ
+/// * `schema` - スキーマ
+pub fn strip_computed_values(rows: &mut [Value], schema: &TableSchema) {
+    let formula_ids: Vec<&str> = schema
+        .columns
+        .iter()
+        .filter(|column| column.column_type.is_computed())
+        .map(|column| column.id.as_str())
+        .collect();
+    if formula_ids.is_empty() {
+        return;
+    }
+    for row in rows {
+        if let Value::Object(object) = row {
+            for id in &formula_ids {
+                object.remove(*id);
+            }
+        }
+    }
+}
+
+/// 計算列を解析・検査し、評価順の計算列と見つかった問題を返す
+fn compile(schema: &TableSchema) -> (Vec<CompiledFormula>, Vec<WorkspaceError>) {
+    let mut problems = Vec::new();
+    let mut parsed: Vec<(&ColumnDefinition, Expr)> = Vec::new();
+    for column in &schema.columns {
+        if column.column_type != ColumnType::Formula {
+            continue;
+        }
+        let Some(source) = column
+            .formula
+            .as_deref()
+            .filter(|text| !text.trim().is_empty())
+        else {
+            problems.push(problem(
+                "formula.missing",
+                tr!("formula.missing", id = column.id),
+                &column.id,
+            ));
+            continue;
+        };
+        match parse(source, schema) {
+            Ok(expr) => parsed.push((column, expr)),
+            Err(err) => problems.push(problem(
+                "formula.column_error",
+                tr!("formula.column_error", id = column.id, error = err),
+                &column.id,
+            )),
+        }
+    }
+
+    let order = match evaluation_order(&parsed) {
+        Ok(order) => order,
+        Err(err) => {
+            problems.push(err);
+            return (Vec::new(), problems);
+        }
+    };
+
+    let mut types: HashMap<String, FormulaType> = HashMap::new();
+    let mut compiled = Vec::new();
+    for position in order {
+        let (column, expr) = &parsed[position];
+        let checker = TypeChecker {
+            schema,
+            formula_types: &types,
+        };
+        match checker.check(expr) {
+            Ok(result_type) => {
+                types.insert(column.id.clone(), result_type);
+                compiled.push(CompiledFormula {
+                    column_id: column.id.clone(),
+                    result_type,
+                    expr: expr.clone(),
+                });
+            }
+            Err(err) => problems.push(problem(
+                "formula.column_error",
+                tr!("formula.column_error", id = column.id, error = err),
+                &column.id,
+            )),
+        }
+    }
+    (compiled, problems)
+}
+
+/// 計算列の依存関係から評価順を求める（循環参照がある場合はエラー）
+fn evaluation_order(parsed: &[(&ColumnDefinition, Expr)]) -> Result<Vec<usize>, WorkspaceError> {
+    let positions: HashMap<&str, usize> = parsed
+        .iter()
+        .enumerate()
+        .map(|(position, (column, _))| (column.id.as_str(), position))
+        .collect();
+    let dependencies: Vec<Vec<usize>> = parsed
+        .iter()
+        .map(|(_, expr)| {
+            let mut columns = Vec::new();
+            referenced_columns(expr, &mut columns);
+            columns
+                .iter()
+                .filter_map(|id| positions.get(id.as_str()).copied())
+                .collect()
+        })
+        .collect();
+
+    // 深さ優先探索で、依存先を先に並べる
+    fn visit(
+        position: usize,
+        dependencies: &[Vec<usize>],
+        done: &mut HashSet<usize>,
+        path: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), Vec<usize>> {
+        if done.contains(&position) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|&visiting| visiting == position) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(position);
+            return Err(cycle);
+        }
+        path.push(position);
+        for &dependency in &dependencies[position] {
+            visit(dependency, dependencies, done, path, order)?;
+        }
+        path.pop();
+        done.insert(position);
+        order.push(position);
+        Ok(())
+    }
+
+    let mut done = HashSet::new();
+    let mut order = Vec::new();
+    for position in 0..parsed.len() {
+        visit(
+            position,
+            &dependencies,
+            &mut done,
+            &mut Vec::new(),
+            &mut order,
+        )
+        .map_err(|cycle| {
+            let names: Vec<&str> = cycle
+                .iter()
+                .map(|&position| parsed[position].0.id.as_str())
+                .collect();
+            problem(
+                "formula.cycle",
+                tr!("formula.cycle", columns = names.join(" → ")),
+                names[0],
+            )
+        })?;
+    }
+    Ok(order)
+}
+
+/// 計算列の問題を作成する
+fn problem(message_key: &'static str, message: String, column_id: &str) -> WorkspaceError {
+    WorkspaceError::new(ErrorCode::InvalidSchema, message_key, message).with_column(column_id)
+}
+
+/// 式が参照するカラムIDを集める
+fn referenced_columns(expr: &Expr, columns: &mut Vec<String>) {
+    match expr {
+        Expr::Column(id) => columns.push(id.clone()),
+        Expr::Rollup {
+            relation,
+            column,
+            external,
+            ..
+        } => {
+            columns.push(relation.clone());
+            if !external {
+                columns.extend(column.clone());
+            }
+        }
+        Expr::Negate(inner) | Expr::Not(inner) => referenced_columns(inner, columns),
+        Expr::Binary(_, left, right) => {
+            referenced_columns(left, columns);
+            referenced_columns(right, columns);
+        }
+        Expr::Call(_, args) => args.iter().for_each(|arg| referenced_columns(arg, columns)),
+        Expr::Number(_) | Expr::Text(_) | Expr::Boolean(_) | Expr::Null => {}
+    }
+}
+
+/// 字句
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    /// 関数名・キーワード・カラムID
+    Ident(String),
+    /// {} で囲んだカラムIDまたは表示名
+    Column(String),
+    Symbol(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+/// 式を字句に分割する（位置は文字単位）
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, String> {
+    const SYMBOLS: [&str; 16] = [
+        "==", "!=", "<>", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "&", "=", "<", ">",
+    ];
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while position < chars.len() {
+        let start = position;
+        let ch = chars[position];
+        if ch.is_whitespace() {
+            position += 1;
+            continue;
+        }
+        let token = match ch {
+            '(' => {
+                position += 1;
+                Token::LParen
+            }
+            ')' => {
+                position += 1;
+                Token::RParen
+            }
+            ',' => {
+                position += 1;
+                Token::Comma
+            }
+            '"' | '\'' => {
+                position += 1;
+                let mut text = String::new();
+                loop {
+                    match chars.get(position) {
+                        None => return Err(at(start, &tr!("formula.unclosed_string"))),
+                        Some('\\') => {
+                            match chars.get(position + 1) {
+                                Some('n') => text.push('\n'),
+                                Some(&escaped) => text.push(escaped),
+                                None => return Err(at(start, &tr!("formula.unclosed_string"))),
+                            }
+                            position += 2;
+                        }
+                        Some(&quote) if quote == ch => {
+                            position += 1;
+                            break;
+                        }
+                        Some(&other) => {
+                            text.push(other);
+                            position += 1;
+                        }
+                    }
+                }
+                Token::Text(text)
+            }
+            '{' => {
+                let end = chars[position..]
+                    .iter()
+                    .position(|&ch| ch == '}')
+                    .ok_or_else(|| at(start, &tr!("formula.unclosed_column")))?;
+                let name: String = chars[position + 1..position + end].iter().collect();
+                position += end + 1;
+                Token::Column(name.trim().to_string())
+            }
+            _ if ch.is_ascii_digit() || ch == '.' => {
+                while position < chars.len()
+                    && (chars[position].is_ascii_digit() || chars[position] == '.')
+                {
+                    position += 1;
+                }
+                let text: String = chars[start..position].iter().collect();
+                let number = text
+                    .parse::<f64>()
+                    .map_err(|_| at(start, &tr!("formula.invalid_number", text = text)))?;
+                Token::Number(number)
+            }
+            _ if ch.is_alphabetic() || ch == '_' => {
+                while position < chars.len()
+                    && (chars[position].is_alphanumeric() || chars[position] == '_')
+                {
+                    position += 1;
+                }
+                Token::Ident(chars[start..position].iter().collect())
+            }
+            _ => {
+                let rest: String = chars[position..].iter().take(2).collect();
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|symbol| rest.starts_with(**symbol))
+                    .ok_or_else(|| at(start, &tr!("formula.invalid_char", char = ch)))?;
+                position += symbol.chars().count();
+                Token::Symbol(symbol)
+            }
+        };
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+/// 式を解析し、カラムの参照をIDに解決する
+fn parse(source: &str, schema: &TableSchema) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+        end: source.chars().count(),
+        depth: 0,
+        schema,
+    };
+    let expr = parser.expression()?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(expr),
+        Some((offset, _)) => Err(at(*offset, &tr!("formula.trailing"))),
+    }
+}
+
+/// 括弧・関数呼び出し・単項演算子の入れ子の上限（深い再帰でスタックが溢れないようにする）
+const MAX_DEPTH: usize = 64;
+
+/// 再帰下降の構文解析器
+/// 優先順位は低い順に or, and, not, 比較, &, 加減算, 乗除算, 単項マイナス
+struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    /// 式の文字数（末尾のエラー位置に使用）
+    end: usize,
+    /// 現在の入れ子の深さ
+    depth: usize,
+    schema: &'a TableSchema,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end, |(offset, _)| *offset)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(_, token)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    /// 次の字句が記号またはキーワード（大文字・小文字を区別しない）のいずれかであれば読み進める
+    fn accept(&mut self, symbols: &[&str], keyword: Option<&str>) -> bool {
+        let matched = match self.peek() {
+            Some(Token::Symbol(symbol)) => symbols.contains(symbol),
+            Some(Token::Ident(ident)) => {
+                keyword.is_some_and(|word| ident.eq_ignore_ascii_case(word))
+            }
+            _ => false,
+        };
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn expect(&mut self, expected: Token, description: &str) -> Result<(), String> {
+        if self.peek() == Some(&expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(at(
+                self.offset(),
+                &tr!("formula.expected", token = description),
+            ))
+        }
+    }
+
+    /// 入れ子を1段深くして解析する（上限を超える場合はエラー）
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(at(self.offset(), &tr!("formula.too_deep", max = MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        self.nested(|parser| {
+            let mut left = parser.and()?;
+            while parser.accept(&["||"], Some("or")) {
+                left = Expr::Binary(BinaryOp::Or, Box::new(left), Box::new(parser.and()?));
+            }
+            Ok(left)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.not()?;
+        while self.accept(&["&&"], Some("and")) {
+            left = Expr::Binary(BinaryOp::And, Box::new(left), Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.accept(&[], Some("not")) {
+            return self.nested(|parser| Ok(Expr::Not(Box::new(parser.not()?))));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.concat()?;
+        let op = match self.peek() {
+            Some(Token::Symbol("=" | "==")) => BinaryOp::Eq,
+            Some(Token::Symbol("!=" | "<>")) => BinaryOp::Ne,
+            Some(Token::Symbol("<")) => BinaryOp::Lt,
+            Some(Token::Symbol("<=")) => BinaryOp::Le,
+            Some(Token::Symbol(">")) => BinaryOp::Gt,
+            Some(Token::Symbol(">=")) => BinaryOp::Ge,
+            _ => return Ok(left),
+        };
+        self.position += 1;
+        Ok(Expr::Binary(op, Box::new(left), Box::new(self.concat()?)))
+    }
+
+    fn concat(&mut self) -> Result<Expr, String> {
+        let mut left = self.additive()?;
+        while self.accept(&["&"], None) {
+            left = Expr::Binary(BinaryOp::Concat, Box::new(left), Box::new(self.additive()?));
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> Result<Expr, String> {
+        let mut left = self.multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol("+")) => BinaryOp::Add,
+                Some(Token::Symbol("-")) => BinaryOp::Sub,
+                _ => return Ok(left),
+            };
+            self.position += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol("*")) => BinaryOp::Mul,
+                Some(Token::Symbol("/")) => BinaryOp::Div,
+                Some(Token::Symbol("%")) => BinaryOp::Rem,
+                _ => return Ok(left),
+            };
+            self.position += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.accept(&["-"], None) {
+            return self.nested(|parser| Ok(Expr::Negate(Box::new(parser.unary()?))));
+        }
+        if self.accept(&["+"], None) {
+            return self.nested(Self::unary);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let offset = self.offset();
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expr::Number(number)),
+            Some(Token::Text(text)) => Ok(Expr::Text(text)),
+            Some(Token::Column(name)) => self.column(&name, offset).map(Expr::Column),
+            Some(Token::LParen) => {
+                let expr = self.expression()?;
+                self.expect(Token::RParen, ")")?;
+                Ok(expr)
+            }
+            Some(Token::Ident(ident)) => {
+                if self.peek() == Some(&Token::LParen) {
+                    self.position += 1;
+                    return self.call(&ident.to_lowercase(), offset);
+                }
+                match ident.to_lowercase().as_str() {
+                    "true" => Ok(Expr::Boolean(true)),
+                    "false" => Ok(Expr::Boolean(false)),
+                    "null" => Ok(Expr::Null),
+                    _ => self.column(&ident, offset).map(Expr::Column),
+                }
+            }
+            Some(_) => Err(at(offset, &tr!("formula.value_expected"))),
+            None => Err(at(offset, &tr!("formula.unexpected_end"))),
+        }
+    }
+
+    /// 関数呼び出しの引数を読み取る（開き括弧の後から）
+    fn call(&mut self, name: &str, offset: usize) -> Result<Expr, String> {
+        let rollup = match name {
+            "count" => Some(RollupFunction::Count),
+            "sum" => Some(RollupFunction::Sum),
+            "avg" => Some(RollupFunction::Avg),
+            "min" => Some(RollupFunction::Min),
+            "max" => Some(RollupFunction::Max),
+            "concat" => Some(RollupFunction::Concat),
+            _ => None,
+        };
+        if let Some(function) = rollup {
+            return self.rollup(function, name, offset);
+        }
+        let mut args = Vec::new();
+        if !self.accept_token(&Token::RParen) {
+            loop {
+                args.push(self.expression()?);
+                if self.accept_token(&Token::RParen) {
+                    break;
+                }
+                self.expect(Token::Comma, &tr!("formula.comma_or_paren"))?;
+            }
+        }
+        Ok(Expr::Call(name.to_string(), args))
+    }
+
+    /// 集計関数の引数（リレーションのカラムと、リレーション先のカラム）を読み取る
+    fn rollup(
+        &mut self,
+        function: RollupFunction,
+        name: &str,
+        offset: usize,
+    ) -> Result<Expr, String> {
+        let usage = if function == RollupFunction::Count {
+            tr!("formula.rollup_usage_count", name = name)
+        } else {
+            tr!("formula.rollup_usage", name = name)
+        };
+        let relation = self.column_name().ok_or_else(|| at(offset, &usage))?;
+        let relation = self.column(&relation, offset)?;
+        let external = self
+            .schema
+            .columns
+            .iter()
+            .any(|column| column.id == relation && column.relation.is_some());
+        let column = if function == RollupFunction::Count {
+            None
+        } else {
+            if !self.accept_token(&Token::Comma) {
+                return Err(at(offset, &usage));
+            }
+            let column = self.column_name().ok_or_else(|| at(offset, &usage))?;
+            Some(if external {
+                column
+            } else {
+                self.column(&column, offset)?
+            })
+        };
+        if !self.accept_token(&Token::RParen) {
+            return Err(at(offset, &usage));
+        }
+        Ok(Expr::Rollup {
+            function,
+            relation,
+            column,
+            external,
+        })
+    }
+
+    /// カラム名の字句（{} で囲んだ名前または識別子）を読み進める
+    fn column_name(&mut self) -> Option<String> {
+        let name = match self.peek()? {
+            Token::Column(name) | Token::Ident(name) => name.clone(),
+            _ => return None,
+        };
+        self.position += 1;
+        Some(name)
+    }
+
+    fn accept_token(&mut self, expected: &Token) -> bool {
+        let matched = self.peek() == Some(expected);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    /// カラムの参照をIDに解決する（IDが一致しない場合は表示名で探す）
+    fn column(&self, name: &str, offset: usize) -> Result<String, String> {
+        if SYSTEM_FIELDS.iter().any(|(id, _)| *id == name) {
+            return Ok(name.to_string());
+        }
+        self.schema
+            .columns
+            .iter()
+            .find(|column| column.id == name)
+            .or_else(|| {
+                self.schema
+                    .columns
+                    .iter()
+                    .find(|column| column.name == name)
+            })
+            .map(|column| column.id.clone())
+            .ok_or_else(|| at(offset, &tr!("column.not_found", column = name)))
+    }
+}
+
+/// 行の管理用フィールドとその型（スキーマに定義がなくても参照できる）
+const SYSTEM_FIELDS: [(&str, FormulaType); 4] = [
+    ("_id", FormulaType::Text),
+    ("_created", FormulaType::Date),
+    ("_updated", FormulaType::Date),
+    ("_order", FormulaType::Number),
+];
+
+/// 位置付きのエラーメッセージを作る
+fn at(offset: usize, message: &str) -> String {
+    tr!("formula.at", position = offset + 1, message = message)
+}
+
+/// 式の型検査
+struct TypeChecker<'a> {
+    schema: &'a TableSchema,
+    /// 検査済みの計算列の型
+    formula_types: &'a HashMap<String, FormulaType>,
+}
+
+impl TypeChecker<'_> {
+    fn check(&self, expr: &Expr) -> Result<FormulaType, String> {
+        use FormulaType::*;
+        match expr {
+            Expr::Number(_) => Ok(Number),
+            Expr::Text(_) => Ok(Text),
+            Expr::Boolean(_) => Ok(Boolean),
+            Expr::Null => Ok(Any),
+            Expr::Column(id) => self.column_type(id),
+            Expr::Negate(inner) => self
+                .expect(inner, &[Number], &tr!("formula.unary_minus"))
+                .map(|_| Number),
+            Expr::Not(inner) => self.expect(inner, &[Boolean], "not").map(|_| Boolean),
+            Expr::Binary(op, left, right) => {
+                let (left, right) = (self.check(left)?, self.check(right)?);
+                binary_type(*op, left, right).ok_or_else(|| {
+                    tr!(
+                        "formula.operator_types",
+                        operator = op.symbol(),
+                        left = left,
+                        right = right
+                    )
+                })
+            }
+            Expr::Call(name, args) => self.call(name, args),
+            Expr::Rollup {
+                function,
+                relation,
+                column,
+                external,
+            } => {
+                if self.definition(relation).map(|column| column.column_type)
+                    != Some(ColumnType::Relation)
+                {
+                    return Err(tr!("formula.not_relation", column = relation));
+                }
+                if *function == RollupFunction::Concat {
+                    return Ok(Text);
+                }
+                // 別のワークスペースのカラムは評価時に確認する（数値とチェックボックス以外は集計しない）
+                if let Some(column) = column.as_ref().filter(|_| !external) {
+                    let column_type = self.column_type(column)?;
+                    if !matches!(column_type, Number | Boolean | Any) {
+                        return Err(tr!("formula.rollup_numeric", column = column));
+                    }
+                }
+                Ok(Number)
+            }
+        }
+    }
+
+    /// 関数呼び出しの型を求める
+    fn call(&self, name: &str, args: &[Expr]) -> Result<FormulaType, String> {
+        use FormulaType::*;
+        let arity = |min: usize, max: usize| {
+            if args.len() < min || args.len() > max {
+                Err(if min == max {
+                    tr!("formula.arity_exact", name = name, count = min)
+                } else {
+                    tr!("formula.arity_range", name = name, min = min, max = max)
+                })
+            } else {
+                Ok(())
+            }
+        };
+        match name {
+            "if" => {
+                arity(3, 3)?;
+                self.expect(&args[0], &[Boolean], &tr!("formula.if_condition"))?;
+                let (then, otherwise) = (self.check(&args[1])?, self.check(&args[2])?);
+                unify(then, otherwise)
+                    .ok_or_else(|| tr!("formula.if_branches", then = then, otherwise = otherwise))
+            }
+            "is_empty" => {
+                arity(1, 1)?;
+                self.check(&args[0])?;
+                Ok(Boolean)
+            }
+            "abs" | "floor" | "ceil" => {
+                arity(1, 1)?;
+                self.expect(&args[0], &[Number], name)?;
+                Ok(Number)
+            }
+            "round" => {
+                arity(1, 2)?;
+                for arg in args {
+                    self.expect(arg, &[Number], name)?;
+                }
+                Ok(Number)
+            }
+            "len" => {
+                arity(1, 1)?;
+                self.expect(&args[0], &[Text, List], name)?;
+                Ok(Number)
+            }
+            "upper" | "lower" | "trim" => {
+                arity(1, 1)?;
+                self.expect(&args[0], &[Text], name)?;
+                Ok(Text)
+            }
+            "text" => {
+                arity(1, 1)?;
+                self.check(&args[0])?;
+                Ok(Text)
+            }
+            "contains" => {
+                arity(2, 2)?;
+                self.expect(&args[0], &[Text, List], name)?;
+                self.expect(&args[1], &[Text], name)?;
+                Ok(Boolean)
+            }
+            "join" => {
+                arity(1, 2)?;
+                self.expect(&args[0], &[List], name)?;
+                if let Some(separator) = args.get(1) {
+                    self.expect(separator, &[Text], name)?;
+                }
+                Ok(Text)
+            }
+            "date" => {
+                arity(1, 1)?;
+                self.expect(&args[0], &[Text, Date], name)?;
+                Ok(Date)
+            }
+            "today" | "now" => {
+                arity(0, 0)?;
+                Ok(Date)
+            }
+            "year" | "month" | "day" => {
+                arity(1, 1)?;
+                self.expect(&args[0], &[Date], name)?;
+                Ok(Number)
+            }
+            "add_days" => {
+                arity(2, 2)?;
+                self.expect(&args[0], &[Date], name)?;
+                self.expect(&args[1], &[Number], name)?;
+                Ok(Date)
+            }
+            "days_between" => {
+                arity(2, 2)?;
+                self.expect(&args[0], &[Date], name)?;
+                self.expect(&args[1], &[Date], name)?;
+                Ok(Number)
+            }
+            "format_date" => {
+                arity(2, 2)?;
+                self.expect(&args[0], &[Date], name)?;
+                self.expect(&args[1], &[Text], name)?;
+                Ok(Text)
+            }
+            _ => Err(tr!("formula.unknown_function", name = name)),
+        }
+    }
+
+    /// 式の型が候補のいずれか（またはnull）であることを確認する
+    fn expect(
+        &self,
+        expr: &Expr,
+        allowed: &[FormulaType],
+        context: &str,
+    ) -> Result<FormulaType, String> {
+        let actual = self.check(expr)?;
+        if actual == FormulaType::Any || allowed.contains(&actual) {
+            return Ok(actual);
+        }
+        let allowed: Vec<String> = allowed.iter().map(ToString::to_string).collect();
+        Err(tr!(
+            "formula.type_expected",
+            context = context,
+            allowed = allowed.join(&tr!("formula.or")),
+            actual = actual
+        ))
+    }
+
+    fn definition(&self, id: &str) -> Option<&ColumnDefinition> {
+        self.schema.columns.iter().find(|column| column.id == id)
+    }
+
+    /// カラムの値の型
+    fn column_type(&self, id: &str) -> Result<FormulaType, String> {
+        if let Some((_, field_type)) = SYSTEM_FIELDS.iter().find(|(field, _)| *field == id) {
+            return Ok(*field_type);
+        }
+        let column = self
+            .definition(id)
+            .ok_or_else(|| tr!("column.not_found", column = id))?;
+        Ok(match column.column_type {
+            ColumnType::Text => FormulaType::Text,
+            ColumnType::Number => FormulaType::Number,
+            ColumnType::Checkbox => FormulaType::Boolean,
+            ColumnType::Multiselect | ColumnType::Relation | ColumnType::Lookup => {
+                FormulaType::List
+            }
+            ColumnType::Rollup => rollup_type(column),
+            ColumnType::Formula => *self
+                .formula_types
+                .get(id)
+                .ok_or_else(|| tr!("formula.column_invalid", id = id))?,
+        })
+    }
+}
+
+/// 二項演算の結果の型（組み合わせられない場合はNone）
+fn binary_type(op: BinaryOp, left: FormulaType, right: FormulaType) -> Option<FormulaType> {
+    use FormulaType::*;
+    match op {
+        BinaryOp::Concat => Some(Text),
+        BinaryOp::Eq | BinaryOp::Ne => unify(left, right).map(|_| Boolean),
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => match unify(left, right)? {
+            Number | Text | Date | Any => Some(Boolean),
+            _ => None,
+        },
+        BinaryOp::And | BinaryOp::Or => match (left, right) {
+            (Boolean | Any, Boolean | Any) => Some(Boolean),
+            _ => None,
+        },
+        BinaryOp::Add => match (left, right) {
+            (Number, Number) => Some(Number),
+            (Date, Number) | (Number, Date) => Some(Date),
+            (Any, Number | Date | Any) | (Number | Date, Any) => Some(Any),
+            _ => None,
+        },
+        BinaryOp::Sub => match (left, right) {
+            (Number, Number) | (Date, Date) => Some(Number),
+            (Date, Number) => Some(Date),
+            (Any, Number | Date | Any) | (Number | Date, Any) => Some(Any),
+            _ => None,
+        },
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => match (left, right) {
+            (Number, Number) => Some(Number),
+            (Number | Any, Number | Any) => Some(Any),
+            _ => None,
+        },
+    }
+}
+
+/// 2つの型をまとめる（nullはどの型とも一致する）
+fn unify(left: FormulaType, right: FormulaType) -> Option<FormulaType> {
+    match (left, right) {
+        (FormulaType::Any, other) | (other, FormulaType::Any) => Some(other),
+        _ if left == right => Some(left),
+        _ => None,
+    }
+}
+
+/// 評価に必要な情報
+struct Context<'a> {
+    columns: HashMap<&'a str, &'a ColumnDefinition>,
+    formula_types: HashMap<String, FormulaType>,
+    /// リレーション列のIDごとの参照先と、その_idから行の位置への索引
+    related: HashMap<&'a str, (&'a RelatedTable, HashMap<String, usize>)>,
+    /// 評価を始めた日時（today・nowはすべての行で同じ値）
+    now: DateTime<Utc>,
+}
+
+impl Context<'_> {
+    /// 1行について式を評価する（0除算や日付として読めない値はnull）
+    fn eval(
+        &self,
+        expr: &Expr,
+        row: &Value,
+        rows: &[Value],
+        index: &HashMap<String, usize>,
+    ) -> Datum {
+        let eval = |expr: &Expr| self.eval(expr, row, rows, index);
+        match expr {
+            Expr::Number(number) => Datum::Number(*number),
+            Expr::Text(text) => Datum::Text(text.clone()),
+            Expr::Boolean(flag) => Datum::Boolean(*flag),
+            Expr::Null => Datum::Null,
+            Expr::Column(id) => self.read(row, id),
+            Expr::Negate(inner) => match eval(inner) {
+                Datum::Number(number) => Datum::Number(-number),
+                _ => Datum::Null,
+            },
+            Expr::Not(inner) => Datum::Boolean(!truthy(&eval(inner))),
+            Expr::Binary(BinaryOp::And, left, right) => {
+                Datum::Boolean(truthy(&eval(left)) && truthy(&eval(right)))
+            }
+            Expr::Binary(BinaryOp::Or, left, right) => {
+                Datum::Boolean(truthy(&eval(left)) || truthy(&eval(right)))
+            }
+            Expr::Binary(op, left, right) => binary(*op, eval(left), eval(right)),
+            Expr::Call(name, args) if name == "if" => {
+                if truthy(&eval(&args[0])) {
+                    eval(&args[1])
+                } else {
+                    eval(&args[2])
+                }
+            }
+            Expr::Call(name, args) => {
+                let args: Vec<Datum> = args.iter().map(eval).collect();
+                self.call(name, &args)
+            }
+            Expr::Rollup {
+                function,
+                relation,
+                column,
+                external,
+            } => {
+                let Datum::List(ids) = self.read(row, relation) else {
+                    return Datum::Null;
+                };
+                let (target_rows, target_index, column) = if *external {
+                    // 参照先を読み込めなかった場合や、カラムが見つからない場合はnull
+                    let Some((table, target_index)) = self.related.get(relation.as_str()) else {
+                        return Datum::Null;
+                    };
+                    let column = match column {
+                        Some(name) => match resolve_column(&table.schema, name) {
+                            Some(id) => Some(id),
+                            None => return Datum::Null,
+                        },
+                        None => None,
+                    };
+                    (table.rows.as_slice(), target_index, column)
+                } else {
+                    (rows, index, column.clone())
+                };
+                let related: Vec<&Value> = ids
+                    .iter()
+                    .filter_map(|id| target_index.get(id).map(|&position| &target_rows[position]))
+                    .collect();
+                match rollup_value(*function, &related, column.as_deref()) {
+                    Value::Number(number) => number.as_f64().map_or(Datum::Null, Datum::Number),
+                    Value::String(text) => Datum::Text(text),
+                    _ => Datum::Null,
+                }
+            }
+        }
+    }
+
+    /// 組み込み関数を評価する（型検査済みのため、引数の型が異なるのはnullの場合のみ）
+    fn call(&self, name: &str, args: &[Datum]) -> Datum {
+        let number = |position: usize| match args.get(position) {
+            Some(Datum::Number(number)) => Some(*number),
+            _ => None,
+        };
+        let date = |position: usize| match args.get(position) {
+            Some(Datum::Date(date)) => Some(*date),
+            _ => None,
+        };
+        let text = |position: usize| match args.get(position) {
+            Some(Datum::Text(text)) => Some(text.as_str()),
+            _ => None,
+        };
+        let result = match name {
+            "is_empty" => Some(Datum::Boolean(is_empty(&args[0]))),
+            "abs" => number(0).map(|n| Datum::Number(n.abs())),
+            "floor" => number(0).map(|n| Datum::Number(n.floor())),
+            "ceil" => number(0).map(|n| Datum::Number(n.ceil())),
+            "round" => number(0).map(|n| {
+                let scale = 10f64.powi(number(1).unwrap_or(0.0) as i32);
+                Datum::Number((n * scale).round() / scale)
+            }),
+            "len" => match &args[0] {
+                Datum::Text(text) => Some(Datum::Number(text.chars().count() as f64)),
+                Datum::List(items) => Some(Datum::Number(items.len() as f64)),
+                _ => None,
+            },
+            "upper" => text(0).map(|text| Datum::Text(text.to_uppercase())),
+            "lower" => text(0).map(|text| Datum::Text(text.to_lowercase())),
+            "trim" => text(0).map(|text| Datum::Text(text.trim().to_string())),
+            "text" => Some(Datum::Text(display(&args[0]))),
+            "contains" => {
+                let needle = text(1).unwrap_or_default();
+                match &args[0] {
+                    Datum::Text(text) => Some(Datum::Boolean(text.contains(needle))),
+                    Datum::List(items) => {
+                        Some(Datum::Boolean(items.iter().any(|item| item == needle)))
+                    }
+                    _ => Some(Datum::Boolean(false)),
+                }
+            }
+            "join" => match &args[0] {
+                Datum::List(items) => Some(Datum::Text(items.join(text(1).unwrap_or(", ")))),
+                _ => None,
+            },
+            "date" => match &args[0] {
+                Datum::Date(date) => Some(Datum::Date(*date)),
+                Datum::Text(text) => parse_date(text).map(Datum::Date),
+                _ => None,
+            },
+            "today" => Some(Datum::Date(
+                self.now.date_naive().and_time(NaiveTime::MIN).and_utc(),
+            )),
+            "now" => Some(Datum::Date(self.now)),
+            "year" => date(0).map(|date| Datum::Number(f64::from(date.year()))),
+            "month" => date(0).map(|date| Datum::Number(f64::from(date.month()))),
+            "day" => date(0).map(|date| Datum::Number(f64::from(date.day()))),
+            "add_days" => date(0)
+                .zip(number(1))
+                .and_then(|(date, days)| add_days(date, days))
+                .map(Datum::Date),
+            "days_between" => date(0)
+                .zip(date(1))
+                .map(|(from, to)| Datum::Number((to - from).num_days() as f64)),
+            "format_date" => date(0).zip(text(1)).and_then(|(date, pattern)| {
+                // 不正な書式指定はパニックせずにnullにする
+                let mut formatted = String::new();
+                write!(formatted, "{}", date.format(pattern)).ok()?;
+                Some(Datum::Text(formatted))
+            }),
+            _ => None,
+        };
+        result.unwrap_or(Datum::Null)
+    }
+
+    /// 行からカラムの値を読み取る
+    fn read(&self, row: &Value, id: &str) -> Datum {
+        let value = row.get(id).unwrap_or(&Value::Null);
+        let value_type = SYSTEM_FIELDS
+            .iter()
+            .find(|(field, _)| *field == id)
+            .map(|(_, field_type)| *field_type)
+            .or_else(|| {
+                let column = self.columns.get(id)?;
+                match column.column_type {
+                    ColumnType::Formula => self.formula_types.get(id).copied(),
+                    ColumnType::Text => Some(FormulaType::Text),
+                    ColumnType::Number => Some(FormulaType::Number),
+                    ColumnType::Checkbox => Some(FormulaType::Boolean),
+                    ColumnType::Multiselect | ColumnType::Relation | ColumnType::Lookup => {
+                        Some(FormulaType::List)
+                    }
+                    ColumnType::Rollup => Some(rollup_type(column)),
+                }
+            });
+        match (value_type, value) {
+            (Some(FormulaType::Number), Value::Number(number)) => {
+                number.as_f64().map_or(Datum::Null, Datum::Number)
+            }
+            (Some(FormulaType::Text), Value::String(text)) => Datum::Text(text.clone()),
+            // 未入力のチェックボックスはオフとして扱う
+            (Some(FormulaType::Boolean), Value::Bool(flag)) => Datum::Boolean(*flag),
+            (Some(FormulaType::Boolean), Value::Null) => Datum::Boolean(false),
+            (Some(FormulaType::Date), Value::String(text)) => {
+                parse_date(text).map_or(Datum::Null, Datum::Date)
+            }
+            // リレーションは_id単体の場合もある
+            (Some(FormulaType::List), Value::String(text)) if !text.is_empty() => {
+                Datum::List(vec![text.clone()])
+            }
+            // lookupの値は数値や真偽値を含む場合がある
+            (Some(FormulaType::List), Value::Array(items)) => Datum::List(
+                items
+                    .iter()
+                    .filter_map(|item| match item {
+                        Value::String(text) => Some(text.clone()),
+                        Value::Number(_) | Value::Bool(_) => Some(item.to_string()),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            (Some(FormulaType::List), _) => Datum::List(Vec::new()),
+            _ => Datum::Null,
+        }
+    }
+}
+
+/// rollup列の値の型（concat は文字列、それ以外は数値）
+fn rollup_type(column: &ColumnDefinition) -> FormulaType {
+    match column.rollup.as_ref().map(|config| config.function) {
+        Some(RollupFunction::Concat) => FormulaType::Text,
+        _ => FormulaType::Number,
+    }
+}
+
+/// 行の_idから位置への索引
+fn id_index(rows: &[Value]) -> HashMap<String, usize> {
+    rows.iter()
+        .enumerate()
+        .filter_map(|(position, row)| row_id(row).map(|id| (id.to_string(), position)))
+        .collect()
+}
+
+/// 二項演算を評価する（and・or以外）
+fn binary(op: BinaryOp, left: Datum, right: Datum) -> Datum {
+    use Datum::*;
+    match op {
+        BinaryOp::Concat => Text(display(&left) + &display(&right)),
+        BinaryOp::Eq => Boolean(equals(&left, &right)),
+        BinaryOp::Ne => Boolean(!equals(&left, &right)),
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let ordering = match (&left, &right) {
+                (Number(a), Number(b)) => a.partial_cmp(b),
+                (Text(a), Text(b)) => Some(a.cmp(b)),
+                (Date(a), Date(b)) => Some(a.cmp(b)),
+                _ => None,
+            };
+            ordering.map_or(Null, |ordering| {
+                Boolean(match op {
+                    BinaryOp::Lt => ordering.is_lt(),
+                    BinaryOp::Le => ordering.is_le(),
+                    BinaryOp::Gt => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                })
+            })
+        }
+        _ => match (op, left, right) {
+            (BinaryOp::Add, Number(a), Number(b)) => Number(a + b),
+            (BinaryOp::Add, Date(date), Number(days))
+            | (BinaryOp::Add, Number(days), Date(date)) => add_days(date, days).map_or(Null, Date),
+            (BinaryOp::Sub, Number(a), Number(b)) => Number(a - b),
+            (BinaryOp::Sub, Date(date), Number(days)) => add_days(date, -days).map_or(Null, Date),
+            // 日時の差は日数（小数を含む）
+            (BinaryOp::Sub, Date(a), Date(b)) => {
+                Number((a - b).num_milliseconds() as f64 / 86_400_000.0)
+            }
+            (BinaryOp::Mul, Number(a), Number(b)) => Number(a * b),
+            (BinaryOp::Div | BinaryOp::Rem, Number(_), Number(0.0)) => Null,
+            (BinaryOp::Div, Number(a), Number(b)) => Number(a / b),
+            (BinaryOp::Rem, Number(a), Number(b)) => Number(a % b),
+            _ => Null,
+        },
+    }
+}
+
+/// 値が等しいか判定する（null と空の値は等しい）
+fn equals(left: &Datum, right: &Datum) -> bool {
+    match (left, right) {
+        (Datum::Null, other) | (other, Datum::Null) => is_empty(other),
+        (Datum::List(a), Datum::List(b)) => {
+            a.iter().all(|item| b.contains(item)) && b.iter().all(|item| a.contains(item))
+        }
+        _ => left == right,
+    }
+}
+
+/// 条件として真か（nullは偽）
+fn truthy(value: &Datum) -> bool {
+    matches!(value, Datum::Boolean(true))
+}
+
+fn is_empty(value: &Datum) -> bool {
+    match value {
+        Datum::Null => true,
+        Datum::Text(text) => text.trim().is_empty(),
+        Datum::List(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+/// 日時に日数（小数を含む）を加える
+fn add_days(date: DateTime<Utc>, days: f64) -> Option<DateTime<Utc>> {
+    let milliseconds = (days * 86_400_000.0).round();
+    if !milliseconds.is_finite() {
+        return None;
+    }
+    date.checked_add_signed(chrono::Duration::try_milliseconds(milliseconds as i64)?)
+}
+
+/// 文字列を日時として読み取る（RFC3339、または YYYY-MM-DD・YYYY/MM/DD の日付）
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Some(date.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d", "%Y/%m/%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+}
+
+/// 値を表示用の文字列にする（& と text() で使用）
+fn display(value: &Datum) -> String {
+    match value {
+        Datum::Null => String::new(),
+        Datum::Text(text) => text.clone(),
+        Datum::Boolean(flag) => flag.to_string(),
+        Datum::List(items) => items.join(", "),
+        Datum::Number(_) | Datum::Date(_) => match to_json(value.clone()) {
+            Value::String(text) => text,
+            other => other.to_string(),
+        },
+    }
+}
+
+/// 評価結果をJSONの値にする（日時は0時ちょうどであれば日付のみ）
+fn to_json(value: Datum) -> Value {
+    match value {
+        Datum::Null => Value::Null,
+        Datum::Number(number) => number_value(number),
+        Datum::Text(text) => Value::String(text),
+        Datum::Boolean(flag) => Value::Bool(flag),
+        Datum::Date(date) if date.time() == NaiveTime::MIN => {
+            Value::String(date.format("%Y-%m-%d").to_string())
+        }
+        Datum::Date(date) => Value::String(date.to_rfc3339()),
+        Datum::List(items) => Value::Array(items.into_iter().map(Value::String).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 数値・文字列の列と、指定した式の計算列 f を持つスキーマ
+    fn schema_with(formula: &str) -> TableSchema {
+        let mut schema = TableSchema::new("test", "2024-01-01T00:00:00Z");
+        schema
+            .columns
+            .push(ColumnDefinition::new("price", "Price", ColumnType::Number));
+        schema
+            .columns
+            .push(ColumnDefinition::new("qty", "Qty", ColumnType::Number));
+        schema
+            .columns
+            .push(ColumnDefinition::new("name", "Name", ColumnType::Text));
+        schema
+            .columns
+            .push(ColumnDefinition::new("due", "Due", ColumnType::Text));
+        let mut column = ColumnDefinition::new("f", "F", ColumnType::Formula);
+        column.formula = Some(formula.to_string());
+        schema.columns.push(column);
+        schema
+    }
+
+    /// 1行分のデータで式を評価する
+    fn evaluate(formula: &str, row: Value) -> Value {
+        let schema = schema_with(formula);
+        let mut rows = vec![row];
+        evaluate_formulas(&mut rows, &schema, &RelatedTables::new()).unwrap();
+        rows[0]["f"].clone()
+    }
+
+    fn sample() -> Value {
+        json!({"_id": "row_a", "price": 120, "qty": 3, "name": " Tea ", "due": "2024-03-15"})
+    }
+
+    fn has_problem(formula: &str) -> bool {
+        !formula_problems(&schema_with(formula)).is_empty()
+    }
+
+    #[test]
+    fn arithmetic_follows_precedence() {
+        assert_eq!(evaluate("1 + 2 * 3", sample()), json!(7));
+        assert_eq!(evaluate("(1 + 2) * 3", sample()), json!(9));
+        assert_eq!(evaluate("-2 * 3 + 10 % 4", sample()), json!(-4));
+        assert_eq!(evaluate("price * qty - 10", sample()), json!(350));
+        assert_eq!(evaluate("1 + 2 = 3 and not 1 > 2", sample()), json!(true));
+        assert_eq!(evaluate("\"a\" & 1 + 2", sample()), json!("a3"));
+    }
+
+    #[test]
+    fn division_by_zero_is_null() {
+        assert_eq!(evaluate("price / 0", sample()), Value::Null);
+        assert_eq!(evaluate("price % 0", sample()), Value::Null);
+        assert_eq!(evaluate("price / 4", sample()), json!(30));
+    }
+
+    #[test]
+    fn text_and_conditional_functions() {
+        assert_eq!(evaluate("upper(trim(name))", sample()), json!("TEA"));
+        assert_eq!(evaluate("len({Name})", sample()), json!(5));
+        assert_eq!(
+            evaluate("if(qty >= 3, \"many\", \"few\")", sample()),
+            json!("many")
+        );
+        assert_eq!(evaluate("round(10 / 3, 2)", sample()), json!(3.33));
+        assert_eq!(
+            evaluate("is_empty(name)", json!({"_id": "row_b"})),
+            json!(true)
+        );
+    }
+
+    #[test]
+    fn date_functions() {
+        assert_eq!(
+            evaluate("year(date(due)) * 100 + month(date(due))", sample()),
+            json!(202403)
+        );
+        assert_eq!(evaluate("day(add_days(date(due), 20))", sample()), json!(4));
+        assert_eq!(
+            evaluate("add_days(date(due), 1)", sample()),
+            json!("2024-03-16")
+        );
+        assert_eq!(
+            evaluate("days_between(date(\"2024-03-01\"), date(due))", sample()),
+            json!(14)
+        );
+        assert_eq!(
+            evaluate("date(due) - date(\"2024/03/10\")", sample()),
+            json!(5)
+        );
+        assert_eq!(
+            evaluate("format_date(date(due), \"%d/%m/%Y\")", sample()),
+            json!("15/03/2024")
+        );
+        assert_eq!(evaluate("date(\"not a date\")", sample()), Value::Null);
+    }
+
+    #[test]
+    fn arity_errors_are_reported() {
+        assert!(has_problem("abs()"));
+        assert!(has_problem("abs(1, 2)"));
+        assert!(has_problem("round(1, 2, 3)"));
+        assert!(has_problem("today(1)"));
+        assert!(has_problem("unknown(1)"));
+        assert!(!has_problem("round(1)"));
+    }
+
+    #[test]
+    fn type_errors_are_reported() {
+        assert!(has_problem("price + name"));
+        assert!(has_problem("upper(price)"));
+        assert!(has_problem("year(name)"));
+        assert!(has_problem("add_days(date(due), name)"));
+        assert!(has_problem("if(price, 1, 2)"));
+        assert!(has_problem("if(true, 1, \"a\")"));
+        assert!(has_problem("-name"));
+        assert!(!has_problem("if(true, 1, null)"));
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        assert!(has_problem("1 +"));
+        assert!(has_problem("(1 + 2"));
+        assert!(has_problem("1 2"));
+        assert!(has_problem("\"open"));
+        assert!(has_problem("{missing}"));
+    }
+
+    #[test]
+    fn nesting_depth_is_limited() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(!has_problem(&nested(MAX_DEPTH - 1)));
+        assert!(has_problem(&nested(MAX_DEPTH)));
+        assert!(has_problem(&nested(10_000)));
+        assert!(has_problem(&"-".repeat(10_000)));
+        assert!(has_problem(&format!("{}true", "not ".repeat(10_000))));
+    }
+
+    #[test]
+    fn self_reference_is_a_cycle() {
+        assert!(has_problem("f + 1"));
+    }
+}
@@ -0,0 +1,800 @@
+// 保存ごとの変更履歴（追記専用ファイル）と、元に戻す・やり直す処理
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+
+use crate::error::{ErrorCode, WorkspaceError};
+use crate::rows::{changed_cells, row_id, row_id_of};
+use crate::schema::TableSchema;
+use crate::storage::write_atomically;
+use crate::tr;
+
+/// 履歴ファイル名の接尾辞（例: data.json → data.json.history.jsonl）
+const HISTORY_SUFFIX: &str = ".history.jsonl";
+
+/// 履歴ファイルの上限（超えたら古い履歴を削除して半分以下にする）
+const MAX_HISTORY_BYTES: u64 = 16 * 1024 * 1024;
+
+/// 履歴ファイルの末尾を読むときの単位
+const TAIL_CHUNK: u64 = 64 * 1024;
+
+/// 履歴の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryAction {
+    /// 保存による変更
+    Edit,
+    /// 変更を元に戻した
+    Undo,
+    /// 元に戻した変更をやり直した
+    Redo,
+}
+
+/// 1つのセルの変更
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CellChange {
+    pub column_id: String,
+    /// 変更前の値（存在しなかった場合はnull）
+    pub before: Value,
+    /// 変更後の値（削除された場合はnull）
+    pub after: Value,
+}
+
+/// 1行の変更
+/// 追加された行は `after`、削除された行は `before` に行全体を保存し、
+/// 更新された行は変更されたセルだけを `cells` に保存する
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowChange {
+    pub row_id: String,
+    /// 追加・削除された行の位置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cells: Vec<CellChange>,
+}
+
+/// 行の並び順の変更（変更前後の_idの並び）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderChange {
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// スキーマの変更（メタデータ以外が変わった場合のみ記録する）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaChange {
+    pub before: TableSchema,
+    pub after: TableSchema,
+}
+
+/// 読み込んだ履歴ファイルの内容
+#[derive(Debug, Default)]
+pub struct HistoryLog {
+    /// 古い順の履歴
+    pub entries: Vec<ChangeSet>,
+    /// 読み込めずに飛ばした行（行番号を含むエラー）
+    pub skipped: Vec<WorkspaceError>,
+}
+
+/// 1回の保存で行われた変更（履歴ファイルの1行）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeSet {
+    /// 履歴番号（1始まり）
+    pub seq: u64,
+    pub timestamp: String,
+    pub action: HistoryAction,
+    /// 元に戻した・やり直した履歴の番号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rows: Vec<RowChange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<OrderChange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<SchemaChange>,
+}
+
+/// 履歴番号だけを読み取るための履歴の一部
+#[derive(Deserialize)]
+struct SeqOnly {
+    seq: u64,
+}
+
+/// 履歴の閲覧用に1セル（または1行）ずつに分けた変更
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryItem {
+    pub seq: u64,
+    pub timestamp: String,
+    pub action: HistoryAction,
+    pub row_id: String,
+    /// セルの変更の場合はカラムID、行の追加・削除の場合はNone
+    pub column_id: Option<String>,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// 変更を適用する向き
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// 変更前の状態に戻す
+    Backward,
+    /// 変更後の状態にする
+    Forward,
+}
+
+/// データファイルに対応する履歴ファイルのパス
+pub fn history_path(data_path: &Path) -> PathBuf {
+    let mut file_name = data_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(HISTORY_SUFFIX);
+    data_path.with_file_name(file_name)
+}
+
+/// 履歴ファイルを読み込む（存在しない場合は空）
+/// 書き込みが途中で中断された末尾の行（改行で終わっていない行）は読み飛ばす
+/// 読み込めない行は飛ばし、残りの履歴は使えるようにする（履歴番号を決める read_tail と同じ扱い）
+///
+/// # 引数
+/// * `path` - 履歴ファイルのパス
+///
+/// # 戻り値
+/// 成功時は古い順の履歴と飛ばした行、ファイルを読み込めない場合はエラー
+pub fn read_history(path: &Path) -> Result<HistoryLog, WorkspaceError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(HistoryLog::default()),
+        Err(err) => return Err(WorkspaceError::io(err, path)),
+    };
+    let complete = contents.rfind('\n').map_or(0, |end| end + 1);
+    let mut log = HistoryLog::default();
+    for (index, line) in contents[..complete].lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(entry) => log.entries.push(entry),
+            Err(err) => {
+                let mut error = WorkspaceError::new(
+                    ErrorCode::InvalidJson,
+                    "history.corrupt_line",
+                    tr!(
+                        "history.corrupt_line",
+                        path = path.display(),
+                        line = index + 1,
+                        error = err
+                    ),
+                )
+                .with_path(path);
+                error.context.line = Some(index + 1);
+                log.skipped.push(error.with_source(&err));
+            }
+        }
+    }
+    Ok(log)
+}
+
+/// 保存前後の内容を比べて履歴ファイルに追記する
+/// 通常の保存で変更がなかった場合は記録しない
+/// 履歴番号はファイルの末尾だけを読んで決め、ファイルが上限を超えたら古い履歴から削除する
+///
+/// # 引数
+/// * `path` - 履歴ファイルのパス
+/// * `before` - 保存前の行データとスキーマ
+/// * `after` - 保存後の行データとスキーマ
+/// * `action` - 履歴の種類
+/// * `target` - 元に戻した・やり直した履歴の番号
+/// * `timestamp` - 保存日時
+///
+/// # 戻り値
+/// 成功時は追記した履歴の番号（記録しなかった場合はNone）、失敗時はエラー
+pub fn record_change(
+    path: &Path,
+    before: (&[Value], &TableSchema),
+    after: (&[Value], &TableSchema),
+    action: HistoryAction,
+    target: Option<u64>,
+    timestamp: &str,
+) -> Result<Option<u64>, WorkspaceError> {
+    let mut change_set = diff_tables(before, after);
+    if action == HistoryAction::Edit && is_empty(&change_set) {
+        return Ok(None);
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| WorkspaceError::io(err, path))?;
+    let io_error = |err| WorkspaceError::io(err, path);
+    let (last_seq, complete_len) = read_tail(&mut file).map_err(io_error)?;
+    // 中断された書き込みの残りを取り除いてから追記する
+    if file.metadata().map_err(io_error)?.len() > complete_len {
+        file.set_len(complete_len).map_err(io_error)?;
+    }
+
+    let seq = last_seq + 1;
+    change_set.seq = seq;
+    change_set.timestamp = timestamp.to_string();
+    change_set.action = action;
+    change_set.target = target;
+
+    let line = serde_json::to_string(&change_set).map_err(|err| WorkspaceError::json(err, path))?;
+    writeln!(file, "{line}").map_err(io_error)?;
+    file.flush().map_err(io_error)?;
+    drop(file);
+
+    if complete_len + line.len() as u64 + 1 > MAX_HISTORY_BYTES {
+        compact_history(path)?;
+    }
+    Ok(Some(seq))
+}
+
+/// 履歴を先頭から再生し、元に戻せる履歴とやり直せる履歴の番号を求める
+/// どちらも末尾が次に対象となる履歴
+pub fn undo_stacks(entries: &[ChangeSet]) -> (Vec<u64>, Vec<u64>) {
+    let mut undo = Vec::new();
+    let mut redo = Vec::new();
+    let mut recorded = HashSet::new();
+    for entry in entries {
+        // 上限を超えて削除された履歴を対象とする取り消し・やり直しは無視する
+        if entry
+            .target
+            .is_some_and(|target| !recorded.contains(&target))
+        {
+            continue;
+        }
+        match (entry.action, entry.target) {
+            (HistoryAction::Edit, _) => {
+                recorded.insert(entry.seq);
+                undo.push(entry.seq);
+                redo.clear();
+            }
+            (HistoryAction::Undo, Some(target)) => {
+                undo.retain(|seq| *seq != target);
+                redo.push(target);
+            }
+            (HistoryAction::Redo, Some(target)) => {
+                redo.retain(|seq| *seq != target);
+                undo.push(target);
+            }
+            _ => {}
+        }
+    }
+    (undo, redo)
+}
+
+/// 履歴の変更を行データとスキーマに適用する
+/// セル単位で適用するため、その後に別の場所で変更されたセルは残る
+///
+/// # 引数
+/// * `rows` - 行データの可変参照
+/// * `schema` - スキーマの可変参照
+/// * `change_set` - 適用する履歴
+/// * `direction` - 元に戻すか、やり直すか
+/// * `timestamp` - 変更された行に設定する更新日時
+pub fn apply_change_set(
+    rows: &mut Vec<Value>,
+    schema: &mut TableSchema,
+    change_set: &ChangeSet,
+    direction: Direction,
+    timestamp: &str,
+) {
+    let pick = |before: &Value, after: &Value| match direction {
+        Direction::Backward => before.clone(),
+        Direction::Forward => after.clone(),
+    };
+    let mut touched = HashSet::new();
+
+    for change in &change_set.rows {
+        let (from, to) = match direction {
+            Direction::Backward => (&change.after, &change.before),
+            Direction::Forward => (&change.before, &change.after),
+        };
+        let position = position_of(rows, &change.row_id);
+        match (from, to, position) {
+            // 行を削除する
+            (Some(_), None, Some(position)) => {
+                rows.remove(position);
+            }
+            // 行を作り直す（既にある場合は置き換える）
+            (None, Some(row), position) => {
+                match position {
+                    Some(position) => rows[position] = row.clone(),
+                    None => {
+                        let index = change.index.unwrap_or(rows.len()).min(rows.len());
+                        rows.insert(index, row.clone());
+                    }
+                }
+                touched.insert(change.row_id.clone());
+            }
+            // セルを書き戻す（行が既に削除されている場合は何もしない）
+            (_, _, Some(position)) => {
+                if let Value::Object(object) = &mut rows[position] {
+                    for cell in &change.cells {
+                        match pick(&cell.before, &cell.after) {
+                            Value::Null => object.remove(&cell.column_id),
+                            value => object.insert(cell.column_id.clone(), value),
+                        };
+                    }
+                    touched.insert(change.row_id.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(order) = &change_set.order {
+        let target = match direction {
+            Direction::Backward => &order.before,
+            Direction::Forward => &order.after,
+        };
+        let ranks: HashMap<&str, usize> = target
+            .iter()
+            .enumerate()
+            .map(|(rank, id)| (id.as_str(), rank))
+            .collect();
+        // 並びにない行は元の相対順のまま末尾へ
+        rows.sort_by_key(|row| {
+            row_id(row)
+                .and_then(|id| ranks.get(id).copied())
+                .unwrap_or(usize::MAX)
+        });
+    }
+
+    if let Some(change) = &change_set.schema {
+        let target = match direction {
+            Direction::Backward => &change.before,
+            Direction::Forward => &change.after,
+        };
+        let metadata = schema.metadata.clone();
+        *schema = target.clone();
+        schema.metadata = metadata;
+    }
+
+    for (index, row) in rows.iter_mut().enumerate() {
+        if let Value::Object(object) = row {
+            object.insert("_order".into(), Value::Number(Number::from(index as u64)));
+            if row_id_of(object).is_some_and(|id| touched.contains(id)) {
+                object.insert("_updated".into(), Value::String(timestamp.to_string()));
+            }
+        }
+    }
+}
+
+/// 履歴を1セル（または1行）ずつの変更に分けて、新しい順に返す
+///
+/// # 引数
+/// * `entries` - 古い順の履歴
+/// * `row_id` - 指定した場合はその行の変更のみ
+/// * `column_id` - 指定した場合はそのカラムの変更のみ
+/// * `limit` - 返す件数の上限
+pub fn history_items(
+    entries: &[ChangeSet],
+    row_id: Option<&str>,
+    column_id: Option<&str>,
+    limit: usize,
+) -> Vec<HistoryItem> {
+    let mut items = Vec::new();
+    for entry in entries.iter().rev() {
+        for change in entry
+            .rows
+            .iter()
+            .filter(|change| row_id.is_none_or(|id| change.row_id == id))
+        {
+            let item = |column_id: Option<String>, before: Value, after: Value| HistoryItem {
+                seq: entry.seq,
+                timestamp: entry.timestamp.clone(),
+                action: entry.action,
+                row_id: change.row_id.clone(),
+                column_id,
+                before,
+                after,
+            };
+            if change.before.is_some() || change.after.is_some() {
+                let before = change.before.clone().unwrap_or(Value::Null);
+                let after = change.after.clone().unwrap_or(Value::Null);
+                match column_id {
+                    // 行の追加・削除は、そのカラムの値の変化として扱う
+                    Some(column_id) => {
+                        let before = before.get(column_id).cloned().unwrap_or(Value::Null);
+                        let after = after.get(column_id).cloned().unwrap_or(Value::Null);
+                        if before != after {
+                            items.push(item(Some(column_id.to_string()), before, after));
+                        }
+                    }
+                    None => items.push(item(None, before, after)),
+                }
+            } else {
+                for cell in change
+                    .cells
+                    .iter()
+                    .filter(|cell| column_id.is_none_or(|id| cell.column_id == id))
+                {
+                    items.push(item(
+                        Some(cell.column_id.clone()),
+                        cell.before.clone(),
+                        cell.after.clone(),
+                    ));
+                }
+            }
+            if items.len() >= limit {
+                items.truncate(limit);
+                return items;
+            }
+        }
+    }
+    items
+}
+
+/// 保存前後の行データとスキーマの差分を求める（seq等は呼び出し側で設定する）
+fn diff_tables(
+    (before_rows, before_schema): (&[Value], &TableSchema),
+    (after_rows, after_schema): (&[Value], &TableSchema),
+) -> ChangeSet {
+    let before_index: HashMap<&str, (usize, &Value)> = before_rows
+        .iter()
+        .enumerate()
+        .filter_map(|(index, row)| row_id(row).map(|id| (id, (index, row))))
+        .collect();
+    let after_ids: HashSet<&str> = after_rows.iter().filter_map(row_id).collect();
+
+    let mut rows = Vec::new();
+    for (index, row) in after_rows.iter().enumerate() {
+        let Some(id) = row_id(row) else {
+            continue;
+        };
+        match before_index.get(id) {
+            None => rows.push(RowChange {
+                row_id: id.to_string(),
+                index: Some(index),
+                before: None,
+                after: Some(row.clone()),
+                cells: Vec::new(),
+            }),
+            Some((_, previous)) => {
+                let cells = diff_cells(previous, row);
+                if !cells.is_empty() {
+                    rows.push(RowChange {
+                        row_id: id.to_string(),
+                        index: None,
+                        before: None,
+                        after: None,
+                        cells,
+                    });
+                }
+            }
+        }
+    }
+    for (index, row) in before_rows.iter().enumerate() {
+        if let Some(id) = row_id(row).filter(|id| !after_ids.contains(id)) {
+            rows.push(RowChange {
+                row_id: id.to_string(),
+                index: Some(index),
+                before: Some(row.clone()),
+                after: None,
+                cells: Vec::new(),
+            });
+        }
+    }
+
+    // 両方にある行の相対順が変わった場合のみ並び順を記録する
+    let before_order: Vec<String> = before_rows
+        .iter()
+        .filter_map(row_id)
+        .map(str::to_string)
+        .collect();
+    let after_order: Vec<String> = after_rows
+        .iter()
+        .filter_map(row_id)
+        .map(str::to_string)
+        .collect();
+    let common_before = before_order
+        .iter()
+        .filter(|id| after_ids.contains(id.as_str()));
+    let common_after = after_order
+        .iter()
+        .filter(|id| before_index.contains_key(id.as_str()));
+    let order = (!common_before.eq(common_after)).then_some(OrderChange {
+        before: before_order,
+        after: after_order,
+    });
+
+    let schema = (schema_body(before_schema) != schema_body(after_schema)).then(|| SchemaChange {
+        before: before_schema.clone(),
+        after: after_schema.clone(),
+    });
+
+    ChangeSet {
+        seq: 0,
+        timestamp: String::new(),
+        action: HistoryAction::Edit,
+        target: None,
+        rows,
+        order,
+        schema,
+    }
+}
+
+/// 2つの行で値が異なるセルを求める（nullはキーがないものとして扱う）
+fn diff_cells(before: &Value, after: &Value) -> Vec<CellChange> {
+    changed_cells(before, after)
+        .into_iter()
+        .map(|(column_id, before, after)| CellChange {
+            column_id,
+            before,
+            after,
+        })
+        .collect()
+}
+
+/// メタデータ（更新日時・行数）を除いたスキーマの内容
+fn schema_body(schema: &TableSchema) -> Value {
+    let mut value = serde_json::to_value(schema).unwrap_or(Value::Null);
+    if let Value::Object(object) = &mut value {
+        object.remove("metadata");
+    }
+    value
+}
+
+fn is_empty(change_set: &ChangeSet) -> bool {
+    change_set.rows.is_empty() && change_set.order.is_none() && change_set.schema.is_none()
+}
+
+/// 履歴ファイルを末尾から読み、最後の履歴の番号と改行で終わっている部分の長さを求める
+/// 読み込めない行は飛ばしてその前の行を使う
+fn read_tail(file: &mut File) -> io::Result<(u64, u64)> {
+    let mut position = file.metadata()?.len();
+    // まだ調べていない部分（先頭の行は途中から始まっている場合がある）
+    let mut pending: Vec<u8> = Vec::new();
+    let mut complete_len = None;
+    while position > 0 {
+        let size = TAIL_CHUNK.min(position);
+        position -= size;
+        let mut chunk = vec![0; size as usize];
+        file.seek(SeekFrom::Start(position))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&pending);
+        pending = chunk;
+
+        if complete_len.is_none() {
+            let Some(end) = pending.iter().rposition(|byte| *byte == b'\n') else {
+                continue;
+            };
+            complete_len = Some(position + end as u64 + 1);
+            pending.truncate(end + 1);
+        }
+        let start = if position == 0 {
+            0
+        } else {
+            match pending.iter().position(|byte| *byte == b'\n') {
+                Some(index) => index + 1,
+                None => continue,
+            }
+        };
+        for line in pending[start..].split(|byte| *byte == b'\n').rev() {
+            if let Ok(entry) = serde_json::from_slice::<SeqOnly>(line) {
+                return Ok((entry.seq, complete_len.unwrap_or(0)));
+            }
+        }
+        pending.truncate(start);
+    }
+    Ok((0, complete_len.unwrap_or(0)))
+}
+
+/// 履歴ファイルが上限の半分以下になるよう古い履歴を削除する（最新の履歴は必ず残す）
+fn compact_history(path: &Path) -> Result<(), WorkspaceError> {
+    let contents = fs::read_to_string(path).map_err(|err| WorkspaceError::io(err, path))?;
+    let mut size = 0;
+    let mut kept: Vec<&str> = contents
+        .lines()
+        .rev()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .take_while(|(index, line)| {
+            size += line.len() as u64 + 1;
+            *index == 0 || size <= MAX_HISTORY_BYTES / 2
+        })
+        .map(|(_, line)| line)
+        .collect();
+    kept.reverse();
+    let mut compacted = kept.join("\n");
+    compacted.push('\n');
+    write_atomically(path, compacted)
+}
+
+fn position_of(rows: &[Value], id: &str) -> Option<usize> {
+    rows.iter().position(|row| row_id(row) == Some(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> TableSchema {
+        TableSchema::new("test", "2024-01-01T00:00:00Z")
+    }
+
+    fn row(id: &str, name: &str) -> Value {
+        json!({"_id": id, "_order": 0, "name": name})
+    }
+
+    /// 行データを書き換えて履歴に記録する
+    fn edit(path: &Path, rows: &mut Vec<Value>, next: Vec<Value>) -> Option<u64> {
+        let schema = schema();
+        let seq = record_change(
+            path,
+            (rows, &schema),
+            (&next, &schema),
+            HistoryAction::Edit,
+            None,
+            "2024-01-01T00:00:00Z",
+        )
+        .unwrap();
+        *rows = next;
+        seq
+    }
+
+    #[test]
+    fn records_only_changes_with_increasing_seq() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.json.history.jsonl");
+        let mut rows = vec![row("row_a", "A")];
+        assert_eq!(edit(&path, &mut rows, vec![row("row_a", "A")]), None);
+        assert_eq!(edit(&path, &mut rows, vec![row("row_a", "B")]), Some(1));
+        assert_eq!(
+            edit(&path, &mut rows, vec![row("row_a", "B"), row("row_b", "C")]),
+            Some(2)
+        );
+
+        let entries = read_history(&path).unwrap().entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].rows[0].cells[0].column_id, "name");
+        assert_eq!(entries[1].rows[0].after, Some(row("row_b", "C")));
+    }
+
+    #[test]
+    fn undo_and_redo_restore_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.json.history.jsonl");
+        let mut rows = vec![row("row_a", "A")];
+        edit(&path, &mut rows, vec![row("row_a", "B")]);
+        edit(&path, &mut rows, vec![row("row_a", "B"), row("row_b", "C")]);
+
+        let entries = read_history(&path).unwrap().entries;
+        assert_eq!(undo_stacks(&entries), (vec![1, 2], vec![]));
+
+        let mut schema = schema();
+        apply_change_set(
+            &mut rows,
+            &mut schema,
+            &entries[1],
+            Direction::Backward,
+            "t",
+        );
+        assert_eq!(rows.len(), 1);
+        apply_change_set(
+            &mut rows,
+            &mut schema,
+            &entries[0],
+            Direction::Backward,
+            "t",
+        );
+        assert_eq!(rows[0]["name"], "A");
+        apply_change_set(&mut rows, &mut schema, &entries[0], Direction::Forward, "t");
+        assert_eq!(rows[0]["name"], "B");
+        assert_eq!(rows[0]["_updated"], "t");
+
+        let mut entries = entries;
+        let mut undo = entries[1].clone();
+        undo.seq = 3;
+        undo.action = HistoryAction::Undo;
+        undo.target = Some(2);
+        entries.push(undo);
+        assert_eq!(undo_stacks(&entries), (vec![1], vec![2]));
+        let mut redo = entries[1].clone();
+        redo.seq = 4;
+        redo.action = HistoryAction::Redo;
+        redo.target = Some(2);
+        entries.push(redo);
+        assert_eq!(undo_stacks(&entries), (vec![1, 2], vec![]));
+    }
+
+    #[test]
+    fn corrupt_lines_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.json.history.jsonl");
+        let mut rows = vec![row("row_a", "A")];
+        edit(&path, &mut rows, vec![row("row_a", "B")]);
+        let mut contents = fs::read_to_string(&path).unwrap();
+        contents.push_str("{broken\n");
+        fs::write(&path, &contents).unwrap();
+
+        // 壊れた行があっても番号は最後に読める履歴の続きになる
+        assert_eq!(edit(&path, &mut rows, vec![row("row_a", "C")]), Some(2));
+
+        // 途中の壊れた行だけを飛ばし、前後の履歴は読める
+        let log = read_history(&path).unwrap();
+        assert_eq!(
+            log.entries
+                .iter()
+                .map(|entry| entry.seq)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(log.skipped.len(), 1);
+        assert_eq!(log.skipped[0].message_key, "history.corrupt_line");
+        assert_eq!(log.skipped[0].context.line, Some(2));
+        let (undo, _) = undo_stacks(&log.entries);
+        assert_eq!(undo, vec![1, 2]);
+    }
+
+    #[test]
+    fn interrupted_write_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.json.history.jsonl");
+        let mut rows = vec![row("row_a", "A")];
+        edit(&path, &mut rows, vec![row("row_a", "B")]);
+        let mut contents = fs::read_to_string(&path).unwrap();
+        contents.push_str("{\"seq\":2,\"timest");
+        fs::write(&path, &contents).unwrap();
+
+        let log = read_history(&path).unwrap();
+        assert_eq!(log.entries.len(), 1);
+        assert!(log.skipped.is_empty());
+        assert_eq!(edit(&path, &mut rows, vec![row("row_a", "C")]), Some(2));
+        let entries = read_history(&path).unwrap().entries;
+        assert_eq!(
+            entries.iter().map(|entry| entry.seq).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn last_seq_is_found_across_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.json.history.jsonl");
+        let mut rows = vec![row("row_a", "A")];
+        let long = "x".repeat(TAIL_CHUNK as usize * 2);
+        edit(&path, &mut rows, vec![row("row_a", &long)]);
+        assert_eq!(edit(&path, &mut rows, vec![row("row_a", "B")]), Some(2));
+        assert_eq!(edit(&path, &mut rows, vec![row("row_a", &long)]), Some(3));
+        assert_eq!(edit(&path, &mut rows, vec![row("row_a", "C")]), Some(4));
+    }
+
+    #[test]
+    fn compaction_keeps_latest_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.json.history.jsonl");
+        let mut rows = vec![row("row_a", "A")];
+        let long = "x".repeat(MAX_HISTORY_BYTES as usize / 8);
+        for index in 0..5 {
+            let name = format!("{index}{long}");
+            edit(&path, &mut rows, vec![row("row_a", &name)]);
+        }
+
+        assert!(fs::metadata(&path).unwrap().len() <= MAX_HISTORY_BYTES);
+        let entries = read_history(&path).unwrap().entries;
+        assert_eq!(entries.last().map(|entry| entry.seq), Some(5));
+        assert!(entries.len() < 5);
+
+        // 削除された履歴を対象とする取り消しは無視される
+        let mut undo = entries[0].clone();
+        undo.seq = 6;
+        undo.action = HistoryAction::Undo;
+        undo.target = Some(1);
+        let mut entries = entries;
+        entries.push(undo);
+        let (undo, redo) = undo_stacks(&entries);
+        assert_eq!(undo.last(), Some(&5));
+        assert!(redo.is_empty());
+    }
+}
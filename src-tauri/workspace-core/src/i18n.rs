@@ -0,0 +1,852 @@
+// 利用者向けのメッセージのカタログ（日本語・英語）と、表示する言語の切り替え
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// メッセージの言語
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    Ja,
+    En,
+}
+
+impl Locale {
+    /// 言語タグ（"ja-JP"、"en_US.UTF-8"など）から言語を選ぶ
+    /// 日本語以外はすべて英語として扱う
+    pub fn from_tag(tag: &str) -> Self {
+        let language = tag
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if language == "ja" {
+            Locale::Ja
+        } else {
+            Locale::En
+        }
+    }
+
+    /// 言語タグ
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Locale::Ja => "ja",
+            Locale::En => "en",
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// 現在の言語（Locale::Ja = 0, Locale::En = 1）
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// 現在の言語
+pub fn current_locale() -> Locale {
+    match CURRENT.load(Ordering::Relaxed) {
+        0 => Locale::Ja,
+        _ => Locale::En,
+    }
+}
+
+/// 以降のメッセージの言語を設定する
+pub fn set_locale(locale: Locale) {
+    let value = match locale {
+        Locale::Ja => 0,
+        Locale::En => 1,
+    };
+    CURRENT.store(value, Ordering::Relaxed);
+}
+
+/// OSの言語設定から言語を選ぶ（取得できない場合は日本語）
+pub fn detect_locale() -> Locale {
+    sys_locale::get_locale()
+        .map(|tag| Locale::from_tag(&tag))
+        .unwrap_or(Locale::Ja)
+}
+
+/// キーに対応するメッセージを現在の言語で作る
+/// `{name}`の部分は引数の値に置き換える（カタログにないキーはそのまま返す）
+///
+/// # 引数
+/// * `key` - メッセージのキー
+/// * `args` - 埋め込む値の名前と値
+pub fn text(key: &str, args: &[(&str, String)]) -> String {
+    let Some(template) = template(key, current_locale()) else {
+        return key.to_string();
+    };
+    let args: HashMap<&str, &str> = args
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .collect();
+
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after
+            .find('}')
+            .and_then(|end| args.get(&after[..end]).map(|value| (end, value)))
+        {
+            Some((end, value)) => {
+                result.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// キーに対応するメッセージを現在の言語で作る
+///
+/// ```ignore
+/// tr!("error.file_not_found", path = path.display())
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::text($key, &[$((stringify!($name), $value.to_string())),*])
+    };
+}
+
+/// 指定した言語のメッセージの書式
+fn template(key: &str, locale: Locale) -> Option<&'static str> {
+    MESSAGES
+        .iter()
+        .find(|(candidate, _, _)| *candidate == key)
+        .map(|(_, ja, en)| match locale {
+            Locale::Ja => *ja,
+            Locale::En => *en,
+        })
+}
+
+/// メッセージのカタログ（キー、日本語、英語）
+const MESSAGES: &[(&str, &str, &str)] = &[
+    // コマンドのエラー（WorkspaceError::message_key）
+    (
+        "error.file_not_found",
+        "ファイルが見つかりません: {path}",
+        "File not found: {path}",
+    ),
+    (
+        "error.permission_denied",
+        "ファイルへのアクセスが許可されていません: {path}",
+        "Permission denied: {path}",
+    ),
+    (
+        "error.io",
+        "ファイルを読み書きできません: {path}",
+        "Could not read or write the file: {path}",
+    ),
+    (
+        "error.invalid_json",
+        "JSONの形式が正しくありません: {path}",
+        "Invalid JSON: {path}",
+    ),
+    (
+        "error.invalid_json_at",
+        "JSONの形式が正しくありません: {path} ({line}行 {column}列)",
+        "Invalid JSON: {path} (line {line}, column {column})",
+    ),
+    (
+        "error.database",
+        "データベースを操作できません: {path}",
+        "Database operation failed: {path}",
+    ),
+    (
+        "error.conflict",
+        "読み込み後にファイルが他で更新されています",
+        "The file was modified elsewhere after it was loaded",
+    ),
+    (
+        "error.workspace_missing",
+        "指定されたデータファイルが存在しません",
+        "The specified data file does not exist",
+    ),
+    (
+        "error.workspace_not_loaded",
+        "ワークスペースが開かれていません: {id}",
+        "Workspace not loaded: {id}",
+    ),
+    (
+        "error.path_required",
+        "ファイルパスを指定してください",
+        "Please specify a file path",
+    ),
+    (
+        "error.file_name_required",
+        "ファイル名を入力してください",
+        "Please enter a file name",
+    ),
+    (
+        "error.file_name_unavailable",
+        "ファイル名を取得できません",
+        "Could not determine the file name",
+    ),
+    (
+        "error.folder_unavailable",
+        "保存先フォルダを取得できません",
+        "Could not determine the destination folder",
+    ),
+    (
+        "error.data_file_name_unavailable",
+        "データファイル名を取得できません",
+        "Could not determine the data file name",
+    ),
+    (
+        "error.parent_unavailable",
+        "親ディレクトリを取得できません",
+        "Could not determine the parent directory",
+    ),
+    (
+        "error.already_exists",
+        "同名のファイルが既に存在します",
+        "A file with the same name already exists",
+    ),
+    (
+        "error.folder_not_found",
+        "フォルダが見つかりません: {path}",
+        "Folder not found: {path}",
+    ),
+    (
+        "error.data_not_array",
+        "データファイルの形式が正しくありません（配列ではありません）: {path}",
+        "Invalid data file format (not an array): {path}",
+    ),
+    (
+        "error.schema_format",
+        "スキーマファイルの形式が正しくありません ({line}行 {column}列)",
+        "Invalid schema file format (line {line}, column {column})",
+    ),
+    (
+        "error.schema_missing",
+        "データベースにスキーマが保存されていません",
+        "No schema is stored in the database",
+    ),
+    (
+        "error.row_corrupted",
+        "行 {id} のデータが壊れています",
+        "The data of row {id} is corrupted",
+    ),
+    (
+        "error.row_id_missing",
+        "{row} 行目に _id がありません",
+        "Row {row} has no _id",
+    ),
+    (
+        "error.backup_id_invalid",
+        "バックアップIDが不正です: {id}",
+        "Invalid backup ID: {id}",
+    ),
+    (
+        "error.backup_not_found",
+        "バックアップが見つかりません: {id}",
+        "Backup not found: {id}",
+    ),
+    (
+        "error.same_storage_kind",
+        "変換元と変換先の形式が同じです",
+        "The source and target formats are the same",
+    ),
+    (
+        "error.watch_failed",
+        "ファイルの監視を開始できません: {path}",
+        "Could not start watching the file: {path}",
+    ),
+    (
+        "error.window_failed",
+        "ウィンドウを開けません",
+        "Could not open the window",
+    ),
+    (
+        "error.task_failed",
+        "処理を完了できませんでした",
+        "The operation could not be completed",
+    ),
+    // ローカルAPI
+    (
+        "error.api_start_failed",
+        "ローカルAPIを開始できません: {address}",
+        "Could not start the local API: {address}",
+    ),
+    (
+        "error.unauthorized",
+        "トークンが指定されていないか、正しくありません",
+        "The token is missing or invalid",
+    ),
+    (
+        "error.api_route_not_found",
+        "エンドポイントが見つかりません: {method} {path}",
+        "Endpoint not found: {method} {path}",
+    ),
+    (
+        "error.api_body_invalid",
+        "リクエストの本文をJSONとして読み込めません",
+        "Could not read the request body as JSON",
+    ),
+    (
+        "error.api_body_too_large",
+        "リクエストの本文が大きすぎます（上限 {limit} バイト）",
+        "The request body is too large (limit: {limit} bytes)",
+    ),
+    (
+        "error.api_body_timeout",
+        "リクエストの本文を制限時間内に読み込めませんでした",
+        "The request body was not received in time",
+    ),
+    (
+        "error.api_if_match_required",
+        "書き込みには、読み込んだときのリビジョンをIf-Matchヘッダーで指定してください（If-Match: * は使えません）",
+        "Writes require the revision you read in the If-Match header (If-Match: * is not accepted)",
+    ),
+    (
+        "error.api_busy",
+        "処理中のリクエストが多すぎます。しばらくしてから再試行してください",
+        "Too many requests in progress; try again shortly",
+    ),
+    (
+        "error.api_response_failed",
+        "応答を作成できません",
+        "Could not build the response",
+    ),
+    (
+        "error.api_rows_invalid",
+        "追加する行はオブジェクト、またはオブジェクトの配列で指定してください",
+        "Rows to insert must be an object or an array of objects",
+    ),
+    (
+        "error.api_parameter_invalid",
+        "パラメーター {name} の値が正しくありません: {value}",
+        "Invalid value for parameter {name}: {value}",
+    ),
+    // 行データの検証
+    (
+        "validation.invalid_rows",
+        "行データが不正です: {details}",
+        "Invalid row data: {details}",
+    ),
+    (
+        "validation.row",
+        "行 {row}: {message}",
+        "Row {row}: {message}",
+    ),
+    ("validation.more", "ほか {count} 件", "and {count} more"),
+    (
+        "validation.not_object",
+        "行がオブジェクトではありません",
+        "The row is not an object",
+    ),
+    (
+        "validation.invalid_id",
+        "_id は空でない文字列である必要があります",
+        "_id must be a non-empty string",
+    ),
+    (
+        "validation.duplicate_id",
+        "_id が重複しています: {id}",
+        "Duplicate _id: {id}",
+    ),
+    (
+        "validation.required_missing",
+        "必須カラム「{column}」が空です",
+        "Required column \"{column}\" is empty",
+    ),
+    (
+        "validation.type_mismatch",
+        "カラム「{column}」は {type} 型ですが、値 {value} は一致しません",
+        "Column \"{column}\" is of type {type}, but the value {value} does not match",
+    ),
+    // スキーマの検証
+    (
+        "schema.invalid",
+        "スキーマが不正です: {problems}",
+        "Invalid schema: {problems}",
+    ),
+    (
+        "schema.version_empty",
+        "version が空です",
+        "version is empty",
+    ),
+    (
+        "schema.version_unsupported",
+        "サポートされていないスキーマバージョンです: {version} (対応: {supported})",
+        "Unsupported schema version: {version} (supported: {supported})",
+    ),
+    (
+        "schema.table_name_empty",
+        "table_name が空です",
+        "table_name is empty",
+    ),
+    (
+        "schema.columns_empty",
+        "columns が空です",
+        "columns is empty",
+    ),
+    (
+        "schema.column_id_empty",
+        "columns[{index}] の id が空です",
+        "columns[{index}] has an empty id",
+    ),
+    (
+        "schema.column_id_duplicate",
+        "カラムIDが重複しています: {id}",
+        "Duplicate column ID: {id}",
+    ),
+    (
+        "schema.column_name_empty",
+        "カラム {id} の name が空です",
+        "Column {id} has an empty name",
+    ),
+    (
+        "schema.column_width_zero",
+        "カラム {id} の width が 0 です",
+        "Column {id} has a width of 0",
+    ),
+    (
+        "schema.relation_target_empty",
+        "カラム {id} の参照先が空です",
+        "Column {id} has no relation target",
+    ),
+    (
+        "schema.relation_target_path",
+        "カラム {id} の参照先はワークスペースのフォルダからの相対パスで指定してください（..や.で始まる名前は使えません）: {target}",
+        "The relation target of column {id} must be a path relative to the workspace folder, without .. or names starting with a dot: {target}",
+    ),
+    (
+        "schema.id_column_missing",
+        "_id 列が定義されていません",
+        "The _id column is not defined",
+    ),
+    (
+        "schema.lookup_column_missing",
+        "lookup列 {id} に表示するカラムが設定されていません",
+        "Lookup column {id} has no column to display",
+    ),
+    (
+        "schema.rollup_column_missing",
+        "rollup列 {id} に集計するカラムが設定されていません",
+        "Rollup column {id} has no column to aggregate",
+    ),
+    (
+        "schema.not_relation",
+        "カラム {id} の {relation} はリレーションのカラムではありません",
+        "{relation} used by column {id} is not a relation column",
+    ),
+    (
+        "schema.relation_column_empty",
+        "カラム {id} のリレーション先のカラムが空です",
+        "Column {id} has no column of the related table",
+    ),
+    (
+        "column.not_found",
+        "カラムが見つかりません: {column}",
+        "Column not found: {column}",
+    ),
+    // 差分操作
+    (
+        "changes.failed",
+        "操作 {step} ({operation}) を適用できません: {message}",
+        "Could not apply operation {step} ({operation}): {message}",
+    ),
+    (
+        "changes.row_not_found",
+        "行が見つかりません: {id}",
+        "Row not found: {id}",
+    ),
+    (
+        "changes.row_not_object",
+        "行がオブジェクトではありません: {id}",
+        "The row is not an object: {id}",
+    ),
+    (
+        "changes.system_column_update",
+        "システム列は更新できません: {column}",
+        "System columns cannot be updated: {column}",
+    ),
+    (
+        "changes.system_column_delete",
+        "システム列は削除できません: {column}",
+        "System columns cannot be deleted: {column}",
+    ),
+    // 集計・検索
+    (
+        "aggregate.column_required",
+        "{function} にはカラムを指定してください",
+        "{function} requires a column",
+    ),
+    (
+        "aggregate.numeric_only",
+        "{function} は number か checkbox のカラムにのみ使用できます: {column}",
+        "{function} can only be used on number or checkbox columns: {column}",
+    ),
+    (
+        "aggregate.duplicate_key",
+        "集計のキーが重複しています: {key}",
+        "Duplicate aggregation key: {key}",
+    ),
+    (
+        "query.range_bounds",
+        "範囲の条件に min か max を指定してください: {column}",
+        "Specify min or max for the range filter: {column}",
+    ),
+    // 計算列
+    (
+        "formula.invalid",
+        "計算列が不正です: {problems}",
+        "Invalid formula columns: {problems}",
+    ),
+    (
+        "formula.missing",
+        "計算列 {id} に式が設定されていません",
+        "Formula column {id} has no expression",
+    ),
+    (
+        "formula.column_error",
+        "計算列 {id} の式: {error}",
+        "Expression of formula column {id}: {error}",
+    ),
+    (
+        "formula.column_invalid",
+        "計算列 {id} の式が不正です",
+        "The expression of formula column {id} is invalid",
+    ),
+    (
+        "formula.cycle",
+        "計算列が循環参照しています: {columns}",
+        "Formula columns reference each other in a cycle: {columns}",
+    ),
+    (
+        "formula.at",
+        "{position}文字目: {message}",
+        "Character {position}: {message}",
+    ),
+    (
+        "formula.unclosed_string",
+        "文字列が閉じられていません",
+        "Unterminated string",
+    ),
+    (
+        "formula.unclosed_column",
+        "カラムの参照が } で閉じられていません",
+        "The column reference is not closed with }",
+    ),
+    (
+        "formula.invalid_number",
+        "数値として読み取れません: {text}",
+        "Not a valid number: {text}",
+    ),
+    (
+        "formula.invalid_char",
+        "使用できない文字です: {char}",
+        "Unexpected character: {char}",
+    ),
+    (
+        "formula.trailing",
+        "式の終わりに余分な記述があります",
+        "Unexpected input after the end of the expression",
+    ),
+    (
+        "formula.too_deep",
+        "括弧・関数・演算子の入れ子が深すぎます（上限 {max} 段）",
+        "Nesting of parentheses, functions and operators is too deep (limit {max})",
+    ),
+    ("formula.expected", "{token} が必要です", "Expected {token}"),
+    ("formula.comma_or_paren", ", または )", ", or )"),
+    (
+        "formula.value_expected",
+        "値・カラム・関数が必要です",
+        "Expected a value, column or function",
+    ),
+    (
+        "formula.unexpected_end",
+        "式が途中で終わっています",
+        "The expression ends unexpectedly",
+    ),
+    (
+        "formula.rollup_usage_count",
+        "{name}(リレーションのカラム) の形式で指定してください",
+        "Use the form {name}(relation column)",
+    ),
+    (
+        "formula.rollup_usage",
+        "{name}(リレーションのカラム, 集計するカラム) の形式で指定してください",
+        "Use the form {name}(relation column, column to aggregate)",
+    ),
+    ("formula.unary_minus", "単項 -", "unary -"),
+    (
+        "formula.operator_types",
+        "演算子 {operator} は {left} と {right} の組み合わせに使用できません",
+        "Operator {operator} cannot be used with {left} and {right}",
+    ),
+    (
+        "formula.not_relation",
+        "{column} はリレーションのカラムではありません",
+        "{column} is not a relation column",
+    ),
+    (
+        "formula.rollup_numeric",
+        "集計するカラム {column} は number か checkbox である必要があります",
+        "The aggregated column {column} must be number or checkbox",
+    ),
+    (
+        "formula.arity_exact",
+        "{name} の引数は{count}個です",
+        "{name} takes {count} arguments",
+    ),
+    (
+        "formula.arity_range",
+        "{name} の引数は{min}〜{max}個です",
+        "{name} takes {min} to {max} arguments",
+    ),
+    ("formula.if_condition", "if の条件", "the if condition"),
+    (
+        "formula.if_branches",
+        "if の2つの結果の型が一致しません: {then} と {otherwise}",
+        "The two results of if have different types: {then} and {otherwise}",
+    ),
+    (
+        "formula.unknown_function",
+        "関数が見つかりません: {name}",
+        "Unknown function: {name}",
+    ),
+    (
+        "formula.type_expected",
+        "{context} には {allowed} が必要ですが {actual} が指定されています",
+        "{context} requires {allowed}, but {actual} was given",
+    ),
+    ("formula.or", " か ", " or "),
+    // リレーション
+    (
+        "relation.target_outside",
+        "参照先 {target} はワークスペースのフォルダの外を指しています",
+        "Relation target {target} points outside the workspace folder",
+    ),
+    (
+        "relation.target_unreadable",
+        "参照先 {target} を読み込めません: {error}",
+        "Could not read the related table {target}: {error}",
+    ),
+    (
+        "relation.missing_rows",
+        "カラム「{column}」の参照先の行が見つかりません: {ids}",
+        "Referenced rows of column \"{column}\" were not found: {ids}",
+    ),
+    (
+        "relation.restricted",
+        "{table} の行 {row} がカラム「{column}」で参照しているため削除できません",
+        "Cannot delete because row {row} of {table} references it in column \"{column}\"",
+    ),
+    // 読み込み・書き出し
+    (
+        "delimited.record",
+        "{row} 行目を読み込めません: {error}",
+        "Could not read line {row}: {error}",
+    ),
+    (
+        "delimited.invalid_delimiter",
+        "区切り文字には使用できません: {delimiter}",
+        "Cannot be used as a delimiter: {delimiter}",
+    ),
+    (
+        "delimited.decode",
+        "ファイルを {encoding} として読み込めません。文字コードを確認してください",
+        "Could not read the file as {encoding}. Please check the character encoding",
+    ),
+    (
+        "xlsx.unreadable",
+        "Excelファイルとして読み込めません: {path}",
+        "Could not read the file as an Excel workbook: {path}",
+    ),
+    (
+        "xlsx.write_failed",
+        "Excelファイルを書き出せません: {path}",
+        "Could not write the Excel file: {path}",
+    ),
+    (
+        "xlsx.no_sheets",
+        "ブックにシートがありません",
+        "The workbook has no sheets",
+    ),
+    (
+        "xlsx.sheet_unreadable",
+        "シート「{sheet}」を読み込めません: {error}",
+        "Could not read sheet \"{sheet}\": {error}",
+    ),
+    (
+        "xlsx.header_row_invalid",
+        "見出し行は1以上で指定してください",
+        "The header row must be 1 or greater",
+    ),
+    (
+        "xlsx.header_row_out_of_range",
+        "指定された見出し行がシートの範囲外です",
+        "The specified header row is outside the sheet",
+    ),
+    (
+        "xlsx.too_many_rows",
+        "行数が Excel の上限を超えています",
+        "The number of rows exceeds the Excel limit",
+    ),
+    // バックアップ・スナップショット
+    (
+        "backup.policy_unreadable",
+        "保持ルールを読み込めません: {error}",
+        "Could not read the retention policy: {error}",
+    ),
+    (
+        "snapshot.exists",
+        "同じ名前のスナップショットがあります: {name}",
+        "A snapshot with the same name already exists: {name}",
+    ),
+    (
+        "snapshot.not_found",
+        "スナップショットが見つかりません: {name}",
+        "Snapshot not found: {name}",
+    ),
+    (
+        "snapshot.meta_unreadable",
+        "スナップショットの情報を読み込めません: {error}",
+        "Could not read the snapshot information: {error}",
+    ),
+    (
+        "snapshot.name_required",
+        "スナップショット名を入力してください",
+        "Please enter a snapshot name",
+    ),
+    (
+        "snapshot.name_too_long",
+        "スナップショット名は{max}文字以内で入力してください",
+        "Snapshot names must be at most {max} characters",
+    ),
+    (
+        "snapshot.name_invalid",
+        "スナップショット名に使用できない文字が含まれています: {name}",
+        "The snapshot name contains characters that cannot be used: {name}",
+    ),
+    // 変更履歴
+    (
+        "history.nothing_to_undo",
+        "元に戻せる変更がありません",
+        "There are no changes to undo",
+    ),
+    (
+        "history.nothing_to_redo",
+        "やり直せる変更がありません",
+        "There are no changes to redo",
+    ),
+    (
+        "history.corrupt_line",
+        "履歴ファイル {path} の {line} 行目を読み込めません: {error}",
+        "Cannot read line {line} of history file {path}: {error}",
+    ),
+    (
+        "history.not_found",
+        "履歴 {id} が見つかりません",
+        "History entry {id} not found",
+    ),
+    // コマンドライン
+    (
+        "cli.filter_invalid",
+        "絞り込み条件を読み込めません: {error}",
+        "Could not read the filter: {error}",
+    ),
+    (
+        "cli.assignment_format",
+        "値は 列ID=値 の形式で指定してください: {assignment}",
+        "Specify values as COLUMN=VALUE: {assignment}",
+    ),
+    (
+        "cli.column_not_editable",
+        "このカラムには値を設定できません: {column}",
+        "Values cannot be set in this column: {column}",
+    ),
+    (
+        "cli.not_a_number",
+        "数値ではありません: {column}={text}",
+        "Not a number: {column}={text}",
+    ),
+    (
+        "cli.not_a_boolean",
+        "真偽値ではありません: {column}={text}",
+        "Not a boolean: {column}={text}",
+    ),
+    (
+        "cli.array_invalid",
+        "配列を読み込めません: {column}={text}: {error}",
+        "Could not read the array: {column}={text}: {error}",
+    ),
+    (
+        "cli.output_failed",
+        "結果を書き出せません: {error}",
+        "Could not write the result: {error}",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeSet, HashSet};
+
+    /// 書式に含まれる`{name}`の名前
+    fn placeholders(template: &str) -> BTreeSet<&str> {
+        template
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn every_message_has_both_languages_with_the_same_placeholders() {
+        let mut keys = HashSet::new();
+        for (key, ja, en) in MESSAGES {
+            assert!(keys.insert(key), "duplicate key: {key}");
+            assert!(!ja.trim().is_empty() && !en.trim().is_empty(), "{key}");
+            assert_eq!(placeholders(ja), placeholders(en), "{key}");
+        }
+    }
+
+    #[test]
+    fn messages_follow_the_locale() {
+        let previous = current_locale();
+        set_locale(Locale::En);
+        assert_eq!(
+            tr!("error.file_not_found", path = "a.json"),
+            template("error.file_not_found", Locale::En)
+                .unwrap()
+                .replace("{path}", "a.json")
+        );
+        set_locale(Locale::Ja);
+        assert_eq!(
+            tr!("error.file_not_found", path = "a.json"),
+            "ファイルが見つかりません: a.json"
+        );
+        set_locale(previous);
+
+        // カタログにないキーや、値のない名前はそのまま残す
+        assert_eq!(tr!("missing.key"), "missing.key");
+        assert!(text("error.file_not_found", &[]).contains("{path}"));
+    }
+
+    #[test]
+    fn locales_are_chosen_from_language_tags() {
+        for tag in ["ja", "ja-JP", "ja_JP.UTF-8", "JA"] {
+            assert_eq!(Locale::from_tag(tag), Locale::Ja, "{tag}");
+        }
+        for tag in ["en-US", "en_GB.UTF-8", "fr", ""] {
+            assert_eq!(Locale::from_tag(tag), Locale::En, "{tag}");
+        }
+    }
+}
@@ -0,0 +1,31 @@
+// ワークスペース（データファイルとスキーマ）を扱う処理をまとめたライブラリ
+// Tauriに依存しないため、デスクトップアプリ以外のツールからも利用できる
+pub mod aggregate;
+pub mod changes;
+pub mod delimited;
+pub mod diff;
+mod error;
+pub mod formula;
+pub mod history;
+pub mod i18n;
+pub mod merge;
+pub mod query;
+pub mod registry;
+pub mod relation;
+mod rows;
+pub mod schema;
+pub mod storage;
+pub mod tabular;
+pub mod validation;
+mod workspace;
+pub mod xlsx;
+
+pub use error::{ErrorCode, ErrorContext, WorkspaceError};
+pub use i18n::Locale;
+pub use workspace::{
+    build_table_payload, commit_workspace, create_workspace, ensure_revision, new_row_id,
+    normalise_rows, open_workspace, prepare_new_workspace, read_evaluated, read_previous,
+    restore_with_history, save_workspace, update_schema_metadata, validate_table,
+    validate_workspace, workspace_id, write_imported_workspace, write_workspace, SaveResult,
+    TablePayload, WorkspaceInfo,
+};
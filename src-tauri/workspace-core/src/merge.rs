@@ -0,0 +1,333 @@
+// 外部変更と自分の変更を行・セル単位で統合する3-wayマージ処理
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::rows::{is_bookkeeping, row_id};
+
+/// 競合の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// 同じセルが両方で異なる値に変更された
+    Cell,
+    /// 自分が変更した行がファイル側で削除された
+    DeletedOnDisk,
+    /// ファイル側で変更された行を自分が削除した
+    DeletedLocally,
+}
+
+/// マージで自動解決できなかった競合
+/// マージ結果には自分（local）の内容を採用しているため、ファイル側を採用する場合は `remote` を使う
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeConflict {
+    /// 行の_id
+    pub row_id: String,
+    /// セルの競合の場合はカラムID、行の競合の場合はNone
+    pub column_id: Option<String>,
+    pub kind: ConflictKind,
+    /// 読み込み時の値（行の競合の場合は行全体、存在しない場合はnull）
+    pub base: Value,
+    /// 自分の値
+    pub local: Value,
+    /// ファイル側の値
+    pub remote: Value,
+}
+
+/// マージの結果
+#[derive(Debug, Default)]
+pub struct MergeOutcome {
+    /// 統合した行データ
+    pub rows: Vec<Value>,
+    /// 自動解決できなかった競合
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// 読み込み時の行データを基準に、自分の行データとファイル側の行データを統合する
+/// 行は_idで対応付け、セル単位で片方だけが変更した値を採用する
+/// 両方が同じセルを異なる値に変更した場合は自分の値を採用し、競合として報告する
+///
+/// # 引数
+/// * `base` - 読み込み時の行データ
+/// * `local` - 自分が編集中の行データ
+/// * `remote` - 現在ファイルに保存されている行データ
+///
+/// # 戻り値
+/// 統合した行データと競合の一覧
+pub fn merge_rows(base: &[Value], local: &[Value], remote: &[Value]) -> MergeOutcome {
+    let base_index = index_by_id(base);
+    let local_index = index_by_id(local);
+    let mut conflicts = Vec::new();
+
+    // ファイル側の並び順を基準に、両方に残っている行とファイル側で追加された行を並べる
+    let mut merged = Vec::with_capacity(remote.len().max(local.len()));
+    let mut merged_ids = HashSet::new();
+    for remote_row in remote {
+        let Some(id) = row_id(remote_row) else {
+            merged.push(remote_row.clone());
+            continue;
+        };
+        let base_row = base_index.get(id).copied();
+        match (base_row, local_index.get(id)) {
+            (_, Some(local_row)) => {
+                merged.push(merge_row(
+                    id,
+                    base_row,
+                    local_row,
+                    remote_row,
+                    &mut conflicts,
+                ));
+            }
+            // ファイル側で追加された行
+            (None, None) => merged.push(remote_row.clone()),
+            // 自分が削除した行（ファイル側で変更されていなければ削除を採用する）
+            (Some(base_row), None) => {
+                if same_content(base_row, remote_row) {
+                    continue;
+                }
+                conflicts.push(MergeConflict {
+                    row_id: id.to_string(),
+                    column_id: None,
+                    kind: ConflictKind::DeletedLocally,
+                    base: base_row.clone(),
+                    local: Value::Null,
+                    remote: remote_row.clone(),
+                });
+                merged.push(remote_row.clone());
+            }
+        }
+        merged_ids.insert(id);
+    }
+
+    // ファイル側にない自分の行は、自分の並びで直前にある行の後ろに挿入する
+    let mut inserts: HashMap<Option<String>, Vec<Value>> = HashMap::new();
+    let mut anchor: Option<String> = None;
+    for local_row in local {
+        let id = row_id(local_row);
+        if let Some(id) = id.filter(|id| merged_ids.contains(id)) {
+            anchor = Some(id.to_string());
+            continue;
+        }
+        let keep = match id.and_then(|id| base_index.get(id).map(|base_row| (id, *base_row))) {
+            // 自分が追加した行
+            None => true,
+            // ファイル側で削除された行（自分が変更していなければ削除を採用する）
+            Some((id, base_row)) => {
+                let modified = !same_content(base_row, local_row);
+                if modified {
+                    conflicts.push(MergeConflict {
+                        row_id: id.to_string(),
+                        column_id: None,
+                        kind: ConflictKind::DeletedOnDisk,
+                        base: base_row.clone(),
+                        local: local_row.clone(),
+                        remote: Value::Null,
+                    });
+                }
+                modified
+            }
+        };
+        if keep {
+            inserts
+                .entry(anchor.clone())
+                .or_default()
+                .push(local_row.clone());
+        }
+    }
+
+    let mut rows = inserts.remove(&None).unwrap_or_default();
+    for row in merged {
+        let following = inserts.remove(&row_id(&row).map(str::to_string));
+        rows.push(row);
+        rows.extend(following.into_iter().flatten());
+    }
+
+    // 統合後の並び順で_orderを振り直す
+    for (index, row) in rows.iter_mut().enumerate() {
+        if let Value::Object(object) = row {
+            object.insert("_order".into(), Value::Number(Number::from(index as u64)));
+        }
+    }
+
+    MergeOutcome { rows, conflicts }
+}
+
+/// 両方に存在する1行をセル単位で統合する
+fn merge_row(
+    id: &str,
+    base: Option<&Value>,
+    local: &Value,
+    remote: &Value,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Value {
+    let (Some(local_object), Some(remote_object)) = (local.as_object(), remote.as_object()) else {
+        return local.clone();
+    };
+    let empty = Map::new();
+    let base_object = base.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut merged = Map::new();
+    let keys = local_object.keys().chain(
+        remote_object
+            .keys()
+            .filter(|key| !local_object.contains_key(*key)),
+    );
+    for key in keys {
+        if key == "_order" {
+            continue;
+        }
+        let local_value = cell(local_object, key);
+        let remote_value = cell(remote_object, key);
+        let value = if key == "_updated" {
+            // 更新日時は新しい方を採用する（RFC 3339 は文字列順で比較できる）
+            match (local_value, remote_value) {
+                (Some(Value::String(l)), Some(Value::String(r))) if r > l => remote_value,
+                (None, _) => remote_value,
+                _ => local_value,
+            }
+        } else if local_value == remote_value {
+            local_value
+        } else {
+            let base_value = cell(base_object, key);
+            if local_value == base_value {
+                remote_value
+            } else if remote_value == base_value || key.starts_with('_') {
+                local_value
+            } else {
+                conflicts.push(MergeConflict {
+                    row_id: id.to_string(),
+                    column_id: Some(key.clone()),
+                    kind: ConflictKind::Cell,
+                    base: base_value.cloned().unwrap_or(Value::Null),
+                    local: local_value.cloned().unwrap_or(Value::Null),
+                    remote: remote_value.cloned().unwrap_or(Value::Null),
+                });
+                local_value
+            }
+        };
+        if let Some(value) = value {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    Value::Object(merged)
+}
+
+/// セルの値を取得する（nullはキーがないものとして扱う）
+fn cell<'a>(object: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    object.get(key).filter(|value| !value.is_null())
+}
+
+/// 管理用のキーを除いて2つの行の内容が同じか判定する
+fn same_content(left: &Value, right: &Value) -> bool {
+    let (Some(left), Some(right)) = (left.as_object(), right.as_object()) else {
+        return left == right;
+    };
+    left.keys()
+        .chain(right.keys())
+        .filter(|key| !is_bookkeeping(key))
+        .all(|key| cell(left, key) == cell(right, key))
+}
+
+/// 行データを_idで引けるようにする（_idのない行は含めない）
+fn index_by_id(rows: &[Value]) -> HashMap<&str, &Value> {
+    rows.iter()
+        .filter_map(|row| row_id(row).map(|id| (id, row)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn row(id: &str, name: &str, qty: i64) -> Value {
+        json!({"_id": id, "_order": 0, "name": name, "qty": qty})
+    }
+
+    fn ids(rows: &[Value]) -> Vec<&str> {
+        rows.iter().filter_map(row_id).collect()
+    }
+
+    #[test]
+    fn merges_edits_to_different_cells() {
+        let base = vec![row("a", "A", 1), row("b", "B", 2)];
+        let local = vec![row("a", "A2", 1), row("b", "B", 2)];
+        let remote = vec![row("a", "A", 10), row("b", "B", 2)];
+
+        let outcome = merge_rows(&base, &local, &remote);
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(outcome.rows[0]["name"], "A2");
+        assert_eq!(outcome.rows[0]["qty"], 10);
+    }
+
+    #[test]
+    fn same_cell_changed_on_both_sides_keeps_local_and_reports() {
+        let base = vec![row("a", "A", 1)];
+        let local = vec![row("a", "local", 1)];
+        let remote = vec![row("a", "remote", 1)];
+
+        let outcome = merge_rows(&base, &local, &remote);
+        assert_eq!(outcome.rows[0]["name"], "local");
+        assert_eq!(outcome.conflicts.len(), 1);
+        let conflict = &outcome.conflicts[0];
+        assert_eq!(conflict.kind, ConflictKind::Cell);
+        assert_eq!(conflict.column_id.as_deref(), Some("name"));
+        assert_eq!(
+            (&conflict.base, &conflict.local, &conflict.remote),
+            (&json!("A"), &json!("local"), &json!("remote"))
+        );
+    }
+
+    #[test]
+    fn added_rows_keep_their_position() {
+        let base = vec![row("a", "A", 1), row("b", "B", 2)];
+        let local = vec![row("a", "A", 1), row("new", "N", 0), row("b", "B", 2)];
+        let remote = vec![row("a", "A", 1), row("b", "B", 2), row("c", "C", 3)];
+
+        let outcome = merge_rows(&base, &local, &remote);
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(ids(&outcome.rows), ["a", "new", "b", "c"]);
+        let orders: Vec<&Value> = outcome.rows.iter().map(|row| &row["_order"]).collect();
+        assert_eq!(orders, [&json!(0), &json!(1), &json!(2), &json!(3)]);
+    }
+
+    #[test]
+    fn deletions_apply_unless_the_other_side_edited_the_row() {
+        let base = vec![row("a", "A", 1), row("b", "B", 2), row("c", "C", 3)];
+        // ファイル側でa・bを削除し、自分はbを変更してcを削除した
+        let local = vec![row("a", "A", 1), row("b", "B2", 2)];
+        let remote = vec![row("c", "C", 30)];
+
+        let outcome = merge_rows(&base, &local, &remote);
+        // 直前の行（a）が削除されたため、残したbは先頭に入る
+        assert_eq!(ids(&outcome.rows), ["b", "c"]);
+        let kinds: Vec<(&str, ConflictKind)> = outcome
+            .conflicts
+            .iter()
+            .map(|conflict| (conflict.row_id.as_str(), conflict.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("c", ConflictKind::DeletedLocally),
+                ("b", ConflictKind::DeletedOnDisk)
+            ]
+        );
+    }
+
+    #[test]
+    fn bookkeeping_differences_are_not_edits() {
+        let base =
+            vec![json!({"_id": "a", "_order": 0, "_updated": "2024-01-01T00:00:00Z", "name": "A"})];
+        let local =
+            vec![json!({"_id": "a", "_order": 5, "_updated": "2024-01-01T00:00:00Z", "name": "A"})];
+        let remote =
+            vec![json!({"_id": "a", "_order": 0, "_updated": "2024-02-01T00:00:00Z", "name": "A"})];
+
+        let outcome = merge_rows(&base, &local, &remote);
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(outcome.rows[0]["_updated"], "2024-02-01T00:00:00Z");
+        assert_eq!(outcome.rows[0]["_order"], 0);
+    }
+}
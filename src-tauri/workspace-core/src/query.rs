@@ -0,0 +1,449 @@
+// 行データの絞り込み・並べ替え・ページ分割（query_rows）の処理
+use std::cmp::Ordering;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::schema::TableSchema;
+use crate::validation::is_empty_value;
+use crate::{tr, WorkspaceError};
+
+/// 絞り込み条件の式
+/// カラムはIDで指定する（_id等のシステム列も指定できる）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Filter {
+    /// すべての条件に一致する
+    And { filters: Vec<Filter> },
+    /// いずれかの条件に一致する
+    Or { filters: Vec<Filter> },
+    /// 条件に一致しない
+    Not { filter: Box<Filter> },
+    /// 値が等しい（数値は数値として、配列は要素の集合として比較する）
+    Equals { column: String, value: Value },
+    /// 文字列を含む（配列の場合はいずれかの要素が含む）
+    Contains {
+        column: String,
+        value: String,
+        /// 大文字・小文字を区別するか
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    /// 値が範囲内にある（min・maxは省略可能、境界を含む）
+    Range {
+        column: String,
+        #[serde(default)]
+        min: Option<Value>,
+        #[serde(default)]
+        max: Option<Value>,
+    },
+    /// 未入力（null、空文字列、空配列）
+    IsEmpty { column: String },
+    /// 値がいずれかの候補と等しい（multiselectの場合はいずれかのタグが候補に含まれる）
+    InSet {
+        column: String,
+        values: Vec<Value>,
+        /// multiselectの場合に、すべての候補がタグに含まれることを条件にするか
+        #[serde(default)]
+        match_all: bool,
+    },
+}
+
+/// 並べ替えのキー
+#[derive(Debug, Clone, Deserialize)]
+pub struct SortKey {
+    pub column: String,
+    /// 降順にするか
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// query_rows の検索条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RowQuery {
+    /// 絞り込み条件（省略時はすべての行）
+    pub filter: Option<Filter>,
+    /// 並べ替えのキー（先頭から優先、省略時は保存順）
+    pub sort: Vec<SortKey>,
+    /// 先頭から読み飛ばす件数
+    pub offset: usize,
+    /// 返す件数の上限（省略時は残りすべて）
+    pub limit: Option<usize>,
+}
+
+/// 検索結果の1ページ
+#[derive(Debug, Default)]
+pub struct QueryPage {
+    /// 指定されたページの行
+    pub rows: Vec<Value>,
+    /// 条件に一致した行の総数
+    pub total: usize,
+}
+
+impl Filter {
+    /// 条件が参照するカラムがスキーマに存在するか確認する
+    ///
+    /// # 引数
+    /// * `schema` - スキーマ
+    ///
+    /// # 戻り値
+    /// 正しい場合は`Ok(())`、不正な場合はエラー
+    pub fn validate(&self, schema: &TableSchema) -> Result<(), WorkspaceError> {
+        match self {
+            Filter::And { filters } | Filter::Or { filters } => filters
+                .iter()
+                .try_for_each(|filter| filter.validate(schema)),
+            Filter::Not { filter } => filter.validate(schema),
+            Filter::Range {
+                column,
+                min: None,
+                max: None,
+            } => {
+                let mut error = WorkspaceError::invalid_input(
+                    "query.range_bounds",
+                    tr!("query.range_bounds", column = column),
+                );
+                error.context.column_id = Some(column.clone());
+                Err(error)
+            }
+            Filter::Equals { column, .. }
+            | Filter::Contains { column, .. }
+            | Filter::Range { column, .. }
+            | Filter::IsEmpty { column }
+            | Filter::InSet { column, .. } => ensure_column(schema, column),
+        }
+    }
+
+    /// 行が条件に一致するか判定する
+    pub fn matches(&self, row: &Value) -> bool {
+        match self {
+            Filter::And { filters } => filters.iter().all(|filter| filter.matches(row)),
+            Filter::Or { filters } => filters.iter().any(|filter| filter.matches(row)),
+            Filter::Not { filter } => !filter.matches(row),
+            Filter::Equals { column, value } => values_equal(cell(row, column), value),
+            Filter::Contains {
+                column,
+                value,
+                case_sensitive,
+            } => {
+                let contains = |text: &str| {
+                    if *case_sensitive {
+                        text.contains(value.as_str())
+                    } else {
+                        text.to_lowercase().contains(&value.to_lowercase())
+                    }
+                };
+                match cell(row, column) {
+                    Value::Array(items) => items.iter().any(|item| contains(&scalar_text(item))),
+                    Value::Null => false,
+                    other => contains(&scalar_text(other)),
+                }
+            }
+            Filter::Range { column, min, max } => {
+                let value = cell(row, column);
+                if is_empty_value(value) {
+                    return false;
+                }
+                let above = min.as_ref().is_none_or(|min| {
+                    compare_values(value, min).is_some_and(|ordering| ordering.is_ge())
+                });
+                let below = max.as_ref().is_none_or(|max| {
+                    compare_values(value, max).is_some_and(|ordering| ordering.is_le())
+                });
+                above && below
+            }
+            Filter::IsEmpty { column } => is_empty_value(cell(row, column)),
+            Filter::InSet {
+                column,
+                values,
+                match_all,
+            } => match cell(row, column) {
+                Value::Array(items) if *match_all => values
+                    .iter()
+                    .all(|value| items.iter().any(|item| values_equal(item, value))),
+                Value::Array(items) => items
+                    .iter()
+                    .any(|item| values.iter().any(|value| values_equal(item, value))),
+                other => values.iter().any(|value| values_equal(other, value)),
+            },
+        }
+    }
+}
+
+/// 行データを絞り込み、並べ替えて、指定されたページを返す
+///
+/// # 引数
+/// * `rows` - 保存されている行データ（保存順）
+/// * `schema` - スキーマ（条件のカラムの確認に使用）
+/// * `query` - 検索条件
+///
+/// # 戻り値
+/// 成功時は指定ページの行と一致した行の総数、条件が不正な場合はエラー
+pub fn run_query(
+    rows: &[Value],
+    schema: &TableSchema,
+    query: &RowQuery,
+) -> Result<QueryPage, WorkspaceError> {
+    if let Some(filter) = &query.filter {
+        filter.validate(schema)?;
+    }
+    for key in &query.sort {
+        ensure_column(schema, &key.column)?;
+    }
+
+    let mut matched: Vec<&Value> = rows
+        .iter()
+        .filter(|row| {
+            query
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(row))
+        })
+        .collect();
+    if !query.sort.is_empty() {
+        // 安定ソートのため、キーが同じ行は保存順のまま
+        matched.sort_by(|a, b| compare_rows(a, b, &query.sort));
+    }
+
+    let total = matched.len();
+    let rows = matched
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
+        .cloned()
+        .collect();
+    Ok(QueryPage { rows, total })
+}
+
+/// 並べ替えのキーに従って2行を比較する（未入力の値は昇順・降順とも末尾）
+fn compare_rows(a: &Value, b: &Value, keys: &[SortKey]) -> Ordering {
+    for key in keys {
+        let (left, right) = (cell(a, &key.column), cell(b, &key.column));
+        let ordering = match (is_empty_value(left), is_empty_value(right)) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => {
+                let ordering = compare_for_sort(left, right);
+                if key.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            }
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// カラムがスキーマに存在するか確認する（_で始まるシステム列は常に許可）
+pub fn ensure_column(schema: &TableSchema, column: &str) -> Result<(), WorkspaceError> {
+    if column.starts_with('_') || schema.columns.iter().any(|existing| existing.id == column) {
+        return Ok(());
+    }
+    let mut error =
+        WorkspaceError::invalid_input("column.not_found", tr!("column.not_found", column = column));
+    error.context.column_id = Some(column.to_string());
+    Err(error)
+}
+
+/// 行からセルの値を取得する（存在しない場合はnull）
+pub fn cell<'a>(row: &'a Value, column: &str) -> &'a Value {
+    row.get(column).unwrap_or(&Value::Null)
+}
+
+/// 2つの値が等しいか判定する
+/// 数値は数値として比較し（1 と 1.0 は等しい）、配列は順序を問わず要素の集合として比較する
+pub fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.iter()
+                .all(|item| b.iter().any(|other| values_equal(item, other)))
+                && b.iter()
+                    .all(|item| a.iter().any(|other| values_equal(item, other)))
+        }
+        (Value::Null, other) | (other, Value::Null) => is_empty_value(other),
+        _ => left == right,
+    }
+}
+
+/// 同じ種類の値を比較する（数値・文字列・真偽値のみ、それ以外はNone）
+fn compare_values(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// 並べ替え用に値を比較する
+/// 種類が異なる場合は 真偽値 < 数値 < 文字列 < 配列 の順、文字列は大文字・小文字を区別せずに比べる
+pub fn compare_for_sort(left: &Value, right: &Value) -> Ordering {
+    let rank = |value: &Value| match value {
+        Value::Bool(_) => 0,
+        Value::Number(_) => 1,
+        Value::String(_) => 2,
+        Value::Array(_) => 3,
+        _ => 4,
+    };
+    match (left, right) {
+        (Value::String(a), Value::String(b)) => a
+            .to_lowercase()
+            .cmp(&b.to_lowercase())
+            .then_with(|| a.cmp(b)),
+        (Value::Array(_), Value::Array(_)) => scalar_text(left)
+            .to_lowercase()
+            .cmp(&scalar_text(right).to_lowercase()),
+        _ => compare_values(left, right).unwrap_or_else(|| rank(left).cmp(&rank(right))),
+    }
+}
+
+/// 値を比較・検索用の文字列にする（配列は要素を連結する）
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(scalar_text).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{ColumnDefinition, ColumnType};
+    use serde_json::json;
+
+    fn schema() -> TableSchema {
+        let mut schema = TableSchema::new("test", "2024-01-01T00:00:00Z");
+        schema.columns.extend([
+            ColumnDefinition::new("name", "Name", ColumnType::Text),
+            ColumnDefinition::new("qty", "Qty", ColumnType::Number),
+            ColumnDefinition::new("tags", "Tags", ColumnType::Multiselect),
+        ]);
+        schema
+    }
+
+    fn rows() -> Vec<Value> {
+        vec![
+            json!({"_id": "a", "name": "Apple", "qty": 3, "tags": ["fruit", "red"]}),
+            json!({"_id": "b", "name": "banana", "qty": 10, "tags": ["fruit"]}),
+            json!({"_id": "c", "name": "Carrot", "qty": 3.0, "tags": []}),
+            json!({"_id": "d", "name": "", "tags": ["red"]}),
+        ]
+    }
+
+    fn query(value: Value) -> RowQuery {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn ids(page: &QueryPage) -> Vec<&str> {
+        page.rows
+            .iter()
+            .filter_map(|row| row["_id"].as_str())
+            .collect()
+    }
+
+    #[test]
+    fn filters_by_each_operator() {
+        let cases = [
+            (
+                json!({"op": "equals", "column": "qty", "value": 3}),
+                vec!["a", "c"],
+            ),
+            (
+                json!({"op": "contains", "column": "name", "value": "AN"}),
+                vec!["b"],
+            ),
+            (
+                json!({"op": "contains", "column": "name", "value": "AN", "case_sensitive": true}),
+                vec![],
+            ),
+            (json!({"op": "range", "column": "qty", "min": 4}), vec!["b"]),
+            (json!({"op": "is_empty", "column": "name"}), vec!["d"]),
+            (
+                json!({"op": "in_set", "column": "tags", "values": ["red"]}),
+                vec!["a", "d"],
+            ),
+            (
+                json!({"op": "in_set", "column": "tags", "values": ["fruit", "red"], "match_all": true}),
+                vec!["a"],
+            ),
+            (
+                json!({"op": "and", "filters": [
+                    {"op": "equals", "column": "qty", "value": 3},
+                    {"op": "not", "filter": {"op": "contains", "column": "name", "value": "apple"}}
+                ]}),
+                vec!["c"],
+            ),
+            (
+                json!({"op": "or", "filters": [
+                    {"op": "equals", "column": "_id", "value": "a"},
+                    {"op": "is_empty", "column": "qty"}
+                ]}),
+                vec!["a", "d"],
+            ),
+        ];
+        for (filter, expected) in cases {
+            let page = run_query(&rows(), &schema(), &query(json!({"filter": filter}))).unwrap();
+            assert_eq!(ids(&page), expected, "{filter}");
+        }
+    }
+
+    #[test]
+    fn sorts_with_empty_values_last_and_pages() {
+        let page = run_query(
+            &rows(),
+            &schema(),
+            &query(json!({"sort": [{"column": "qty", "descending": true}, {"column": "name"}]})),
+        )
+        .unwrap();
+        assert_eq!(ids(&page), ["b", "a", "c", "d"]);
+
+        let page = run_query(
+            &rows(),
+            &schema(),
+            &query(json!({"sort": [{"column": "qty"}], "offset": 1, "limit": 2})),
+        )
+        .unwrap();
+        assert_eq!(page.total, 4);
+        assert_eq!(ids(&page), ["c", "b"]);
+    }
+
+    #[test]
+    fn validate_rejects_unknown_columns_and_unbounded_ranges() {
+        let schema = schema();
+        let filter = |value: Value| serde_json::from_value::<Filter>(value).unwrap();
+
+        let error = filter(json!({"op": "not", "filter": {"op": "is_empty", "column": "missing"}}))
+            .validate(&schema)
+            .unwrap_err();
+        assert_eq!(error.message_key, "column.not_found");
+        assert_eq!(error.context.column_id.as_deref(), Some("missing"));
+
+        let error = filter(json!({"op": "range", "column": "qty"}))
+            .validate(&schema)
+            .unwrap_err();
+        assert_eq!(error.message_key, "query.range_bounds");
+
+        assert!(
+            filter(json!({"op": "equals", "column": "_created", "value": null}))
+                .validate(&schema)
+                .is_ok()
+        );
+
+        let error = run_query(
+            &rows(),
+            &schema,
+            &query(json!({"sort": [{"column": "missing"}]})),
+        )
+        .unwrap_err();
+        assert_eq!(error.code, crate::ErrorCode::InvalidInput);
+    }
+}
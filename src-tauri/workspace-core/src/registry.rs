@@ -0,0 +1,262 @@
+// 開いているワークスペースと、それを表示しているウィンドウの対応を管理する処理
+// ウィンドウやファイル監視の実体には依存しないため、アプリを起動せずに検証できる
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::storage::Storage;
+use crate::{tr, workspace_id, ErrorCode, WorkspaceError};
+
+/// ワークスペースを表示しているウィンドウのラベル（イベントの送信先）
+/// ファイル監視からも参照するため、複製しても同じ集合を共有する
+#[derive(Debug, Clone, Default)]
+pub struct WindowSet(Arc<Mutex<HashSet<String>>>);
+
+impl WindowSet {
+    /// イベントを送信するウィンドウのラベル（ラベル順）
+    pub fn labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> = self.0.lock().iter().cloned().collect();
+        labels.sort();
+        labels
+    }
+
+    fn insert(&self, window_label: &str) {
+        self.0.lock().insert(window_label.to_string());
+    }
+
+    /// ウィンドウを外し、表示しているウィンドウがなくなったかを返す
+    fn remove(&self, window_label: &str) -> bool {
+        let mut windows = self.0.lock();
+        windows.remove(window_label);
+        windows.is_empty()
+    }
+}
+
+/// 開いているワークスペース
+struct OpenWorkspace<W> {
+    storage: Arc<dyn Storage>,
+    windows: WindowSet,
+    /// ファイル監視（参照はせず、ワークスペースを閉じたときに破棄する）
+    _watcher: W,
+}
+
+/// 開いているワークスペースの一覧（キーはワークスペースID）
+/// 同じデータファイルを複数のウィンドウで開いた場合は、ストレージとファイル監視を共有する
+/// `W`はファイル監視の型で、ワークスペースを表示しているウィンドウがなくなったときに破棄する
+pub struct WorkspaceRegistry<W> {
+    workspaces: Mutex<HashMap<String, OpenWorkspace<W>>>,
+}
+
+impl<W> Default for WorkspaceRegistry<W> {
+    fn default() -> Self {
+        Self {
+            workspaces: Mutex::default(),
+        }
+    }
+}
+
+impl<W> WorkspaceRegistry<W> {
+    /// ワークスペースをウィンドウに結び付ける
+    /// 開かれていないワークスペースの場合は、ファイル監視を開始して一覧に加える
+    ///
+    /// # 引数
+    /// * `storage` - ワークスペースのストレージ
+    /// * `window_label` - ワークスペースを表示するウィンドウのラベル
+    /// * `start_watcher` - ファイル監視を開始する処理（ワークスペースID、ストレージ、イベントの送信先を受け取る）
+    ///
+    /// # 戻り値
+    /// 成功時は共有するストレージ（既に開かれている場合はそのストレージ）、ファイル監視を開始できない場合はエラー
+    pub fn open(
+        &self,
+        storage: Arc<dyn Storage>,
+        window_label: &str,
+        start_watcher: impl FnOnce(&str, &Arc<dyn Storage>, &WindowSet) -> Result<W, WorkspaceError>,
+    ) -> Result<Arc<dyn Storage>, WorkspaceError> {
+        let mut workspaces = self.workspaces.lock();
+        let id = workspace_id(storage.as_ref());
+        if let Some(existing) = workspaces.get(&id) {
+            existing.windows.insert(window_label);
+            return Ok(Arc::clone(&existing.storage));
+        }
+
+        let windows = WindowSet::default();
+        windows.insert(window_label);
+        let watcher = start_watcher(&id, &storage, &windows)?;
+        workspaces.insert(
+            id,
+            OpenWorkspace {
+                storage: Arc::clone(&storage),
+                windows,
+                _watcher: watcher,
+            },
+        );
+        Ok(storage)
+    }
+
+    /// ウィンドウとワークスペースの結び付きを解除する
+    /// ワークスペースを表示しているウィンドウがなくなった場合は、ワークスペースを閉じてファイル監視を破棄する
+    ///
+    /// # 引数
+    /// * `workspace_id` - ワークスペースID
+    /// * `window_label` - ウィンドウのラベル
+    ///
+    /// # 戻り値
+    /// ワークスペースを閉じた場合はtrue、他のウィンドウが表示している場合や開かれていなかった場合はfalse
+    pub fn close(&self, workspace_id: &str, window_label: &str) -> bool {
+        let mut workspaces = self.workspaces.lock();
+        let unused = workspaces
+            .get(workspace_id)
+            .is_some_and(|workspace| workspace.windows.remove(window_label));
+        let closed = if unused {
+            workspaces.remove(workspace_id)
+        } else {
+            None
+        };
+        // ファイル監視の停止は一覧のロックを解放してから行う
+        drop(workspaces);
+        closed.is_some()
+    }
+
+    /// 閉じられたウィンドウとすべてのワークスペースの結び付きを解除する
+    ///
+    /// # 引数
+    /// * `window_label` - 閉じられたウィンドウのラベル
+    pub fn release_window(&self, window_label: &str) {
+        let ids: Vec<String> = self.workspaces.lock().keys().cloned().collect();
+        for id in ids {
+            self.close(&id, window_label);
+        }
+    }
+
+    /// 開いているすべてのワークスペースのストレージ
+    pub fn storages(&self) -> Vec<Arc<dyn Storage>> {
+        self.workspaces
+            .lock()
+            .values()
+            .map(|workspace| Arc::clone(&workspace.storage))
+            .collect()
+    }
+
+    /// 開いているワークスペースのストレージを取得する
+    ///
+    /// # 引数
+    /// * `workspace_id` - ワークスペースID
+    ///
+    /// # 戻り値
+    /// 成功時はストレージ、ワークスペースが開かれていない場合はエラー
+    pub fn storage(&self, workspace_id: &str) -> Result<Arc<dyn Storage>, WorkspaceError> {
+        self.workspaces
+            .lock()
+            .get(workspace_id)
+            .map(|workspace| Arc::clone(&workspace.storage))
+            .ok_or_else(|| {
+                WorkspaceError::new(
+                    ErrorCode::WorkspaceNotLoaded,
+                    "error.workspace_not_loaded",
+                    tr!("error.workspace_not_loaded", id = workspace_id),
+                )
+            })
+    }
+
+    /// ワークスペースのイベントを送信するウィンドウのラベル（開かれていない場合は空）
+    ///
+    /// # 引数
+    /// * `workspace_id` - ワークスペースID
+    pub fn windows(&self, workspace_id: &str) -> Vec<String> {
+        self.workspaces
+            .lock()
+            .get(workspace_id)
+            .map(|workspace| workspace.windows.labels())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::open_storage;
+    use std::path::Path;
+
+    /// 破棄されたときに印を付けるファイル監視の代わり
+    struct Watcher(Arc<Mutex<Vec<String>>>, String);
+
+    impl Drop for Watcher {
+        fn drop(&mut self) {
+            self.0.lock().push(self.1.clone());
+        }
+    }
+
+    /// ワークスペースを開き、開始したファイル監視の送信先を返す
+    fn open(
+        registry: &WorkspaceRegistry<Watcher>,
+        stopped: &Arc<Mutex<Vec<String>>>,
+        path: &Path,
+        window_label: &str,
+    ) -> (String, Option<WindowSet>) {
+        let storage = open_storage(path).unwrap();
+        storage.ensure().unwrap();
+        let mut started = None;
+        registry
+            .open(storage, window_label, |id, _, windows| {
+                started = Some(windows.clone());
+                Ok(Watcher(Arc::clone(stopped), id.to_string()))
+            })
+            .unwrap();
+        (workspace_id(open_storage(path).unwrap().as_ref()), started)
+    }
+
+    #[test]
+    fn windows_share_a_workspace_until_the_last_one_closes() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = WorkspaceRegistry::default();
+        let stopped = Arc::default();
+        let (tasks, events) = open(&registry, &stopped, &dir.path().join("tasks.json"), "a");
+        let (shared, again) = open(&registry, &stopped, &dir.path().join("tasks.json"), "b");
+        let (notes, _) = open(&registry, &stopped, &dir.path().join("notes.db"), "b");
+        // 同じワークスペースはファイル監視を共有する
+        assert_eq!(shared, tasks);
+        assert!(again.is_none());
+        let events = events.unwrap();
+        assert_eq!(events.labels(), ["a", "b"]);
+
+        // 一方のウィンドウを閉じても、他のウィンドウのワークスペースは開いたまま
+        registry.release_window("a");
+        assert!(registry.storage(&tasks).is_ok());
+        assert!(registry.storage(&notes).is_ok());
+        assert_eq!(events.labels(), ["b"]);
+        assert_eq!(registry.windows(&tasks), ["b"]);
+        assert!(stopped.lock().is_empty());
+
+        // 表示していないウィンドウから閉じても影響しない
+        assert!(!registry.close(&notes, "a"));
+        assert!(registry.close(&notes, "b"));
+        assert_eq!(stopped.lock().len(), 1);
+        let error = registry.storage(&notes).err().unwrap();
+        assert_eq!(error.code, ErrorCode::WorkspaceNotLoaded);
+
+        registry.release_window("b");
+        assert!(registry.storages().is_empty());
+        assert!(registry.windows(&tasks).is_empty());
+        assert_eq!(*stopped.lock(), [notes, tasks]);
+    }
+
+    #[test]
+    fn failing_to_start_the_watcher_leaves_the_workspace_closed() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry: WorkspaceRegistry<Watcher> = WorkspaceRegistry::default();
+        let storage = open_storage(&dir.path().join("tasks.json")).unwrap();
+        let error = registry
+            .open(storage, "a", |_, _, _| {
+                Err(WorkspaceError::new(
+                    ErrorCode::Io,
+                    "error.watch_failed",
+                    "watch failed".to_string(),
+                ))
+            })
+            .err()
+            .unwrap();
+        assert_eq!(error.message_key, "error.watch_failed");
+        assert!(registry.storages().is_empty());
+    }
+}
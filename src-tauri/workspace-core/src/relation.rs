@@ -0,0 +1,896 @@
+// リレーション列（他のワークスペースの行の_idへの参照）の解決と参照整合性の処理
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::aggregate::number_value;
+use crate::error::{ErrorCode, WorkspaceError};
+use crate::formula::evaluate_formulas;
+use crate::rows::row_id;
+use crate::schema::{
+    relation_target_segments, ColumnDefinition, ColumnType, OnDelete, RelationConfig,
+    RollupFunction, TableSchema,
+};
+use crate::storage::{open_storage, workspace_files, Storage};
+use crate::tr;
+use crate::validation::{IssueKind, IssueSeverity, RowIssue};
+
+/// 参照先のテーブル
+#[derive(Debug, Clone)]
+pub struct RelatedTable {
+    pub schema: TableSchema,
+    /// 計算列の値を含む行データ
+    pub rows: Vec<Value>,
+}
+
+/// リレーション列のIDごとの参照先（読み込めなかった場合はそのリレーション列のエラー）
+pub type RelatedTables = HashMap<String, Result<RelatedTable, WorkspaceError>>;
+
+/// リレーション列ごとの、参照されている_idと表示値
+pub type RelationLinks = HashMap<String, HashMap<String, String>>;
+
+/// 削除された行を参照していた他のワークスペースへの変更（cascade・nullifyの結果）
+pub struct ReferenceUpdate {
+    pub storage: Arc<dyn Storage>,
+    pub schema: TableSchema,
+    /// 読み込んだときのリビジョン（書き込み前に変更されていないか確認する）
+    pub revision: String,
+    /// 変更前の行データ
+    pub previous_rows: Vec<Value>,
+    /// 変更後の行データ
+    pub rows: Vec<Value>,
+}
+
+/// 参照先が設定されたリレーション列の参照先を読み込む
+/// 参照先の計算列は、さらに別のワークスペースを参照する集計を除いて評価する
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `rows` - ワークスペースの行データ（自分自身を参照する場合に使用）
+/// * `schema` - スキーマ
+///
+/// # 戻り値
+/// リレーション列のIDごとの参照先
+pub fn load_related(storage: &dyn Storage, rows: &[Value], schema: &TableSchema) -> RelatedTables {
+    let mut loaded: HashMap<PathBuf, Result<RelatedTable, WorkspaceError>> = HashMap::new();
+    let mut related = RelatedTables::new();
+    for column in &schema.columns {
+        let Some(config) = relation_config(column) else {
+            continue;
+        };
+        let table = target_path(storage.data_path(), &config.target).and_then(|path| {
+            loaded
+                .entry(path.clone())
+                .or_insert_with(|| {
+                    if path == storage.data_path() {
+                        return Ok(RelatedTable {
+                            schema: schema.clone(),
+                            rows: rows.to_vec(),
+                        });
+                    }
+                    read_related(&path)
+                })
+                .clone()
+        });
+        let table = table.map_err(|err| {
+            let mut error = WorkspaceError::new(
+                err.code,
+                "relation.target_unreadable",
+                tr!(
+                    "relation.target_unreadable",
+                    target = config.target,
+                    error = err.message
+                ),
+            )
+            .with_column(&column.id);
+            error.context.path = err.context.path.clone();
+            error.sources = err.sources;
+            error
+        });
+        related.insert(column.id.clone(), table);
+    }
+    related
+}
+
+/// 行データにlookup・rollup列の値を設定する
+/// 値はファイルに保存せず、読み込みのたびにこの関数で求める（計算列より先に評価する）
+///
+/// # 引数
+/// * `rows` - 行データ（lookup・rollup列の値を上書きする）
+/// * `schema` - スキーマ
+/// * `related` - リレーション列の参照先
+pub fn evaluate_linked_columns(rows: &mut [Value], schema: &TableSchema, related: &RelatedTables) {
+    for column in &schema.columns {
+        let (relation, target_column, function) =
+            match (column.column_type, &column.lookup, &column.rollup) {
+                (ColumnType::Lookup, Some(config), _) => {
+                    (&config.relation, Some(&config.column), None)
+                }
+                (ColumnType::Rollup, _, Some(config)) => (
+                    &config.relation,
+                    config.column.as_ref(),
+                    Some(config.function),
+                ),
+                _ => continue,
+            };
+        let Some(relation_column) = schema
+            .columns
+            .iter()
+            .find(|candidate| &candidate.id == relation)
+        else {
+            continue;
+        };
+
+        let values: Vec<Value> = {
+            let own_rows: &[Value] = rows;
+            // 参照先を読み込めなかった場合や、カラムが見つからない場合はnull
+            let target = match (related.get(relation), relation_config(relation_column)) {
+                (Some(Ok(table)), _) => Some((table.rows.as_slice(), &table.schema)),
+                (None, None) => Some((own_rows, schema)),
+                _ => None,
+            };
+            let target = target.and_then(|(target_rows, target_schema)| {
+                let target_column = match target_column {
+                    Some(name) => Some(resolve_column(target_schema, name)?),
+                    None => None,
+                };
+                Some((target_rows, target_column))
+            });
+            match target {
+                Some((target_rows, target_column)) => {
+                    let index: HashMap<&str, &Value> = target_rows
+                        .iter()
+                        .filter_map(|target| row_id(target).map(|id| (id, target)))
+                        .collect();
+                    own_rows
+                        .iter()
+                        .map(|row| {
+                            let linked: Vec<&Value> =
+                                linked_ids(row.get(relation).unwrap_or(&Value::Null))
+                                    .into_iter()
+                                    .filter_map(|id| index.get(id).copied())
+                                    .collect();
+                            match function {
+                                Some(function) => {
+                                    rollup_value(function, &linked, target_column.as_deref())
+                                }
+                                None => lookup_value(&linked, target_column.as_deref()),
+                            }
+                        })
+                        .collect()
+                }
+                None => vec![Value::Null; own_rows.len()],
+            }
+        };
+        for (row, value) in rows.iter_mut().zip(values) {
+            if let Value::Object(object) = row {
+                object.insert(column.id.clone(), value);
+            }
+        }
+    }
+}
+
+/// リレーション先の行のカラムの値を配列にする（配列の値は展開し、未入力の値は除く）
+fn lookup_value(linked: &[&Value], column: Option<&str>) -> Value {
+    Value::Array(
+        cell_values(linked, column)
+            .into_iter()
+            .filter(|value| !matches!(value, Value::Null) && value.as_str() != Some(""))
+            .cloned()
+            .collect(),
+    )
+}
+
+/// リレーション先の行のカラムを集計する
+/// 数値の集計では、チェックボックスのオンを 1、オフを 0 として扱う
+///
+/// # 引数
+/// * `function` - 集計方法
+/// * `linked` - リレーション先の行
+/// * `column` - 集計するカラムID（count の場合は不要）
+///
+/// # 戻り値
+/// 集計結果（値がない場合の avg・min・max はnull）
+pub fn rollup_value(function: RollupFunction, linked: &[&Value], column: Option<&str>) -> Value {
+    let values = cell_values(linked, column);
+    let numbers: Vec<f64> = values
+        .iter()
+        .filter_map(|value| match value {
+            Value::Number(number) => number.as_f64(),
+            Value::Bool(flag) => Some(if *flag { 1.0 } else { 0.0 }),
+            _ => None,
+        })
+        .collect();
+    match function {
+        RollupFunction::Count => Value::from(linked.len()),
+        RollupFunction::Sum => number_value(numbers.iter().sum()),
+        RollupFunction::Avg if numbers.is_empty() => Value::Null,
+        RollupFunction::Avg => number_value(numbers.iter().sum::<f64>() / numbers.len() as f64),
+        RollupFunction::Min => numbers
+            .into_iter()
+            .reduce(f64::min)
+            .map_or(Value::Null, number_value),
+        RollupFunction::Max => numbers
+            .into_iter()
+            .reduce(f64::max)
+            .map_or(Value::Null, number_value),
+        RollupFunction::Concat => Value::String(
+            values
+                .into_iter()
+                .filter_map(|value| match value {
+                    Value::String(text) if !text.is_empty() => Some(text.clone()),
+                    Value::Number(number) => Some(number.to_string()),
+                    Value::Bool(flag) => Some(flag.to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    }
+}
+
+/// リレーション先の行のカラムの値（配列の値は要素に展開する）
+fn cell_values<'a>(linked: &[&'a Value], column: Option<&str>) -> Vec<&'a Value> {
+    let Some(column) = column else {
+        return Vec::new();
+    };
+    linked
+        .iter()
+        .filter_map(|row| row.get(column))
+        .flat_map(|value| match value {
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        })
+        .collect()
+}
+
+/// 参照先のスキーマでカラムをIDまたは表示名から探す
+pub fn resolve_column(schema: &TableSchema, name: &str) -> Option<String> {
+    schema
+        .columns
+        .iter()
+        .find(|column| column.id == name)
+        .or_else(|| schema.columns.iter().find(|column| column.name == name))
+        .map(|column| column.id.clone())
+}
+
+/// リレーション列の参照先のワークスペースのファイル（変更の監視に使用、自分自身は除く）
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+///
+/// # 戻り値
+/// 参照先のデータファイルとスキーマファイルのパス（スキーマを読み込めない場合は空）
+pub fn related_watch_paths(storage: &dyn Storage) -> Vec<PathBuf> {
+    let Ok(schema) = storage.read_schema() else {
+        return Vec::new();
+    };
+    let own_paths = storage.watch_paths();
+    let mut paths = Vec::new();
+    for config in schema.columns.iter().filter_map(relation_config) {
+        let Ok(path) = target_path(storage.data_path(), &config.target) else {
+            continue;
+        };
+        let Ok(target) = open_storage(&path) else {
+            continue;
+        };
+        for path in target.watch_paths() {
+            if !own_paths.contains(&path) && !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths
+}
+
+/// リレーション列の_idが参照先に存在するか検証する
+/// 参照先が設定されていないリレーション列は、同じテーブルの行を参照しているものとして扱う
+///
+/// # 引数
+/// * `rows` - 検証する行データ
+/// * `schema` - スキーマ
+/// * `related` - 参照先のテーブル
+///
+/// # 戻り値
+/// 見つからない_idを含むセルの問題（エラー扱い）
+pub fn validate_relations(
+    rows: &[Value],
+    schema: &TableSchema,
+    related: &RelatedTables,
+) -> Vec<RowIssue> {
+    let mut issues = Vec::new();
+    for column in relation_columns(schema) {
+        let known: Result<HashSet<&str>, &str> = match related.get(&column.id) {
+            Some(Ok(table)) => Ok(table.rows.iter().filter_map(row_id).collect()),
+            Some(Err(err)) => Err(err.message.as_str()),
+            None => Ok(rows.iter().filter_map(row_id).collect()),
+        };
+        for (row_index, row) in rows.iter().enumerate() {
+            let ids = linked_ids(row.get(&column.id).unwrap_or(&Value::Null));
+            if ids.is_empty() {
+                continue;
+            }
+            let message = match &known {
+                Ok(known) => {
+                    let missing: Vec<&str> =
+                        ids.into_iter().filter(|id| !known.contains(id)).collect();
+                    if missing.is_empty() {
+                        continue;
+                    }
+                    tr!(
+                        "relation.missing_rows",
+                        column = column.name,
+                        ids = missing.join(", ")
+                    )
+                }
+                Err(err) => err.to_string(),
+            };
+            issues.push(RowIssue {
+                row_index,
+                row_id: row_id(row).map(str::to_string),
+                column_id: Some(column.id.clone()),
+                kind: IssueKind::BrokenRelation,
+                severity: IssueSeverity::Error,
+                message,
+            });
+        }
+    }
+    issues
+}
+
+/// リレーション列で参照されている行の表示値を求める
+///
+/// # 引数
+/// * `rows` - 行データ
+/// * `schema` - スキーマ
+/// * `related` - 参照先のテーブル
+///
+/// # 戻り値
+/// リレーション列のIDごとの、参照先の_idと表示値（見つからない_idは含まない）
+pub fn relation_links(
+    rows: &[Value],
+    schema: &TableSchema,
+    related: &RelatedTables,
+) -> RelationLinks {
+    let mut links = RelationLinks::new();
+    for column in relation_columns(schema) {
+        let (target_rows, target_schema) = match related.get(&column.id) {
+            Some(Ok(table)) => (table.rows.as_slice(), &table.schema),
+            Some(Err(_)) => continue,
+            None => (rows, schema),
+        };
+        let display_column = relation_config(column)
+            .and_then(|config| config.display_column.clone())
+            .or_else(|| {
+                target_schema
+                    .columns
+                    .iter()
+                    .find(|candidate| !candidate.system && !candidate.id.starts_with('_'))
+                    .map(|candidate| candidate.id.clone())
+            })
+            .unwrap_or_else(|| "_id".to_string());
+        let index: HashMap<&str, &Value> = target_rows
+            .iter()
+            .filter_map(|target| row_id(target).map(|id| (id, target)))
+            .collect();
+
+        let mut values = HashMap::new();
+        for row in rows {
+            for id in linked_ids(row.get(&column.id).unwrap_or(&Value::Null)) {
+                if let Some(target) = index.get(id) {
+                    values.insert(id.to_string(), display_text(target, &display_column, id));
+                }
+            }
+        }
+        links.insert(column.id.clone(), values);
+    }
+    links
+}
+
+/// 削除された行を参照している行に、リレーション列の削除時の動作を適用する
+/// 同じフォルダのワークスペースと自分自身のリレーション列が対象で、cascadeで削除された行にも繰り返し適用する
+/// 親フォルダやサブフォルダのワークスペースは、利用者の開いていないファイルを書き換えないよう対象外とする
+///
+/// # 引数
+/// * `storage` - 行を削除するワークスペースのストレージ
+/// * `previous_rows` - 削除前の行データ
+/// * `rows` - 削除後の行データ（自分自身を参照する行への変更を反映する）
+/// * `schema` - スキーマ
+///
+/// # 戻り値
+/// 成功時は変更が必要な他のワークスペース、restrictの列が参照している場合はエラー
+pub fn apply_delete_rules(
+    storage: &dyn Storage,
+    previous_rows: &[Value],
+    rows: &mut Vec<Value>,
+    schema: &TableSchema,
+) -> Result<Vec<ReferenceUpdate>, WorkspaceError> {
+    let remaining: HashSet<&str> = rows.iter().filter_map(row_id).collect();
+    let deleted: HashSet<String> = previous_rows
+        .iter()
+        .filter_map(row_id)
+        .filter(|id| !remaining.contains(id))
+        .map(str::to_string)
+        .collect();
+    if deleted.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let data_path = storage.data_path();
+    let own_name = file_name(data_path);
+    // リレーション列を持つ同じフォルダのワークスペース（読み込めないものは対象外）
+    let mut others: Vec<ReferenceUpdate> = Vec::new();
+    if let Some(folder) = data_path.parent().filter(|folder| folder.is_dir()) {
+        for path in workspace_files(folder)? {
+            if file_name(&path) == own_name {
+                continue;
+            }
+            let Ok(other) = open_storage(&path) else {
+                continue;
+            };
+            let Ok(other_schema) = other.read_schema() else {
+                continue;
+            };
+            if !other_schema
+                .columns
+                .iter()
+                .any(|column| relation_config(column).is_some())
+            {
+                continue;
+            }
+            // 書き込み前に他の保存と競合していないか確認するため、内容より先にリビジョンを取得する
+            let Ok(revision) = other.revision() else {
+                continue;
+            };
+            let Ok(other_rows) = other.read_rows() else {
+                continue;
+            };
+            others.push(ReferenceUpdate {
+                storage: other,
+                schema: other_schema,
+                revision,
+                previous_rows: other_rows.clone(),
+                rows: other_rows,
+            });
+        }
+    }
+
+    let mut queue = VecDeque::from([(data_path.to_path_buf(), deleted)]);
+    while let Some((target, ids)) = queue.pop_front() {
+        let removed = apply_rules_to(rows, schema, data_path, &target, &ids)?;
+        if !removed.is_empty() {
+            queue.push_back((data_path.to_path_buf(), removed));
+        }
+        for other in &mut others {
+            let path = other.storage.data_path().to_path_buf();
+            let removed = apply_rules_to(&mut other.rows, &other.schema, &path, &target, &ids)?;
+            if !removed.is_empty() {
+                queue.push_back((path, removed));
+            }
+        }
+    }
+
+    others.retain(|other| other.previous_rows != other.rows);
+    Ok(others)
+}
+
+/// 1つのテーブルのうち、削除された行を参照しているリレーション列に削除時の動作を適用する
+///
+/// # 戻り値
+/// 成功時はcascadeで削除した行の_id、restrictの列が参照している場合は参照している行を示すエラー
+fn apply_rules_to(
+    rows: &mut Vec<Value>,
+    schema: &TableSchema,
+    table_path: &Path,
+    target: &Path,
+    deleted: &HashSet<String>,
+) -> Result<HashSet<String>, WorkspaceError> {
+    let mut removed = HashSet::new();
+    for column in relation_columns(schema) {
+        let references_target = relation_config(column).map_or(table_path == target, |config| {
+            target_path(table_path, &config.target).is_ok_and(|path| path == target)
+        });
+        if !references_target {
+            continue;
+        }
+        let on_delete = relation_config(column)
+            .map(|config| config.on_delete)
+            .unwrap_or_default();
+
+        let mut kept = Vec::with_capacity(rows.len());
+        for mut row in rows.drain(..) {
+            let references = linked_ids(row.get(&column.id).unwrap_or(&Value::Null))
+                .into_iter()
+                .any(|id| deleted.contains(id));
+            if !references {
+                kept.push(row);
+                continue;
+            }
+            match on_delete {
+                OnDelete::Restrict => {
+                    let id = row_id(&row).unwrap_or("?");
+                    let mut error = WorkspaceError::new(
+                        ErrorCode::Restricted,
+                        "relation.restricted",
+                        tr!(
+                            "relation.restricted",
+                            table = file_name(table_path),
+                            row = id,
+                            column = column.name
+                        ),
+                    )
+                    .with_path(table_path)
+                    .with_row(id);
+                    error.context.column_id = Some(column.id.clone());
+                    return Err(error);
+                }
+                OnDelete::Cascade => {
+                    if let Some(id) = row_id(&row) {
+                        removed.insert(id.to_string());
+                    }
+                }
+                OnDelete::Nullify => {
+                    if let Some(cell) = row.get_mut(&column.id) {
+                        *cell = match cell.take() {
+                            Value::Array(items) => Value::Array(
+                                items
+                                    .into_iter()
+                                    .filter(|item| {
+                                        item.as_str().is_none_or(|id| !deleted.contains(id))
+                                    })
+                                    .collect(),
+                            ),
+                            _ => Value::Null,
+                        };
+                    }
+                    kept.push(row);
+                }
+            }
+        }
+        *rows = kept;
+    }
+    Ok(removed)
+}
+
+/// 参照先のデータファイルを読み込み、計算列の値を求める
+fn read_related(path: &Path) -> Result<RelatedTable, WorkspaceError> {
+    let storage = open_storage(path)?;
+    if !storage.exists() {
+        return Err(WorkspaceError::new(
+            ErrorCode::NotFound,
+            "error.file_not_found",
+            tr!("error.file_not_found", path = path.display()),
+        )
+        .with_path(path));
+    }
+    let mut rows = storage.read_rows()?;
+    let schema = storage.read_schema()?;
+    evaluate_linked_columns(&mut rows, &schema, &RelatedTables::new());
+    evaluate_formulas(&mut rows, &schema, &RelatedTables::new())?;
+    Ok(RelatedTable { schema, rows })
+}
+
+/// 参照先のデータファイルのパス（ワークスペースのフォルダからの相対パス）
+/// 参照先が実在する場合は、シンボリックリンクを解決してもフォルダ内にあることを確かめる
+///
+/// # 引数
+/// * `data_path` - ワークスペースのデータファイルのパス
+/// * `target` - スキーマに指定された参照先
+///
+/// # 戻り値
+/// 成功時はフォルダのパスに参照先を連結したパス、フォルダの外を指す場合はスキーマのエラー
+pub fn target_path(data_path: &Path, target: &str) -> Result<PathBuf, WorkspaceError> {
+    let folder = data_path.parent().unwrap_or_else(|| Path::new("."));
+    let outside = || {
+        WorkspaceError::new(
+            ErrorCode::InvalidSchema,
+            "relation.target_outside",
+            tr!("relation.target_outside", target = target.trim()),
+        )
+    };
+    let path = relation_target_segments(target)
+        .ok_or_else(outside)?
+        .into_iter()
+        .fold(folder.to_path_buf(), |path, segment| path.join(segment));
+    if let (Ok(resolved), Ok(root)) = (path.canonicalize(), folder.canonicalize()) {
+        if !resolved.starts_with(&root) {
+            return Err(outside());
+        }
+    }
+    Ok(path)
+}
+
+/// リレーション型のカラム
+fn relation_columns(schema: &TableSchema) -> impl Iterator<Item = &ColumnDefinition> {
+    schema
+        .columns
+        .iter()
+        .filter(|column| column.column_type == ColumnType::Relation)
+}
+
+/// リレーション型のカラムの参照先の設定
+fn relation_config(column: &ColumnDefinition) -> Option<&RelationConfig> {
+    if column.column_type == ColumnType::Relation {
+        column.relation.as_ref()
+    } else {
+        None
+    }
+}
+
+/// リレーションのセルに含まれる_id（_id単体または配列）
+fn linked_ids(value: &Value) -> Vec<&str> {
+    match value {
+        Value::String(id) if !id.is_empty() => vec![id.as_str()],
+        Value::Array(items) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|id| !id.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 参照先の行の表示値（表示に使うカラムが空の場合は_id）
+fn display_text(row: &Value, column: &str, id: &str) -> String {
+    match row.get(column) {
+        Some(Value::String(text)) if !text.trim().is_empty() => text.clone(),
+        Some(Value::Array(items)) if !items.is_empty() => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map_or_else(|| item.to_string(), str::to_string)
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        Some(Value::Number(number)) => number.to_string(),
+        Some(Value::Bool(flag)) => flag.to_string(),
+        _ => id.to_string(),
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::JsonStorage;
+    use serde_json::json;
+    use std::fs;
+
+    fn relation(id: &str, target: &str, on_delete: OnDelete) -> ColumnDefinition {
+        let mut column = ColumnDefinition::new(id, id, ColumnType::Relation);
+        column.relation = Some(RelationConfig {
+            target: target.to_string(),
+            display_column: None,
+            on_delete,
+        });
+        column
+    }
+
+    /// フォルダにワークスペースを作成する
+    fn workspace(
+        folder: &Path,
+        file_name: &str,
+        columns: Vec<ColumnDefinition>,
+        rows: Vec<Value>,
+    ) -> (JsonStorage, TableSchema, Vec<Value>) {
+        let storage = JsonStorage::open(folder.join(file_name)).unwrap();
+        let mut schema = TableSchema::new(file_name, "2024-01-01T00:00:00Z");
+        schema.columns.extend(columns);
+        storage.write_table(&rows, &schema).unwrap();
+        (storage, schema, rows)
+    }
+
+    fn customers(folder: &Path) -> (JsonStorage, TableSchema, Vec<Value>) {
+        workspace(
+            folder,
+            "customers.json",
+            vec![ColumnDefinition::new("name", "Name", ColumnType::Text)],
+            vec![
+                json!({"_id": "c1", "name": "Alice"}),
+                json!({"_id": "c2", "name": "Bob"}),
+            ],
+        )
+    }
+
+    /// c1を削除して削除時の動作を適用する
+    fn delete_c1(
+        storage: &JsonStorage,
+        schema: &TableSchema,
+        previous: &[Value],
+    ) -> Result<(Vec<Value>, Vec<ReferenceUpdate>), WorkspaceError> {
+        let mut rows: Vec<Value> = previous
+            .iter()
+            .filter(|row| row_id(row) != Some("c1"))
+            .cloned()
+            .collect();
+        let updates = apply_delete_rules(storage, previous, &mut rows, schema)?;
+        Ok((rows, updates))
+    }
+
+    fn ids(rows: &[Value]) -> Vec<&str> {
+        rows.iter().filter_map(row_id).collect()
+    }
+
+    #[test]
+    fn restrict_rejects_deleting_referenced_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, schema, rows) = customers(dir.path());
+        workspace(
+            dir.path(),
+            "orders.json",
+            vec![relation("customer", "customers.json", OnDelete::Restrict)],
+            vec![json!({"_id": "o1", "customer": "c1"})],
+        );
+
+        let error = delete_c1(&storage, &schema, &rows).err().unwrap();
+        assert_eq!(error.code, ErrorCode::Restricted);
+        assert_eq!(error.context.row_id.as_deref(), Some("o1"));
+        assert_eq!(error.context.column_id.as_deref(), Some("customer"));
+
+        // 参照されていない行は削除できる
+        let mut remaining = vec![rows[0].clone()];
+        assert!(apply_delete_rules(&storage, &rows, &mut remaining, &schema)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn cascade_deletes_referencing_rows_transitively() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, schema, rows) = customers(dir.path());
+        workspace(
+            dir.path(),
+            "orders.json",
+            vec![relation("customer", "customers.json", OnDelete::Cascade)],
+            vec![
+                json!({"_id": "o1", "customer": "c1"}),
+                json!({"_id": "o2", "customer": "c2"}),
+            ],
+        );
+        workspace(
+            dir.path(),
+            "lines.json",
+            vec![relation("order", "orders.json", OnDelete::Cascade)],
+            vec![
+                json!({"_id": "l1", "order": "o1"}),
+                json!({"_id": "l2", "order": "o2"}),
+            ],
+        );
+
+        let (rows, mut updates) = delete_c1(&storage, &schema, &rows).unwrap();
+        assert_eq!(ids(&rows), ["c2"]);
+        updates.sort_by(|a, b| a.storage.data_path().cmp(b.storage.data_path()));
+        let changed: Vec<(String, Vec<&str>)> = updates
+            .iter()
+            .map(|update| (file_name(update.storage.data_path()), ids(&update.rows)))
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                ("lines.json".to_string(), vec!["l2"]),
+                ("orders.json".to_string(), vec!["o2"])
+            ]
+        );
+    }
+
+    #[test]
+    fn rules_leave_workspaces_in_parent_folders_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("master");
+        fs::create_dir(&sub).unwrap();
+        let (storage, schema, rows) = customers(&sub);
+        let (orders, _, orders_rows) = workspace(
+            dir.path(),
+            "orders.json",
+            vec![relation(
+                "customer",
+                "master/customers.json",
+                OnDelete::Cascade,
+            )],
+            vec![
+                json!({"_id": "o1", "customer": "c1"}),
+                json!({"_id": "o2", "customer": "c2"}),
+            ],
+        );
+        let before = fs::read_to_string(orders.data_path()).unwrap();
+
+        let (rows, updates) = delete_c1(&storage, &schema, &rows).unwrap();
+        assert_eq!(ids(&rows), ["c2"]);
+        assert!(updates.is_empty());
+        assert_eq!(fs::read_to_string(orders.data_path()).unwrap(), before);
+        assert_eq!(ids(&orders.read_rows().unwrap()), ids(&orders_rows));
+    }
+
+    #[test]
+    fn nullify_removes_deleted_ids_from_cells() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut schema, mut rows) = customers(dir.path());
+        // 自分自身への参照にも適用する
+        schema
+            .columns
+            .push(relation("referrer", "customers.json", OnDelete::Nullify));
+        rows[1]["referrer"] = json!("c1");
+        workspace(
+            dir.path(),
+            "orders.json",
+            vec![relation("customers", "customers.json", OnDelete::Nullify)],
+            vec![
+                json!({"_id": "o1", "customers": ["c1", "c2"]}),
+                json!({"_id": "o2", "customers": "c1"}),
+            ],
+        );
+
+        let (rows, updates) = delete_c1(&storage, &schema, &rows).unwrap();
+        assert_eq!(
+            rows,
+            [json!({"_id": "c2", "name": "Bob", "referrer": null})]
+        );
+        assert_eq!(updates.len(), 1);
+        assert_eq!(
+            updates[0].rows,
+            [
+                json!({"_id": "o1", "customers": ["c2"]}),
+                json!({"_id": "o2", "customers": null})
+            ]
+        );
+    }
+
+    #[test]
+    fn target_path_accepts_subfolders() {
+        let folder = Path::new("/work");
+        let data_path = folder.join("orders.json");
+        assert_eq!(
+            target_path(&data_path, "customers.json").unwrap(),
+            folder.join("customers.json")
+        );
+        assert_eq!(
+            target_path(&data_path, " master/items.db ").unwrap(),
+            folder.join("master").join("items.db")
+        );
+        assert_eq!(
+            target_path(&data_path, "master\\items.db").unwrap(),
+            folder.join("master").join("items.db")
+        );
+    }
+
+    #[test]
+    fn target_path_rejects_paths_outside_folder() {
+        let data_path = Path::new("/work/orders.json");
+        for target in [
+            "../secret.json",
+            "master/../../secret.json",
+            "/etc/passwd.json",
+            "C:/data.json",
+            ".backups/orders.json",
+            "master//items.db",
+            "",
+        ] {
+            assert!(target_path(data_path, target).is_err(), "{target}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn target_path_rejects_symlinks_outside_folder() {
+        let root = tempfile::tempdir().unwrap();
+        let folder = root.path().join("work");
+        let outside = root.path().join("outside");
+        fs::create_dir_all(&folder).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("items.json"), "[]").unwrap();
+        std::os::unix::fs::symlink(&outside, folder.join("link")).unwrap();
+
+        let data_path = folder.join("orders.json");
+        assert!(target_path(&data_path, "link/items.json").is_err());
+        fs::create_dir_all(folder.join("master")).unwrap();
+        fs::write(folder.join("master/items.json"), "[]").unwrap();
+        assert!(target_path(&data_path, "master/items.json").is_ok());
+    }
+}
@@ -0,0 +1,65 @@
+// 行データ（JSONオブジェクト）を比較するときに使う共通の処理
+use serde_json::{Map, Value};
+
+/// 比較に含めない管理用のキー（保存のたびに変わるため）
+pub const BOOKKEEPING_KEYS: [&str; 2] = ["_order", "_updated"];
+
+/// 管理用のキーか判定する
+pub fn is_bookkeeping(key: &str) -> bool {
+    BOOKKEEPING_KEYS.contains(&key)
+}
+
+/// 行の_idを取得する
+pub fn row_id(row: &Value) -> Option<&str> {
+    row.get("_id").and_then(Value::as_str)
+}
+
+/// オブジェクトとして取り出した行の_idを取得する
+pub fn row_id_of(object: &Map<String, Value>) -> Option<&str> {
+    object.get("_id").and_then(Value::as_str)
+}
+
+/// _idから行の位置を探す
+pub fn find_row(rows: &[Value], id: &str) -> Option<usize> {
+    rows.iter().position(|row| row_id(row) == Some(id))
+}
+
+/// 2つの行で値が異なるセルを求める（nullはキーがないものとして扱う）
+///
+/// # 戻り値
+/// 列IDと変更前・変更後の値の組。管理用のキーは含めない
+pub fn changed_cells(before: &Value, after: &Value) -> Vec<(String, Value, Value)> {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+    before
+        .keys()
+        .chain(after.keys().filter(|key| !before.contains_key(*key)))
+        .filter(|key| !is_bookkeeping(key))
+        .filter_map(|key| {
+            let old = before.get(key).cloned().unwrap_or(Value::Null);
+            let new = after.get(key).cloned().unwrap_or(Value::Null);
+            (old != new).then(|| (key.clone(), old, new))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn changed_cells_ignores_bookkeeping_and_null() {
+        let before = json!({"_id": "row_a", "_order": 1, "name": "A", "memo": null});
+        let after = json!({"_id": "row_a", "_order": 2, "name": "B", "_updated": "now"});
+        let cells = changed_cells(&before, &after);
+        assert_eq!(cells, vec![("name".to_string(), json!("A"), json!("B"))]);
+    }
+
+    #[test]
+    fn row_id_reads_string_id() {
+        assert_eq!(row_id(&json!({"_id": "row_a"})), Some("row_a"));
+        assert_eq!(row_id(&json!({"_id": 1})), None);
+    }
+}
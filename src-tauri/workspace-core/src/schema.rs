@@ -0,0 +1,663 @@
+// スキーマファイル(.schema.json)の型定義と検証処理
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::formula::formula_problems;
+use crate::{tr, ErrorCode, WorkspaceError};
+
+/// 現在サポートしているスキーマのバージョン
+pub const SCHEMA_VERSION: &str = "1.0";
+
+/// カラムのデータ型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    Text,
+    Number,
+    Checkbox,
+    Multiselect,
+    Relation,
+    /// 同じ行の他のカラムから計算する列（値はファイルに保存しない）
+    Formula,
+    /// リレーション先の行のカラムの値を表示する列（値はファイルに保存しない）
+    Lookup,
+    /// リレーション先の行のカラムを集計する列（値はファイルに保存しない）
+    Rollup,
+}
+
+impl ColumnType {
+    /// 組み込みのすべてのカラム型
+    pub const ALL: [ColumnType; 8] = [
+        ColumnType::Text,
+        ColumnType::Number,
+        ColumnType::Checkbox,
+        ColumnType::Multiselect,
+        ColumnType::Relation,
+        ColumnType::Formula,
+        ColumnType::Lookup,
+        ColumnType::Rollup,
+    ];
+
+    /// スキーマファイル上での型名を返す
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColumnType::Text => "text",
+            ColumnType::Number => "number",
+            ColumnType::Checkbox => "checkbox",
+            ColumnType::Multiselect => "multiselect",
+            ColumnType::Relation => "relation",
+            ColumnType::Formula => "formula",
+            ColumnType::Lookup => "lookup",
+            ColumnType::Rollup => "rollup",
+        }
+    }
+
+    /// 読み込み時に値を求める列か（値はファイルに保存しない）
+    pub fn is_computed(self) -> bool {
+        matches!(
+            self,
+            ColumnType::Formula | ColumnType::Lookup | ColumnType::Rollup
+        )
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// カラム定義
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnDefinition {
+    /// カラムの一意なID（行オブジェクトのキー）
+    pub id: String,
+    /// 表示名
+    pub name: String,
+    /// データ型
+    #[serde(rename = "type")]
+    pub column_type: ColumnType,
+    /// 列の幅（ピクセル）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    /// 必須かどうか
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub required: bool,
+    /// 非表示かどうか
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub hidden: bool,
+    /// システム列かどうか（_id, _created等）
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub system: bool,
+    /// フォーマット指定（将来の拡張用）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// 計算列の式（formula型の場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formula: Option<String>,
+    /// リレーションの参照先（relation型の場合のみ、省略時は同じテーブルの行を参照する）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relation: Option<RelationConfig>,
+    /// 表示するリレーション先のカラム（lookup型の場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lookup: Option<LookupConfig>,
+    /// 集計するリレーション先のカラム（rollup型の場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollup: Option<RollupConfig>,
+    /// 未知のフィールド（フロントエンドの拡張用にそのまま保持する）
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ColumnDefinition {
+    /// 新しいカラム定義を作成する
+    ///
+    /// # 引数
+    /// * `id` - カラムID
+    /// * `name` - 表示名
+    /// * `column_type` - データ型
+    pub fn new(id: impl Into<String>, name: impl Into<String>, column_type: ColumnType) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            column_type,
+            width: None,
+            required: false,
+            hidden: false,
+            system: false,
+            format: None,
+            formula: None,
+            relation: None,
+            lookup: None,
+            rollup: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+/// 参照先の行が削除されたときの動作
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnDelete {
+    /// 参照されている行の削除を拒否する
+    #[default]
+    Restrict,
+    /// 参照している行も削除する
+    Cascade,
+    /// 参照している行から削除された_idを取り除く
+    Nullify,
+}
+
+/// リレーション列の参照先
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationConfig {
+    /// 参照先のワークスペースのデータファイル
+    /// ワークスペースのフォルダからの相対パス（例: "customers.json"、"master/items.db"）で、
+    /// フォルダの外（".."や絶対パス）や.で始まるファイル・フォルダは指定できない
+    pub target: String,
+    /// 表示値に使う参照先のカラムID（省略時は参照先の最初のカラム）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_column: Option<String>,
+    /// 参照先の行が削除されたときの動作
+    /// 参照先と同じフォルダにあるワークスペースの列だけに適用する（サブフォルダの参照先では、親フォルダからの参照は対象外）
+    #[serde(default)]
+    pub on_delete: OnDelete,
+}
+
+/// リレーションの参照先をフォルダからの相対パスの要素に分ける
+/// 空の要素、.で始まる要素（".."や隠しフォルダ）、ドライブ指定を含む場合はNone
+///
+/// # 引数
+/// * `target` - 参照先（"/" または "\\" 区切り）
+pub fn relation_target_segments(target: &str) -> Option<Vec<&str>> {
+    target
+        .trim()
+        .split(['/', '\\'])
+        .map(|segment| {
+            let valid = !segment.is_empty() && !segment.starts_with('.') && !segment.contains(':');
+            valid.then_some(segment)
+        })
+        .collect()
+}
+
+/// lookup列の設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LookupConfig {
+    /// 同じスキーマのリレーション列のID
+    pub relation: String,
+    /// 表示するリレーション先のカラム（IDまたは表示名）
+    pub column: String,
+}
+
+/// rollup列の集計方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RollupFunction {
+    /// リレーション先の行数
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    /// 値を「, 」で連結した文字列
+    Concat,
+}
+
+/// rollup列の設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollupConfig {
+    /// 同じスキーマのリレーション列のID
+    pub relation: String,
+    /// 集計するリレーション先のカラム（IDまたは表示名、count の場合は省略できる）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    pub function: RollupFunction,
+}
+
+/// スキーマのメタデータ
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaMetadata {
+    /// 作成日時（RFC3339）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    /// 最終更新日時（RFC3339）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    /// 行数
+    #[serde(default)]
+    pub row_count: usize,
+    /// 未知のフィールド
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// スキーマの拡張情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaExtensions {
+    /// 利用可能なカラム型
+    #[serde(default = "default_available_types")]
+    pub available_types: Vec<ColumnType>,
+    /// 未知のフィールド
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for SchemaExtensions {
+    fn default() -> Self {
+        Self {
+            available_types: default_available_types(),
+            extra: Map::new(),
+        }
+    }
+}
+
+/// テーブルスキーマ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableSchema {
+    /// スキーマバージョン
+    pub version: String,
+    /// テーブル名
+    pub table_name: String,
+    /// カラム定義の配列
+    pub columns: Vec<ColumnDefinition>,
+    /// メタデータ（行数、更新日時等）
+    #[serde(default)]
+    pub metadata: SchemaMetadata,
+    /// 拡張情報
+    #[serde(default)]
+    pub extensions: SchemaExtensions,
+}
+
+impl TableSchema {
+    /// デフォルトのスキーマを作成する（_id列のみを持つ）
+    ///
+    /// # 引数
+    /// * `table_name` - テーブル名
+    /// * `created_at` - 作成日時
+    pub fn new(table_name: impl Into<String>, created_at: &str) -> Self {
+        let mut id_column = ColumnDefinition::new("_id", "ID", ColumnType::Text);
+        id_column.hidden = true;
+        id_column.system = true;
+
+        let mut extensions = SchemaExtensions::default();
+        extensions.extra.insert(
+            "future".into(),
+            Value::String("拡張型を追加できる設計とする".into()),
+        );
+
+        Self {
+            version: SCHEMA_VERSION.to_string(),
+            table_name: table_name.into(),
+            columns: vec![id_column],
+            metadata: SchemaMetadata {
+                created_at: Some(created_at.to_string()),
+                updated_at: Some(created_at.to_string()),
+                row_count: 0,
+                extra: Map::new(),
+            },
+            extensions,
+        }
+    }
+
+    /// 行数と更新日時でメタデータを更新する
+    ///
+    /// # 引数
+    /// * `row_count` - 行数
+    /// * `updated_at` - 更新日時
+    pub fn touch(&mut self, row_count: usize, updated_at: &str) {
+        self.metadata.row_count = row_count;
+        self.metadata.updated_at = Some(updated_at.to_string());
+        if self.metadata.created_at.is_none() {
+            self.metadata.created_at = Some(updated_at.to_string());
+        }
+    }
+
+    /// スキーマの整合性を検証する
+    ///
+    /// # 戻り値
+    /// 問題がなければ`Ok(())`、問題がある場合はすべての問題を`details`に列挙したエラー
+    pub fn validate(&self) -> Result<(), WorkspaceError> {
+        let mut problems = Vec::new();
+
+        if self.version.trim().is_empty() {
+            problems.push(problem("schema.version_empty", tr!("schema.version_empty")));
+        } else if self.version != SCHEMA_VERSION {
+            problems.push(problem(
+                "schema.version_unsupported",
+                tr!(
+                    "schema.version_unsupported",
+                    version = self.version,
+                    supported = SCHEMA_VERSION
+                ),
+            ));
+        }
+
+        if self.table_name.trim().is_empty() {
+            problems.push(problem(
+                "schema.table_name_empty",
+                tr!("schema.table_name_empty"),
+            ));
+        }
+
+        if self.columns.is_empty() {
+            problems.push(problem("schema.columns_empty", tr!("schema.columns_empty")));
+        }
+
+        let mut seen = HashSet::new();
+        for (index, column) in self.columns.iter().enumerate() {
+            if column.id.trim().is_empty() {
+                problems.push(problem(
+                    "schema.column_id_empty",
+                    tr!("schema.column_id_empty", index = index),
+                ));
+                continue;
+            }
+            if !seen.insert(column.id.as_str()) {
+                problems.push(
+                    problem(
+                        "schema.column_id_duplicate",
+                        tr!("schema.column_id_duplicate", id = column.id),
+                    )
+                    .with_column(&column.id),
+                );
+            }
+            if column.name.trim().is_empty() {
+                problems.push(
+                    problem(
+                        "schema.column_name_empty",
+                        tr!("schema.column_name_empty", id = column.id),
+                    )
+                    .with_column(&column.id),
+                );
+            }
+            if column.width == Some(0) {
+                problems.push(
+                    problem(
+                        "schema.column_width_zero",
+                        tr!("schema.column_width_zero", id = column.id),
+                    )
+                    .with_column(&column.id),
+                );
+            }
+            if let Some(relation) = &column.relation {
+                let target = relation.target.trim();
+                if target.is_empty() {
+                    problems.push(
+                        problem(
+                            "schema.relation_target_empty",
+                            tr!("schema.relation_target_empty", id = column.id),
+                        )
+                        .with_column(&column.id),
+                    );
+                } else if relation_target_segments(target).is_none() {
+                    problems.push(
+                        problem(
+                            "schema.relation_target_path",
+                            tr!(
+                                "schema.relation_target_path",
+                                id = column.id,
+                                target = target
+                            ),
+                        )
+                        .with_column(&column.id),
+                    );
+                }
+            }
+            problems.extend(self.linked_column_problems(column));
+        }
+
+        if !self.columns.iter().any(|column| column.id == "_id") {
+            problems.push(problem(
+                "schema.id_column_missing",
+                tr!("schema.id_column_missing"),
+            ));
+        }
+
+        // 式の構文・型・循環参照
+        problems.extend(formula_problems(self));
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(WorkspaceError::invalid_schema("schema.invalid", problems))
+        }
+    }
+
+    /// lookup・rollup列の設定を検証する
+    fn linked_column_problems(&self, column: &ColumnDefinition) -> Vec<WorkspaceError> {
+        let (relation, target_column, column_required) = match column.column_type {
+            ColumnType::Lookup => match &column.lookup {
+                Some(config) => (&config.relation, config.column.as_str(), true),
+                None => {
+                    return vec![problem(
+                        "schema.lookup_column_missing",
+                        tr!("schema.lookup_column_missing", id = column.id),
+                    )
+                    .with_column(&column.id)]
+                }
+            },
+            ColumnType::Rollup => match &column.rollup {
+                Some(config) => (
+                    &config.relation,
+                    config.column.as_deref().unwrap_or_default(),
+                    config.function != RollupFunction::Count,
+                ),
+                None => {
+                    return vec![problem(
+                        "schema.rollup_column_missing",
+                        tr!("schema.rollup_column_missing", id = column.id),
+                    )
+                    .with_column(&column.id)]
+                }
+            },
+            _ => return Vec::new(),
+        };
+
+        let mut problems = Vec::new();
+        let is_relation = self.columns.iter().any(|candidate| {
+            &candidate.id == relation && candidate.column_type == ColumnType::Relation
+        });
+        if !is_relation {
+            problems.push(
+                problem(
+                    "schema.not_relation",
+                    tr!("schema.not_relation", id = column.id, relation = relation),
+                )
+                .with_column(&column.id),
+            );
+        }
+        if column_required && target_column.trim().is_empty() {
+            problems.push(
+                problem(
+                    "schema.relation_column_empty",
+                    tr!("schema.relation_column_empty", id = column.id),
+                )
+                .with_column(&column.id),
+            );
+        }
+        problems
+    }
+}
+
+/// スキーマの問題を作成する
+fn problem(message_key: &'static str, message: String) -> WorkspaceError {
+    WorkspaceError::new(ErrorCode::InvalidSchema, message_key, message)
+}
+
+/// JSON文字列からスキーマを読み込み、検証する
+///
+/// # 引数
+/// * `contents` - スキーマファイルの内容
+///
+/// # 戻り値
+/// 成功時は検証済みのスキーマ、失敗時はエラー（JSONの形式が不正な場合は行・列番号を含む）
+pub fn parse_schema(contents: &str) -> Result<TableSchema, WorkspaceError> {
+    let schema: TableSchema = serde_json::from_str(contents).map_err(|err| {
+        let mut error = WorkspaceError::new(
+            ErrorCode::InvalidSchema,
+            "error.schema_format",
+            tr!(
+                "error.schema_format",
+                line = err.line(),
+                column = err.column()
+            ),
+        );
+        error.context.line = Some(err.line());
+        error.context.column = Some(err.column());
+        error.with_source(&err)
+    })?;
+    schema.validate()?;
+    Ok(schema)
+}
+
+fn default_available_types() -> Vec<ColumnType> {
+    ColumnType::ALL.to_vec()
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// _id列とテキスト列 name を持つスキーマ
+    fn schema() -> TableSchema {
+        let mut schema = TableSchema::new("test", "2024-01-01T00:00:00Z");
+        schema
+            .columns
+            .push(ColumnDefinition::new("name", "Name", ColumnType::Text));
+        schema
+    }
+
+    /// 検証エラーに含まれる個々の問題のキーとカラムID
+    fn problems(schema: &TableSchema) -> Vec<(&'static str, Option<String>)> {
+        let error = schema.validate().unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidSchema);
+        assert_eq!(error.message_key, "schema.invalid");
+        error
+            .details
+            .into_iter()
+            .map(|problem| (problem.message_key, problem.context.column_id))
+            .collect()
+    }
+
+    #[test]
+    fn new_schema_is_valid() {
+        schema().validate().unwrap();
+        let text = serde_json::to_string(&schema()).unwrap();
+        assert_eq!(parse_schema(&text).unwrap().columns.len(), 2);
+    }
+
+    #[test]
+    fn duplicate_column_ids_are_rejected() {
+        let mut schema = schema();
+        schema
+            .columns
+            .push(ColumnDefinition::new("name", "Name 2", ColumnType::Number));
+        assert_eq!(
+            problems(&schema),
+            vec![("schema.column_id_duplicate", Some("name".to_string()))]
+        );
+        // まとめたエラーにも最初の問題のカラムが入る
+        assert_eq!(
+            schema.validate().unwrap_err().context.column_id.as_deref(),
+            Some("name")
+        );
+    }
+
+    #[test]
+    fn all_problems_are_listed() {
+        let mut schema = schema();
+        schema.table_name = " ".into();
+        schema.columns.retain(|column| column.id != "_id");
+        schema.columns[0].width = Some(0);
+        let keys: Vec<_> = problems(&schema).into_iter().map(|(key, _)| key).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "schema.table_name_empty",
+                "schema.column_width_zero",
+                "schema.id_column_missing"
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_column_types_are_rejected() {
+        let contents = json!({
+            "version": SCHEMA_VERSION,
+            "table_name": "test",
+            "columns": [
+                { "id": "_id", "name": "ID", "type": "text" },
+                { "id": "when", "name": "When", "type": "datetime" }
+            ],
+            "metadata": {}
+        })
+        .to_string();
+        let error = parse_schema(&contents).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidSchema);
+        assert_eq!(error.message_key, "error.schema_format");
+        assert!(error.context.line.is_some());
+    }
+
+    #[test]
+    fn unsupported_versions_are_rejected() {
+        let mut schema = schema();
+        schema.version = "9.9".into();
+        assert_eq!(
+            problems(&schema),
+            vec![("schema.version_unsupported", None)]
+        );
+    }
+
+    #[test]
+    fn bad_column_options_are_rejected() {
+        let mut schema = schema();
+        let mut relation = ColumnDefinition::new("customer", "Customer", ColumnType::Relation);
+        relation.relation = Some(RelationConfig {
+            target: "../customers.json".into(),
+            display_column: None,
+            on_delete: OnDelete::default(),
+        });
+        schema.columns.push(relation);
+        schema.columns.push(ColumnDefinition::new(
+            "missing",
+            "Missing",
+            ColumnType::Lookup,
+        ));
+        let mut lookup = ColumnDefinition::new("city", "City", ColumnType::Lookup);
+        lookup.lookup = Some(LookupConfig {
+            relation: "name".into(),
+            column: String::new(),
+        });
+        schema.columns.push(lookup);
+
+        assert_eq!(
+            problems(&schema),
+            vec![
+                ("schema.relation_target_path", Some("customer".to_string())),
+                ("schema.lookup_column_missing", Some("missing".to_string())),
+                ("schema.not_relation", Some("city".to_string())),
+                ("schema.relation_column_empty", Some("city".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn formula_problems_keep_their_column() {
+        let mut schema = schema();
+        let mut formula = ColumnDefinition::new("total", "Total", ColumnType::Formula);
+        formula.formula = Some("{missing} + 1".into());
+        schema.columns.push(formula);
+        assert_eq!(
+            problems(&schema),
+            vec![("formula.column_error", Some("total".to_string()))]
+        );
+    }
+}
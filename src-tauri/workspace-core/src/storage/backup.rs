@@ -0,0 +1,563 @@
+// 保存前の内容を日時付きのバックアップとして残し、保持ルールに従って間引く処理
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{JsonStorage, SqliteStorage, Storage, StorageKind};
+use crate::{tr, ErrorCode, WorkspaceError};
+
+/// バックアップを保存するディレクトリ名（ワークスペースと同じフォルダに作成する）
+const BACKUP_DIR_NAME: &str = ".backups";
+
+/// 保持ルールを保存するファイル名（バックアップディレクトリ内）
+const POLICY_FILE_NAME: &str = "policy.json";
+
+/// バックアップIDの書式（作成日時、UTC）
+const ID_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// バックアップの保持ルール
+/// 直近の件数、現在から遡った期間内の1時間ごと・1日ごとの最新のいずれかに該当するバックアップを残し、
+/// 合計サイズが上限を超える分は古いものから削除する
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupPolicy {
+    /// 直近から無条件に残す件数（最新の1件は常に残す）
+    pub keep_last: usize,
+    /// 現在から何時間前までのバックアップについて、1時間ごとに最新の1件を残すか
+    pub keep_hourly: usize,
+    /// 現在から何日前までのバックアップについて、1日ごとに最新の1件を残すか
+    pub keep_daily: usize,
+    /// バックアップの合計サイズの上限（バイト）
+    pub max_total_bytes: u64,
+    /// 保存時のバックアップの最短間隔（分）
+    /// 前回のバックアップからこの時間が経つまでは、保存しても新しいバックアップを作らない（0の場合は毎回作る）
+    pub min_interval_minutes: u64,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            keep_last: 10,
+            keep_hourly: 24,
+            keep_daily: 14,
+            max_total_bytes: 100 * 1024 * 1024,
+            min_interval_minutes: 0,
+        }
+    }
+}
+
+/// バックアップの一覧に表示する情報
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    /// バックアップID（復元時に指定する）
+    pub id: String,
+    /// 作成日時
+    pub created_at: String,
+    /// 合計サイズ（バイト）
+    pub size: u64,
+    /// 行数（読み込めない場合はNone）
+    pub row_count: Option<usize>,
+}
+
+/// バックアップディレクトリ内の1件
+struct BackupEntry {
+    id: String,
+    created_at: DateTime<Utc>,
+    path: PathBuf,
+    size: u64,
+}
+
+/// ワークスペースのバックアップを保存するディレクトリ
+/// 例: /path/data.json → /path/.backups/data.json/
+pub fn backup_dir(storage: &dyn Storage) -> PathBuf {
+    let data_path = storage.data_path();
+    let parent = data_path.parent().unwrap_or_else(|| Path::new("."));
+    parent
+        .join(BACKUP_DIR_NAME)
+        .join(data_path.file_name().unwrap_or_default())
+}
+
+/// 保持ルールを読み込む（保存されていない場合は既定値）
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+///
+/// # 戻り値
+/// 成功時は保持ルール、失敗時はエラー
+pub fn read_policy(storage: &dyn Storage) -> Result<BackupPolicy, WorkspaceError> {
+    let path = backup_dir(storage).join(POLICY_FILE_NAME);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(BackupPolicy::default())
+        }
+        Err(err) => return Err(WorkspaceError::io(err, &path)),
+    };
+    serde_json::from_str(&contents).map_err(|err| {
+        WorkspaceError::new(
+            ErrorCode::InvalidJson,
+            "backup.policy_unreadable",
+            tr!("backup.policy_unreadable", error = err),
+        )
+        .with_path(&path)
+        .with_source(&err)
+    })
+}
+
+/// 保持ルールを保存し、新しいルールで既存のバックアップを間引く
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `policy` - 保持ルール
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、失敗時はエラー
+pub fn write_policy(storage: &dyn Storage, policy: &BackupPolicy) -> Result<(), WorkspaceError> {
+    let dir = backup_dir(storage);
+    let path = dir.join(POLICY_FILE_NAME);
+    fs::create_dir_all(&dir).map_err(|err| WorkspaceError::io(err, &dir))?;
+    fs::write(
+        &path,
+        serde_json::to_string_pretty(policy).map_err(|err| WorkspaceError::json(err, &path))?,
+    )
+    .map_err(|err| WorkspaceError::io(err, &path))?;
+    prune_backups(storage, policy).map(|_| ())
+}
+
+/// 現在保存されている内容をバックアップし、保持ルールに従って古いバックアップを削除する
+/// 保存の前に呼び出す（ストレージの書き込み自体はバックアップを作らない）
+/// 前回のバックアップから保持ルールの最短間隔が経っていない場合は何もしない
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、失敗時はエラー
+pub fn rotate_backups(storage: &dyn Storage) -> Result<(), WorkspaceError> {
+    let policy = read_policy(storage)?;
+    let interval = i64::try_from(policy.min_interval_minutes)
+        .ok()
+        .and_then(Duration::try_minutes)
+        .unwrap_or(Duration::MAX);
+    if let Some(latest) = read_entries(&backup_dir(storage))?.first() {
+        if Utc::now().signed_duration_since(latest.created_at) < interval {
+            return Ok(());
+        }
+    }
+    if create_backup(storage)?.is_some() {
+        prune_backups(storage, &policy)?;
+    }
+    Ok(())
+}
+
+/// 現在保存されている内容を新しいバックアップとしてコピーする
+/// 保存先がまだ存在しない場合や、最新のバックアップとリビジョンが同じ場合は作成しない
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+///
+/// # 戻り値
+/// 成功時は作成したバックアップのID（作成しなかった場合はNone）、失敗時はエラー
+pub fn create_backup(storage: &dyn Storage) -> Result<Option<String>, WorkspaceError> {
+    let sources: Vec<PathBuf> = storage
+        .watch_paths()
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect();
+    if sources.is_empty() {
+        return Ok(None);
+    }
+
+    let dir = backup_dir(storage);
+    if let Some(latest) = read_entries(&dir)?.first() {
+        let saved = storage_in(storage, &latest.path)
+            .ok()
+            .and_then(|backup| backup.revision().ok());
+        if saved.is_some() && saved == storage.revision().ok() {
+            return Ok(None);
+        }
+    }
+
+    // 同じミリ秒に複数作成された場合は連番を付ける
+    let stamp = Utc::now().format(ID_FORMAT).to_string();
+    let mut id = stamp.clone();
+    let mut counter = 1;
+    while dir.join(&id).exists() {
+        counter += 1;
+        id = format!("{stamp}-{counter}");
+    }
+
+    copy_files(&sources, &dir.join(&id))?;
+    Ok(Some(id))
+}
+
+/// ファイルを新しいディレクトリにコピーする
+/// 一時ディレクトリにコピーしてから名前を変え、途中までのコピーが一覧に出ないようにする
+///
+/// # 引数
+/// * `sources` - コピーするファイル
+/// * `target` - コピー先のディレクトリ（まだ存在しないこと）
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、失敗時はエラー
+pub(super) fn copy_files(sources: &[PathBuf], target: &Path) -> Result<(), WorkspaceError> {
+    let mut staging = target.as_os_str().to_os_string();
+    staging.push(".tmp");
+    let staging = PathBuf::from(staging);
+    fs::create_dir_all(&staging).map_err(|err| WorkspaceError::io(err, &staging))?;
+    for source in sources {
+        fs::copy(source, staging.join(file_name(source)?))
+            .map_err(|err| WorkspaceError::io(err, source))?;
+    }
+    fs::rename(&staging, target).map_err(|err| WorkspaceError::io(err, target))
+}
+
+/// 保持ルールに該当しないバックアップを削除する
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `policy` - 保持ルール
+///
+/// # 戻り値
+/// 成功時は削除したバックアップのID、失敗時はエラー
+pub fn prune_backups(
+    storage: &dyn Storage,
+    policy: &BackupPolicy,
+) -> Result<Vec<String>, WorkspaceError> {
+    let entries = read_entries(&backup_dir(storage))?;
+    let keep = entries_to_keep(&entries, policy, Utc::now());
+
+    let mut removed = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        if !keep.contains(&index) {
+            fs::remove_dir_all(&entry.path).map_err(|err| WorkspaceError::io(err, &entry.path))?;
+            removed.push(entry.id.clone());
+        }
+    }
+    Ok(removed)
+}
+
+/// バックアップを新しい順に一覧する
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+///
+/// # 戻り値
+/// 成功時はバックアップの一覧、失敗時はエラー
+pub fn list_backups(storage: &dyn Storage) -> Result<Vec<BackupInfo>, WorkspaceError> {
+    Ok(read_entries(&backup_dir(storage))?
+        .into_iter()
+        .map(|entry| BackupInfo {
+            row_count: storage_in(storage, &entry.path)
+                .ok()
+                .and_then(|backup| backup.read_rows().ok())
+                .map(|rows| rows.len()),
+            id: entry.id,
+            created_at: entry.created_at.to_rfc3339(),
+            size: entry.size,
+        })
+        .collect())
+}
+
+/// バックアップの内容を現在のワークスペースとして復元する
+/// 復元前の内容もバックアップするため、復元は取り消せる
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `id` - 復元するバックアップのID
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、失敗時はエラー
+pub fn restore_backup(storage: &dyn Storage, id: &str) -> Result<(), WorkspaceError> {
+    if parse_id(id).is_none() {
+        return Err(WorkspaceError::invalid_input(
+            "error.backup_id_invalid",
+            tr!("error.backup_id_invalid", id = id),
+        ));
+    }
+    let source_dir = backup_dir(storage).join(id);
+    if !source_dir.is_dir() {
+        return Err(WorkspaceError::new(
+            ErrorCode::NotFound,
+            "error.backup_not_found",
+            tr!("error.backup_not_found", id = id),
+        )
+        .with_path(&source_dir));
+    }
+
+    // 壊れたバックアップで上書きしないよう、先に読み込めることを確認する
+    let backup = storage_in(storage, &source_dir)?;
+    backup.read_schema()?;
+    backup.read_rows()?;
+
+    // 間引きで復元元が消えないよう、復元が終わってから保持ルールを適用する
+    create_backup(storage)?;
+    for target in storage.watch_paths() {
+        let source = source_dir.join(file_name(&target)?);
+        let tmp_path = target.with_extension("restore.tmp");
+        fs::copy(&source, &tmp_path).map_err(|err| WorkspaceError::io(err, &source))?;
+        fs::rename(&tmp_path, &target).map_err(|err| WorkspaceError::io(err, &target))?;
+    }
+    prune_backups(storage, &read_policy(storage)?)?;
+    Ok(())
+}
+
+/// 保持ルールに従って残すバックアップの位置を決める（entriesは新しい順）
+fn entries_to_keep(
+    entries: &[BackupEntry],
+    policy: &BackupPolicy,
+    now: DateTime<Utc>,
+) -> HashSet<usize> {
+    let mut keep: HashSet<usize> = (0..policy.keep_last.max(1).min(entries.len())).collect();
+
+    // 現在から遡った期間内で、1時間ごと・1日ごとに最新のバックアップを残す
+    for (bucket_format, span) in [
+        (
+            "%Y%m%d%H",
+            i64::try_from(policy.keep_hourly)
+                .ok()
+                .and_then(Duration::try_hours),
+        ),
+        (
+            "%Y%m%d",
+            i64::try_from(policy.keep_daily)
+                .ok()
+                .and_then(Duration::try_days),
+        ),
+    ] {
+        // 期間が大きすぎて計算できない場合はすべてを対象にする
+        let since = span.and_then(|span| now.checked_sub_signed(span));
+        let mut buckets = HashSet::new();
+        for (index, entry) in entries.iter().enumerate() {
+            if since.is_some_and(|since| entry.created_at <= since) {
+                break;
+            }
+            if buckets.insert(entry.created_at.format(bucket_format).to_string()) {
+                keep.insert(index);
+            }
+        }
+    }
+
+    // 合計サイズが上限を超えたら、それより古いものは残さない（最新の1件は除く）
+    let mut total = 0;
+    let mut exceeded = false;
+    for (index, entry) in entries.iter().enumerate() {
+        if !keep.contains(&index) {
+            continue;
+        }
+        total += entry.size;
+        if index > 0 && (exceeded || total > policy.max_total_bytes) {
+            exceeded = true;
+            keep.remove(&index);
+        }
+    }
+
+    keep
+}
+
+/// バックアップディレクトリ内のバックアップを新しい順に読み込む
+fn read_entries(dir: &Path) -> Result<Vec<BackupEntry>, WorkspaceError> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for item in fs::read_dir(dir).map_err(|err| WorkspaceError::io(err, dir))? {
+        let item = item.map_err(|err| WorkspaceError::io(err, dir))?;
+        let path = item.path();
+        let Some(id) = item.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        // 作成途中の一時ディレクトリや保持ルールのファイルは除く
+        let Some(created_at) = parse_id(&id).filter(|_| path.is_dir()) else {
+            continue;
+        };
+        entries.push(BackupEntry {
+            size: dir_size(&path)?,
+            id,
+            created_at,
+            path,
+        });
+    }
+    entries.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+    Ok(entries)
+}
+
+/// バックアップIDから作成日時を取り出す（連番の付いたIDにも対応）
+/// 書式に合わないIDはNone（パスとして使うため、それ以外の文字を含むIDは受け付けない）
+fn parse_id(id: &str) -> Option<DateTime<Utc>> {
+    let stamp = match id.split_once('-') {
+        Some((stamp, counter)) if counter.bytes().all(|byte| byte.is_ascii_digit()) => stamp,
+        Some(_) => return None,
+        None => id,
+    };
+    NaiveDateTime::parse_from_str(stamp, ID_FORMAT)
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+/// バックアップ・スナップショットのディレクトリにコピーしたファイルをストレージとして開く
+pub(super) fn storage_in(
+    storage: &dyn Storage,
+    dir: &Path,
+) -> Result<Box<dyn Storage>, WorkspaceError> {
+    let copied = |path: &Path| file_name(path).map(|name| dir.join(name));
+    Ok(match storage.kind() {
+        StorageKind::Json => Box::new(JsonStorage::with_paths(
+            copied(storage.data_path())?,
+            copied(storage.schema_path())?,
+        )),
+        StorageKind::Sqlite => Box::new(SqliteStorage::open(copied(storage.data_path())?)),
+    })
+}
+
+/// パスのファイル名を取り出す（コピー先での名前に使う）
+fn file_name(path: &Path) -> Result<&OsStr, WorkspaceError> {
+    path.file_name().ok_or_else(|| {
+        WorkspaceError::invalid_input(
+            "error.file_name_unavailable",
+            tr!("error.file_name_unavailable"),
+        )
+        .with_path(path)
+    })
+}
+
+pub(super) fn dir_size(dir: &Path) -> Result<u64, WorkspaceError> {
+    let mut size = 0;
+    for item in fs::read_dir(dir).map_err(|err| WorkspaceError::io(err, dir))? {
+        let metadata = item
+            .and_then(|item| item.metadata())
+            .map_err(|err| WorkspaceError::io(err, dir))?;
+        size += metadata.len();
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    /// `now` から `step` ごとに遡って作成されたバックアップ（新しい順）
+    fn entries(now: DateTime<Utc>, step: Duration, count: usize, size: u64) -> Vec<BackupEntry> {
+        (0..count)
+            .map(|index| {
+                let created_at = now - step * index as i32;
+                let id = created_at.format(ID_FORMAT).to_string();
+                BackupEntry {
+                    path: PathBuf::from(&id),
+                    id,
+                    created_at,
+                    size,
+                }
+            })
+            .collect()
+    }
+
+    fn sorted(keep: HashSet<usize>) -> Vec<usize> {
+        let mut keep: Vec<usize> = keep.into_iter().collect();
+        keep.sort();
+        keep
+    }
+
+    fn policy(keep_last: usize, keep_hourly: usize, keep_daily: usize) -> BackupPolicy {
+        BackupPolicy {
+            keep_last,
+            keep_hourly,
+            keep_daily,
+            max_total_bytes: u64::MAX,
+            min_interval_minutes: 0,
+        }
+    }
+
+    #[test]
+    fn hourly_and_daily_are_relative_to_now() {
+        let now = at("2024-05-10T12:00:00Z");
+        let entries = entries(now, Duration::minutes(30), 144, 1);
+        let keep = entries_to_keep(&entries, &policy(2, 3, 2), now);
+        // 直近2件、09:00より後の1時間ごと（12時・11時・10時・9時台）、
+        // 2日前の12:00より後の1日ごと（10日・9日・8日）の最新
+        assert_eq!(sorted(keep), vec![0, 1, 3, 5, 25, 73]);
+    }
+
+    #[test]
+    fn old_backups_are_not_kept_by_buckets() {
+        let now = at("2024-05-10T12:00:00Z");
+        let old = entries(at("2024-04-01T00:00:00Z"), Duration::days(1), 3, 1);
+        let keep = entries_to_keep(&old, &policy(1, 24, 14), now);
+        assert_eq!(sorted(keep), vec![0]);
+    }
+
+    #[test]
+    fn total_size_limit_removes_oldest() {
+        let now = at("2024-05-10T12:00:00Z");
+        let entries = entries(now, Duration::minutes(1), 5, 10);
+        let mut limited = policy(5, 0, 0);
+        limited.max_total_bytes = 25;
+        assert_eq!(sorted(entries_to_keep(&entries, &limited, now)), vec![0, 1]);
+        // 上限より大きくても最新の1件は残す
+        limited.max_total_bytes = 1;
+        assert_eq!(sorted(entries_to_keep(&entries, &limited, now)), vec![0]);
+    }
+
+    fn json_storage(dir: &Path) -> JsonStorage {
+        let storage = JsonStorage::with_paths(dir.join("data.json"), dir.join("data.schema.json"));
+        fs::write(storage.data_path(), "[]").unwrap();
+        fs::write(storage.schema_path(), "{}").unwrap();
+        storage
+    }
+
+    #[test]
+    fn default_policy_keeps_the_content_before_every_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = json_storage(dir.path());
+
+        // 続けて保存しても、直前の保存内容が残る
+        rotate_backups(&storage).unwrap();
+        fs::write(storage.data_path(), "[{}]").unwrap();
+        rotate_backups(&storage).unwrap();
+        fs::write(storage.data_path(), "broken").unwrap();
+
+        let backups = list_backups(&storage).unwrap();
+        assert_eq!(backups.len(), 2);
+        let latest = backup_dir(&storage).join(&backups[0].id).join("data.json");
+        assert_eq!(fs::read_to_string(latest).unwrap(), "[{}]");
+    }
+
+    #[test]
+    fn rotate_respects_minimum_interval() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = json_storage(dir.path());
+        let mut throttled = policy(10, 0, 0);
+        throttled.min_interval_minutes = 5;
+        write_policy(&storage, &throttled).unwrap();
+
+        rotate_backups(&storage).unwrap();
+        fs::write(storage.data_path(), "[{}]").unwrap();
+        rotate_backups(&storage).unwrap();
+        assert_eq!(list_backups(&storage).unwrap().len(), 1);
+
+        write_policy(&storage, &policy(10, 0, 0)).unwrap();
+        rotate_backups(&storage).unwrap();
+        assert_eq!(list_backups(&storage).unwrap().len(), 2);
+        // 内容が変わっていなければ作らない
+        rotate_backups(&storage).unwrap();
+        assert_eq!(list_backups(&storage).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn backup_ids_are_validated() {
+        assert!(parse_id("20240510T120000.000Z").is_some());
+        assert!(parse_id("20240510T120000.000Z-2").is_some());
+        assert!(parse_id("../20240510T120000.000Z").is_none());
+        assert!(parse_id("20240510T120000.000Z-x").is_none());
+    }
+}
@@ -0,0 +1,167 @@
+// フォルダ内のワークスペース（テーブル）の一覧を作成する処理
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::{open_storage, workspace_files, StorageKind, SQLITE_EXTENSIONS};
+use crate::WorkspaceError;
+
+/// フォルダの一覧に表示するテーブルの情報
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CatalogEntry {
+    /// データファイルのファイル名
+    pub file_name: String,
+    pub data_path: String,
+    pub schema_path: String,
+    /// 保存形式（json / sqlite）
+    pub storage: StorageKind,
+    /// テーブル名（スキーマを読み込めない場合はファイル名）
+    pub table_name: String,
+    /// スキーマのメタデータの行数
+    pub row_count: usize,
+    /// スキーマのメタデータの最終更新日時
+    pub updated_at: Option<String>,
+    /// スキーマを読み込めなかった場合のエラーメッセージ
+    pub error: Option<String>,
+}
+
+/// フォルダ内のワークスペースをファイル名順に一覧する
+/// JSONはスキーマファイルがあるデータファイルのみを対象とし、隠しファイル・ディレクトリ（.backups等）は除く
+///
+/// # 引数
+/// * `folder` - 一覧するフォルダ
+///
+/// # 戻り値
+/// 成功時はテーブルの一覧、フォルダを読み込めない場合はエラー
+pub fn list_tables(folder: &Path) -> Result<Vec<CatalogEntry>, WorkspaceError> {
+    let mut tables = Vec::new();
+    for path in workspace_files(folder)? {
+        let Ok(storage) = open_storage(&path) else {
+            continue;
+        };
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut entry = CatalogEntry {
+            table_name: file_name.clone(),
+            file_name,
+            data_path: storage.data_path().to_string_lossy().into_owned(),
+            schema_path: storage.schema_path().to_string_lossy().into_owned(),
+            storage: storage.kind(),
+            row_count: 0,
+            updated_at: None,
+            error: None,
+        };
+        match storage.read_schema() {
+            Ok(schema) => {
+                entry.table_name = schema.table_name;
+                entry.row_count = schema.metadata.row_count;
+                entry.updated_at = schema.metadata.updated_at;
+            }
+            Err(err) => entry.error = Some(err.to_string()),
+        }
+        tables.push(entry);
+    }
+    Ok(tables)
+}
+
+/// ファイルの変更がフォルダの一覧に影響し得るか（データファイル・スキーマファイルか）
+///
+/// # 引数
+/// * `path` - 変更されたファイルのパス
+pub fn affects_catalog(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    if name.starts_with('.') {
+        return false;
+    }
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .is_some_and(|ext| ext == "json" || SQLITE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::TableSchema;
+    use serde_json::json;
+    use std::fs;
+
+    /// 1行だけのワークスペースを作成する
+    fn workspace(path: &Path, table_name: &str) {
+        let storage = open_storage(path).unwrap();
+        let mut schema = TableSchema::new(table_name, "2024-01-01T00:00:00Z");
+        schema.metadata.row_count = 1;
+        schema.metadata.updated_at = Some("2024-01-02T00:00:00Z".to_string());
+        storage
+            .write_table(&[json!({"_id": "a"})], &schema)
+            .unwrap();
+    }
+
+    #[test]
+    fn lists_json_and_sqlite_workspaces_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path();
+        workspace(&folder.join("tasks.json"), "Tasks");
+        workspace(&folder.join("archive.db"), "Archive");
+        // スキーマファイルのないJSON、ワークスペースではないファイルやデータベース、隠しファイル、サブフォルダは除く
+        fs::write(folder.join("notes.json"), "[]").unwrap();
+        fs::write(folder.join("readme.txt"), "text").unwrap();
+        rusqlite::Connection::open(folder.join("other.db"))
+            .unwrap()
+            .execute_batch("CREATE TABLE items (id TEXT)")
+            .unwrap();
+        fs::write(folder.join("empty.sqlite"), "").unwrap();
+        workspace(&folder.join(".hidden.json"), "Hidden");
+        fs::create_dir(folder.join("sub")).unwrap();
+        workspace(&folder.join("sub").join("nested.json"), "Nested");
+        // スキーマを読み込めないワークスペースはファイル名とエラーを表示する
+        fs::write(folder.join("broken.json"), "[]").unwrap();
+        fs::write(folder.join("broken.schema.json"), "{").unwrap();
+
+        let tables = list_tables(folder).unwrap();
+        let summary: Vec<(&str, StorageKind, &str, usize)> = tables
+            .iter()
+            .map(|table| {
+                (
+                    table.file_name.as_str(),
+                    table.storage,
+                    table.table_name.as_str(),
+                    table.row_count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("archive.db", StorageKind::Sqlite, "Archive", 1),
+                ("broken.json", StorageKind::Json, "broken.json", 0),
+                ("tasks.json", StorageKind::Json, "Tasks", 1),
+            ]
+        );
+        assert!(tables[1].error.is_some());
+        assert_eq!(
+            tables[2].updated_at.as_deref(),
+            Some("2024-01-02T00:00:00Z")
+        );
+        assert!(tables[2].schema_path.ends_with("tasks.schema.json"));
+    }
+
+    #[test]
+    fn only_data_and_schema_files_affect_the_catalog() {
+        for name in [
+            "tasks.json",
+            "tasks.schema.json",
+            "archive.db",
+            "archive.SQLITE",
+        ] {
+            assert!(affects_catalog(Path::new(name)), "{name}");
+        }
+        for name in [".tasks.json", "readme.txt", "archive.db-journal", "folder"] {
+            assert!(!affects_catalog(Path::new(name)), "{name}");
+        }
+    }
+}
@@ -0,0 +1,213 @@
+// JSONファイル（data.json + data.schema.json）によるストレージ実装
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde_json::Value;
+
+use super::{Storage, StorageKind};
+use crate::schema::{parse_schema, TableSchema};
+use crate::{tr, ErrorCode, WorkspaceError};
+
+/// データファイルとスキーマファイルのペアで1つのワークスペースを保存するストレージ
+pub struct JsonStorage {
+    /// データファイル(.json)のパス
+    data_path: PathBuf,
+    /// スキーマファイル(.schema.json)のパス
+    schema_path: PathBuf,
+}
+
+impl JsonStorage {
+    /// データファイルのパスからストレージを作成する
+    ///
+    /// # 引数
+    /// * `data_path` - データファイルのパス
+    ///
+    /// # 戻り値
+    /// 成功時はストレージ、スキーマファイルのパスを決められない場合はエラーメッセージ
+    pub fn open(data_path: PathBuf) -> Result<Self, WorkspaceError> {
+        let schema_path = schema_path_for(&data_path)?;
+        Ok(Self {
+            data_path,
+            schema_path,
+        })
+    }
+
+    /// データファイルとスキーマファイルのパスを指定してストレージを作成する
+    pub fn with_paths(data_path: PathBuf, schema_path: PathBuf) -> Self {
+        Self {
+            data_path,
+            schema_path,
+        }
+    }
+}
+
+impl Storage for JsonStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Json
+    }
+
+    fn data_path(&self) -> &Path {
+        &self.data_path
+    }
+
+    fn schema_path(&self) -> &Path {
+        &self.schema_path
+    }
+
+    fn exists(&self) -> bool {
+        self.data_path.exists() || self.schema_path.exists()
+    }
+
+    fn ensure(&self) -> Result<(), WorkspaceError> {
+        ensure_data_files(&self.data_path, &self.schema_path)
+    }
+
+    fn read_rows(&self) -> Result<Vec<Value>, WorkspaceError> {
+        read_data_file(&self.data_path)
+    }
+
+    fn read_schema(&self) -> Result<TableSchema, WorkspaceError> {
+        read_schema_file(&self.schema_path)
+    }
+
+    fn write_table(&self, rows: &[Value], schema: &TableSchema) -> Result<(), WorkspaceError> {
+        write_atomically(
+            &self.data_path,
+            serde_json::to_string_pretty(rows)
+                .map_err(|err| WorkspaceError::json(err, &self.data_path))?,
+        )?;
+        write_atomically(
+            &self.schema_path,
+            serde_json::to_string_pretty(schema)
+                .map_err(|err| WorkspaceError::json(err, &self.schema_path))?,
+        )
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        vec![self.data_path.clone(), self.schema_path.clone()]
+    }
+}
+
+/// 一時ファイル経由でファイルを安全に書き込む
+///
+/// # 引数
+/// * `path` - 書き込み先のファイルパス
+/// * `contents` - 書き込む内容
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、失敗時はエラー
+pub fn write_atomically(path: &Path, contents: String) -> Result<(), WorkspaceError> {
+    // 親ディレクトリが存在しない場合は作成
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| WorkspaceError::io(err, parent))?;
+    }
+
+    // 一時ファイルに書き込んでからリネーム（アトミック操作）
+    let tmp_path = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp_path).map_err(|err| WorkspaceError::io(err, &tmp_path))?;
+    file.write_all(contents.as_bytes())
+        .map_err(|err| WorkspaceError::io(err, &tmp_path))?;
+    file.flush()
+        .map_err(|err| WorkspaceError::io(err, &tmp_path))?;
+
+    fs::rename(&tmp_path, path).map_err(|err| WorkspaceError::io(err, path))
+}
+
+/// データファイルとスキーマファイルが存在することを保証する
+/// 存在しない場合は空のデータファイルとデフォルトスキーマを作成
+///
+/// # 引数
+/// * `data_path` - データファイルのパス
+/// * `schema_path` - スキーマファイルのパス
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、失敗時はエラー
+pub fn ensure_data_files(data_path: &Path, schema_path: &Path) -> Result<(), WorkspaceError> {
+    // 親ディレクトリが存在しない場合は作成
+    if let Some(parent) = data_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| WorkspaceError::io(err, parent))?;
+    }
+
+    // データファイルが存在しない場合は空の配列を作成
+    if !data_path.exists() {
+        fs::write(data_path, "[]").map_err(|err| WorkspaceError::io(err, data_path))?;
+    }
+
+    // スキーマファイルが存在しない場合はデフォルトスキーマを作成
+    if !schema_path.exists() {
+        let now = Utc::now().to_rfc3339();
+        let table_name = data_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("Untitled");
+        let default_schema = TableSchema::new(table_name, &now);
+        fs::write(
+            schema_path,
+            serde_json::to_string_pretty(&default_schema)
+                .map_err(|err| WorkspaceError::json(err, schema_path))?,
+        )
+        .map_err(|err| WorkspaceError::io(err, schema_path))?;
+    }
+
+    Ok(())
+}
+
+/// データファイルを読み込む
+///
+/// # 引数
+/// * `path` - データファイルのパス
+///
+/// # 戻り値
+/// 成功時はJSON配列、失敗時はエラー
+pub fn read_data_file(path: &Path) -> Result<Vec<Value>, WorkspaceError> {
+    let contents = fs::read_to_string(path).map_err(|err| WorkspaceError::io(err, path))?;
+    let value: Value =
+        serde_json::from_str(&contents).map_err(|err| WorkspaceError::json(err, path))?;
+    match value {
+        Value::Array(array) => Ok(array),
+        _ => Err(WorkspaceError::new(
+            ErrorCode::InvalidJson,
+            "error.data_not_array",
+            tr!("error.data_not_array", path = path.display()),
+        )
+        .with_path(path)),
+    }
+}
+
+/// スキーマファイルを読み込む
+///
+/// # 引数
+/// * `path` - スキーマファイルのパス
+///
+/// # 戻り値
+/// 成功時は検証済みのスキーマ、失敗時はエラー
+pub fn read_schema_file(path: &Path) -> Result<TableSchema, WorkspaceError> {
+    let contents = fs::read_to_string(path).map_err(|err| WorkspaceError::io(err, path))?;
+    parse_schema(&contents).map_err(|err| err.with_path(path))
+}
+
+/// データファイルパスからスキーマファイルパスを生成する
+/// 例: data.json → data.schema.json
+///
+/// # 引数
+/// * `data_path` - データファイルのパス
+///
+/// # 戻り値
+/// 成功時はスキーマファイルのパス、失敗時はエラー
+pub fn schema_path_for(data_path: &Path) -> Result<PathBuf, WorkspaceError> {
+    let invalid = |message_key: &'static str| {
+        WorkspaceError::invalid_input(message_key, tr!(message_key)).with_path(data_path)
+    };
+    let stem = data_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| invalid("error.data_file_name_unavailable"))?;
+
+    let parent = data_path
+        .parent()
+        .ok_or_else(|| invalid("error.parent_unavailable"))?;
+
+    Ok(parent.join(format!("{stem}.schema.json")))
+}
@@ -0,0 +1,241 @@
+// ワークスペースの保存先（JSONファイル / SQLite）を抽象化するストレージ層
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::rows::row_id;
+use crate::schema::TableSchema;
+use crate::WorkspaceError;
+
+mod backup;
+mod catalog;
+mod json;
+mod snapshot;
+mod sqlite;
+
+pub use backup::{
+    create_backup, list_backups, prune_backups, read_policy, restore_backup, rotate_backups,
+    write_policy, BackupInfo, BackupPolicy,
+};
+pub use catalog::{affects_catalog, list_tables, CatalogEntry};
+pub(crate) use json::write_atomically;
+pub use json::{ensure_data_files, schema_path_for, JsonStorage};
+pub use snapshot::{create_snapshot, delete_snapshot, list_snapshots, read_snapshot, SnapshotInfo};
+pub use sqlite::{is_workspace_file, SqliteStorage};
+
+/// SQLiteストレージとして扱う拡張子
+const SQLITE_EXTENSIONS: [&str; 3] = ["db", "sqlite", "sqlite3"];
+
+/// ストレージの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    Json,
+    Sqlite,
+}
+
+/// ワークスペースの行データとスキーマを読み書きするストレージ
+pub trait Storage: Send + Sync {
+    /// ストレージの種類
+    fn kind(&self) -> StorageKind;
+
+    /// 行データの保存先パス
+    fn data_path(&self) -> &Path;
+
+    /// スキーマの保存先パス（SQLiteの場合はデータと同じファイル）
+    fn schema_path(&self) -> &Path;
+
+    /// 保存先が既に存在するか
+    fn exists(&self) -> bool;
+
+    /// 保存先が存在しない場合は空のデータとデフォルトスキーマを作成する
+    fn ensure(&self) -> Result<(), WorkspaceError>;
+
+    /// 行データを読み込む
+    fn read_rows(&self) -> Result<Vec<Value>, WorkspaceError>;
+
+    /// スキーマを読み込み、検証する
+    fn read_schema(&self) -> Result<TableSchema, WorkspaceError>;
+
+    /// 行データとスキーマを保存する
+    fn write_table(&self, rows: &[Value], schema: &TableSchema) -> Result<(), WorkspaceError>;
+
+    /// 前回の保存内容からの変更を保存する（既定では全体を書き込む）
+    ///
+    /// # 引数
+    /// * `rows` - 保存後のすべての行データ
+    /// * `schema` - 保存するスキーマ
+    /// * `changes` - 前回の保存内容から追加・変更・削除された行
+    fn write_changes(
+        &self,
+        rows: &[Value],
+        schema: &TableSchema,
+        changes: &RowChanges,
+    ) -> Result<(), WorkspaceError> {
+        let _ = changes;
+        self.write_table(rows, schema)
+    }
+
+    /// 外部変更を検出するために監視するファイル
+    fn watch_paths(&self) -> Vec<PathBuf>;
+
+    /// 保存されている内容のリビジョン（既定では監視対象ファイルの内容のハッシュ）
+    /// 読み込み時と保存時のリビジョンを比べることで、他のプロセスによる変更を検出する
+    fn revision(&self) -> Result<String, WorkspaceError> {
+        content_hash(&self.watch_paths())
+    }
+}
+
+/// ファイルの内容のハッシュ（16進数）
+pub(super) fn content_hash(paths: &[PathBuf]) -> Result<String, WorkspaceError> {
+    let mut hasher = Sha256::new();
+    for path in paths {
+        let contents = fs::read(path).map_err(|err| WorkspaceError::io(err, path))?;
+        // ファイルの境界が曖昧にならないよう長さを含める
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// 前回の保存内容からの行の変更（_idで比較する）
+#[derive(Debug, Default)]
+pub struct RowChanges {
+    /// 追加された行と、内容または位置が変わった行の_id
+    pub changed: HashSet<String>,
+    /// 削除された行の_id
+    pub removed: Vec<String>,
+}
+
+impl RowChanges {
+    /// 保存前後の行データを比べて変更を求める
+    ///
+    /// # 引数
+    /// * `previous` - 保存されている行データ
+    /// * `rows` - これから保存する行データ
+    pub fn between(previous: &[Value], rows: &[Value]) -> Self {
+        let before: HashMap<&str, (usize, &Value)> = previous
+            .iter()
+            .enumerate()
+            .filter_map(|(position, row)| row_id(row).map(|id| (id, (position, row))))
+            .collect();
+        let changed = rows
+            .iter()
+            .enumerate()
+            .filter_map(|(position, row)| {
+                let id = row_id(row)?;
+                let unchanged = before.get(id) == Some(&(position, row));
+                (!unchanged).then(|| id.to_string())
+            })
+            .collect();
+        let after: HashSet<&str> = rows.iter().filter_map(row_id).collect();
+        let removed = before
+            .keys()
+            .filter(|id| !after.contains(*id))
+            .map(|id| id.to_string())
+            .collect();
+        Self { changed, removed }
+    }
+}
+
+/// 拡張子からストレージの種類を判定する
+///
+/// # 引数
+/// * `path` - データファイルのパス
+pub fn kind_for_path(path: &Path) -> StorageKind {
+    let is_sqlite = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| SQLITE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false);
+    if is_sqlite {
+        StorageKind::Sqlite
+    } else {
+        StorageKind::Json
+    }
+}
+
+/// フォルダ内のワークスペースのデータファイルを名前順に列挙する
+/// スキーマファイルがあるJSONファイルとworkspace_metaテーブルがあるSQLiteファイルを対象とし、.で始まるファイルやサブフォルダは含めない
+///
+/// # 引数
+/// * `folder` - 探すフォルダ
+///
+/// # 戻り値
+/// 成功時はデータファイルのパス、失敗時はエラー
+pub fn workspace_files(folder: &Path) -> Result<Vec<PathBuf>, WorkspaceError> {
+    let mut files = Vec::new();
+    for item in fs::read_dir(folder).map_err(|err| WorkspaceError::io(err, folder))? {
+        let path = item.map_err(|err| WorkspaceError::io(err, folder))?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if name.starts_with('.') || !path.is_file() {
+            continue;
+        }
+        let is_workspace = match kind_for_path(&path) {
+            StorageKind::Sqlite => is_workspace_file(&path),
+            StorageKind::Json => {
+                path.extension().and_then(|ext| ext.to_str()) == Some("json")
+                    && !name.ends_with(".schema.json")
+                    && schema_path_for(&path).is_ok_and(|schema| schema.is_file())
+            }
+        };
+        if is_workspace {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// データファイルのパスに対応するストレージを開く
+///
+/// # 引数
+/// * `data_path` - データファイル（.json / .db / .sqlite）のパス
+///
+/// # 戻り値
+/// 成功時はストレージ、失敗時はエラーメッセージ
+pub fn open_storage(data_path: &Path) -> Result<Arc<dyn Storage>, WorkspaceError> {
+    open_storage_as(kind_for_path(data_path), data_path)
+}
+
+/// 種類を指定してストレージを開く
+///
+/// # 引数
+/// * `kind` - ストレージの種類
+/// * `data_path` - データファイルのパス
+///
+/// # 戻り値
+/// 成功時はストレージ、失敗時はエラーメッセージ
+pub fn open_storage_as(
+    kind: StorageKind,
+    data_path: &Path,
+) -> Result<Arc<dyn Storage>, WorkspaceError> {
+    Ok(match kind {
+        StorageKind::Json => Arc::new(JsonStorage::open(data_path.to_path_buf())?),
+        StorageKind::Sqlite => Arc::new(SqliteStorage::open(data_path.to_path_buf())),
+    })
+}
+
+/// 別の形式に変換する際の既定の出力先を決める
+/// 例: data.json → data.db、data.db → data.json
+///
+/// # 引数
+/// * `source` - 変換元のデータファイルのパス
+/// * `target` - 変換先のストレージの種類
+pub fn default_conversion_path(source: &Path, target: StorageKind) -> PathBuf {
+    match target {
+        StorageKind::Json => source.with_extension("json"),
+        StorageKind::Sqlite => source.with_extension("db"),
+    }
+}
@@ -0,0 +1,304 @@
+// 名前を付けたスナップショット（ある時点のワークスペースのコピー）を保存・読み込みする処理
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::backup::{copy_files, dir_size, storage_in};
+use super::Storage;
+use crate::schema::TableSchema;
+use crate::{tr, ErrorCode, WorkspaceError};
+
+/// スナップショットを保存するディレクトリ名（ワークスペースと同じフォルダに作成する）
+const SNAPSHOT_DIR_NAME: &str = ".snapshots";
+
+/// スナップショットの情報を保存するファイル名（各スナップショットのディレクトリ内）
+const META_FILE_NAME: &str = ".snapshot-meta.json";
+
+/// スナップショット名の最大文字数
+const MAX_NAME_LEN: usize = 100;
+
+/// スナップショットの一覧に表示する情報
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotInfo {
+    pub name: String,
+    /// 作成日時
+    pub created_at: String,
+    /// 合計サイズ（バイト）
+    pub size: u64,
+    /// 行数（読み込めない場合はNone）
+    pub row_count: Option<usize>,
+}
+
+/// スナップショットのディレクトリに保存する情報
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotMeta {
+    name: String,
+    created_at: String,
+}
+
+/// ワークスペースのスナップショットを保存するディレクトリ
+/// 例: /path/data.json → /path/.snapshots/data.json/
+fn snapshot_root(storage: &dyn Storage) -> PathBuf {
+    let data_path = storage.data_path();
+    let parent = data_path.parent().unwrap_or_else(|| Path::new("."));
+    parent
+        .join(SNAPSHOT_DIR_NAME)
+        .join(data_path.file_name().unwrap_or_default())
+}
+
+/// 現在保存されている内容を名前付きのスナップショットとして保存する
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `name` - スナップショット名（同じ名前のスナップショットがある場合はエラー）
+///
+/// # 戻り値
+/// 成功時は作成したスナップショットの情報、失敗時はエラー
+pub fn create_snapshot(storage: &dyn Storage, name: &str) -> Result<SnapshotInfo, WorkspaceError> {
+    let name = validate_name(name)?;
+    let target = snapshot_root(storage).join(name);
+    if target.exists() {
+        return Err(WorkspaceError::new(
+            ErrorCode::AlreadyExists,
+            "snapshot.exists",
+            tr!("snapshot.exists", name = name),
+        )
+        .with_path(&target));
+    }
+
+    let sources = storage.watch_paths();
+    if let Some(missing) = sources.iter().find(|path| !path.exists()) {
+        return Err(WorkspaceError::new(
+            ErrorCode::NotFound,
+            "error.file_not_found",
+            tr!("error.file_not_found", path = missing.display()),
+        )
+        .with_path(missing));
+    }
+    let root = snapshot_root(storage);
+    fs::create_dir_all(&root).map_err(|err| WorkspaceError::io(err, &root))?;
+    copy_files(&sources, &target)?;
+
+    let meta = SnapshotMeta {
+        name: name.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+    };
+    let meta_path = target.join(META_FILE_NAME);
+    fs::write(
+        &meta_path,
+        serde_json::to_string_pretty(&meta).map_err(|err| WorkspaceError::json(err, &meta_path))?,
+    )
+    .map_err(|err| WorkspaceError::io(err, &meta_path))?;
+
+    snapshot_info(storage, &target)
+}
+
+/// スナップショットを作成日時の新しい順に一覧する
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+///
+/// # 戻り値
+/// 成功時はスナップショットの一覧、失敗時はエラー
+pub fn list_snapshots(storage: &dyn Storage) -> Result<Vec<SnapshotInfo>, WorkspaceError> {
+    let root = snapshot_root(storage);
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut snapshots = Vec::new();
+    for item in fs::read_dir(&root).map_err(|err| WorkspaceError::io(err, &root))? {
+        let path = item.map_err(|err| WorkspaceError::io(err, &root))?.path();
+        // 作成途中の一時ディレクトリは情報ファイルがないため除く
+        if path.join(META_FILE_NAME).is_file() {
+            snapshots.push(snapshot_info(storage, &path)?);
+        }
+    }
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(snapshots)
+}
+
+/// スナップショットの行データとスキーマを読み込む
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `name` - スナップショット名
+///
+/// # 戻り値
+/// 成功時は行データとスキーマ、失敗時はエラー
+pub fn read_snapshot(
+    storage: &dyn Storage,
+    name: &str,
+) -> Result<(Vec<Value>, TableSchema), WorkspaceError> {
+    let snapshot = storage_in(storage, &snapshot_dir(storage, name)?)?;
+    Ok((snapshot.read_rows()?, snapshot.read_schema()?))
+}
+
+/// スナップショットを削除する
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `name` - スナップショット名
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、失敗時はエラー
+pub fn delete_snapshot(storage: &dyn Storage, name: &str) -> Result<(), WorkspaceError> {
+    let dir = snapshot_dir(storage, name)?;
+    fs::remove_dir_all(&dir).map_err(|err| WorkspaceError::io(err, &dir))
+}
+
+/// 既存のスナップショットのディレクトリを求める
+fn snapshot_dir(storage: &dyn Storage, name: &str) -> Result<PathBuf, WorkspaceError> {
+    let name = validate_name(name)?;
+    let dir = snapshot_root(storage).join(name);
+    if dir.join(META_FILE_NAME).is_file() {
+        Ok(dir)
+    } else {
+        Err(WorkspaceError::new(
+            ErrorCode::NotFound,
+            "snapshot.not_found",
+            tr!("snapshot.not_found", name = name),
+        )
+        .with_path(&dir))
+    }
+}
+
+/// スナップショットのディレクトリから一覧用の情報を作る
+fn snapshot_info(storage: &dyn Storage, dir: &Path) -> Result<SnapshotInfo, WorkspaceError> {
+    let meta_path = dir.join(META_FILE_NAME);
+    let contents =
+        fs::read_to_string(&meta_path).map_err(|err| WorkspaceError::io(err, &meta_path))?;
+    let meta: SnapshotMeta = serde_json::from_str(&contents).map_err(|err| {
+        WorkspaceError::new(
+            ErrorCode::InvalidJson,
+            "snapshot.meta_unreadable",
+            tr!("snapshot.meta_unreadable", error = err),
+        )
+        .with_path(&meta_path)
+        .with_source(&err)
+    })?;
+    Ok(SnapshotInfo {
+        row_count: storage_in(storage, dir)
+            .ok()
+            .and_then(|snapshot| snapshot.read_rows().ok())
+            .map(|rows| rows.len()),
+        size: dir_size(dir)?,
+        name: meta.name,
+        created_at: meta.created_at,
+    })
+}
+
+/// スナップショット名を検証する（ディレクトリ名として使うため、パスになる文字は受け付けない）
+fn validate_name(name: &str) -> Result<&str, WorkspaceError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(WorkspaceError::invalid_input(
+            "snapshot.name_required",
+            tr!("snapshot.name_required"),
+        ));
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(WorkspaceError::invalid_input(
+            "snapshot.name_too_long",
+            tr!("snapshot.name_too_long", max = MAX_NAME_LEN),
+        ));
+    }
+    let invalid = name.starts_with('.')
+        || name.ends_with(".tmp")
+        || name.chars().any(|ch| {
+            ch.is_control() || matches!(ch, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+        });
+    if invalid {
+        return Err(WorkspaceError::invalid_input(
+            "snapshot.name_invalid",
+            tr!("snapshot.name_invalid", name = name),
+        ));
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::open_storage;
+    use serde_json::json;
+
+    /// 1行だけのワークスペースを作成する
+    fn workspace(dir: &Path, file_name: &str) -> std::sync::Arc<dyn Storage> {
+        let storage = open_storage(&dir.join(file_name)).unwrap();
+        storage.ensure().unwrap();
+        let schema = storage.read_schema().unwrap();
+        storage
+            .write_table(&[json!({"_id": "a", "name": "A"})], &schema)
+            .unwrap();
+        storage
+    }
+
+    #[test]
+    fn snapshots_keep_the_content_at_creation() {
+        let dir = tempfile::tempdir().unwrap();
+        for file_name in ["data.json", "data.db"] {
+            let storage = workspace(dir.path(), file_name);
+            let info = create_snapshot(storage.as_ref(), " before edit ").unwrap();
+            assert_eq!(info.name, "before edit");
+            assert_eq!(info.row_count, Some(1));
+            assert!(info.size > 0);
+
+            // スナップショットの作成後に変更しても、スナップショットの内容は変わらない
+            let schema = storage.read_schema().unwrap();
+            storage
+                .write_table(
+                    &[
+                        json!({"_id": "a", "name": "A2"}),
+                        json!({"_id": "b", "name": "B"}),
+                    ],
+                    &schema,
+                )
+                .unwrap();
+            let (rows, snapshot_schema) = read_snapshot(storage.as_ref(), "before edit").unwrap();
+            assert_eq!(rows, vec![json!({"_id": "a", "name": "A"})], "{file_name}");
+            assert_eq!(snapshot_schema.table_name, schema.table_name);
+        }
+    }
+
+    #[test]
+    fn snapshots_are_listed_and_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = workspace(dir.path(), "data.json");
+        assert!(list_snapshots(storage.as_ref()).unwrap().is_empty());
+
+        create_snapshot(storage.as_ref(), "first").unwrap();
+        create_snapshot(storage.as_ref(), "second").unwrap();
+        let error = create_snapshot(storage.as_ref(), "first").unwrap_err();
+        assert_eq!(error.code, ErrorCode::AlreadyExists);
+
+        // 新しい順
+        let names: Vec<String> = list_snapshots(storage.as_ref())
+            .unwrap()
+            .into_iter()
+            .map(|info| info.name)
+            .collect();
+        assert_eq!(names, vec!["second", "first"]);
+        // 同じフォルダの別のワークスペースには表示しない
+        let other = workspace(dir.path(), "other.json");
+        assert!(list_snapshots(other.as_ref()).unwrap().is_empty());
+
+        delete_snapshot(storage.as_ref(), "first").unwrap();
+        let error = read_snapshot(storage.as_ref(), "first").unwrap_err();
+        assert_eq!(error.message_key, "snapshot.not_found");
+        assert_eq!(list_snapshots(storage.as_ref()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn names_that_are_paths_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = workspace(dir.path(), "data.json");
+        for name in ["", "../escape", ".hidden", "a/b", "tmp.tmp"] {
+            let error = create_snapshot(storage.as_ref(), name).unwrap_err();
+            assert_eq!(error.code, ErrorCode::InvalidInput, "{name}");
+        }
+        assert!(!dir.path().join(".snapshots").join("data.json").exists());
+    }
+}
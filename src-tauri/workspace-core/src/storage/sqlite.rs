@@ -0,0 +1,474 @@
+// SQLiteデータベース（.db / .sqlite）によるストレージ実装
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde_json::Value;
+
+use super::{content_hash, RowChanges, Storage, StorageKind};
+use crate::rows::row_id;
+use crate::schema::{parse_schema, TableSchema};
+use crate::{tr, ErrorCode, WorkspaceError};
+
+/// ロック中のデータベースを待つ時間
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// スキーマを保存するメタデータのキー
+const SCHEMA_KEY: &str = "schema";
+
+/// リビジョンを保存するメタデータのキー（CREATE_TABLES のトリガーと同じ値）
+const REVISION_KEY: &str = "revision";
+
+/// テーブルの作成SQL
+/// 行は _id をキーに JSON のまま保存し、スキーマはメタデータテーブルに保存する
+/// 行やスキーマが変わるたびに、トリガーでリビジョンを新しい乱数に置き換える
+/// （他のツールで直接書き換えられた場合も検出でき、ファイル全体を読まずにリビジョンを求められる。
+/// 連番ではなく乱数にするのは、バックアップから戻したファイルの値と重ならないようにするため）
+const CREATE_TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS workspace_meta (
+        key   TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS rows (
+        id       TEXT PRIMARY KEY,
+        position INTEGER NOT NULL,
+        data     TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS rows_position ON rows (position);
+    CREATE TRIGGER IF NOT EXISTS rows_inserted AFTER INSERT ON rows BEGIN
+        UPDATE workspace_meta SET value = lower(hex(randomblob(16))) WHERE key = 'revision';
+    END;
+    CREATE TRIGGER IF NOT EXISTS rows_updated AFTER UPDATE ON rows BEGIN
+        UPDATE workspace_meta SET value = lower(hex(randomblob(16))) WHERE key = 'revision';
+    END;
+    CREATE TRIGGER IF NOT EXISTS rows_deleted AFTER DELETE ON rows BEGIN
+        UPDATE workspace_meta SET value = lower(hex(randomblob(16))) WHERE key = 'revision';
+    END;
+    CREATE TRIGGER IF NOT EXISTS meta_inserted AFTER INSERT ON workspace_meta
+    WHEN NEW.key != 'revision' BEGIN
+        UPDATE workspace_meta SET value = lower(hex(randomblob(16))) WHERE key = 'revision';
+    END;
+    CREATE TRIGGER IF NOT EXISTS meta_updated AFTER UPDATE ON workspace_meta
+    WHEN NEW.key != 'revision' BEGIN
+        UPDATE workspace_meta SET value = lower(hex(randomblob(16))) WHERE key = 'revision';
+    END;
+    INSERT OR IGNORE INTO workspace_meta (key, value)
+    VALUES ('revision', lower(hex(randomblob(16))));
+";
+
+/// SQLiteファイルがワークスペースか（workspace_metaテーブルがあるか）を判定する
+/// 他のアプリケーションのデータベースを一覧に含めないよう、読み取り専用で開いて確認する
+///
+/// # 引数
+/// * `path` - データベースファイルのパス
+pub fn is_workspace_file(path: &Path) -> bool {
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY
+        | OpenFlags::SQLITE_OPEN_URI
+        | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    let Ok(connection) = Connection::open_with_flags(path, flags) else {
+        return false;
+    };
+    connection
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'workspace_meta'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()
+        .is_ok_and(|found| found.is_some())
+}
+
+/// 1つのSQLiteファイルに1つのワークスペース（rowsテーブル + workspace_meta）を保存するストレージ
+pub struct SqliteStorage {
+    /// データベースファイルのパス
+    path: PathBuf,
+}
+
+impl SqliteStorage {
+    /// データベースファイルのパスからストレージを作成する
+    pub fn open(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// データベースに接続する
+    /// 書き込み時以外はファイルを作成せず、ファイルがない場合は`NotFound`のエラーを返す
+    ///
+    /// # 引数
+    /// * `create` - ファイルがない場合に作成するか
+    fn connect(&self, create: bool) -> Result<Connection, WorkspaceError> {
+        let mut flags = OpenFlags::SQLITE_OPEN_READ_WRITE
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        if create {
+            flags |= OpenFlags::SQLITE_OPEN_CREATE;
+        } else if !self.path.exists() {
+            return Err(WorkspaceError::io(
+                io::ErrorKind::NotFound.into(),
+                &self.path,
+            ));
+        }
+        let connection = Connection::open_with_flags(&self.path, flags)
+            .map_err(|err| self.database_error(err))?;
+        connection
+            .busy_timeout(BUSY_TIMEOUT)
+            .map_err(|err| self.database_error(err))?;
+        Ok(connection)
+    }
+
+    /// 行データとスキーマを1つのトランザクションで書き込む
+    ///
+    /// # 引数
+    /// * `rows` - 保存後のすべての行データ
+    /// * `schema` - 保存するスキーマ
+    /// * `changes` - 前回の保存内容からの変更（Noneの場合は全行を突き合わせる）
+    fn write(
+        &self,
+        rows: &[Value],
+        schema: &TableSchema,
+        changes: Option<&RowChanges>,
+    ) -> Result<(), WorkspaceError> {
+        let mut connection = self.connect(true)?;
+        let transaction = connection
+            .transaction()
+            .map_err(|err| self.database_error(err))?;
+        // 古いファイルにもリビジョンのトリガーを作成する
+        transaction
+            .execute_batch(CREATE_TABLES)
+            .map_err(|err| self.database_error(err))?;
+
+        let mut keep = HashSet::with_capacity(rows.len());
+        {
+            // 変更のない行は書き換えない
+            let mut upsert = transaction
+                .prepare(
+                    "INSERT INTO rows (id, position, data) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(id) DO UPDATE SET position = excluded.position, data = excluded.data
+                     WHERE rows.position != excluded.position OR rows.data != excluded.data",
+                )
+                .map_err(|err| self.database_error(err))?;
+            for (position, row) in rows.iter().enumerate() {
+                let id = row_id(row).ok_or_else(|| {
+                    WorkspaceError::new(
+                        ErrorCode::InvalidRows,
+                        "error.row_id_missing",
+                        tr!("error.row_id_missing", row = position + 1),
+                    )
+                })?;
+                keep.insert(id);
+                if changes.is_some_and(|changes| !changes.changed.contains(id)) {
+                    continue;
+                }
+                let data = serde_json::to_string(row)
+                    .map_err(|err| WorkspaceError::json(err, &self.path))?;
+                upsert
+                    .execute(params![id, position as i64, data])
+                    .map_err(|err| self.database_error(err))?;
+            }
+
+            // 保存対象に含まれない行を削除
+            let removed: Vec<String> = match changes {
+                Some(changes) => changes.removed.clone(),
+                None => transaction
+                    .prepare("SELECT id FROM rows")
+                    .and_then(|mut statement| {
+                        statement
+                            .query_map([], |row| row.get(0))?
+                            .collect::<Result<Vec<String>, _>>()
+                    })
+                    .map_err(|err| self.database_error(err))?,
+            };
+            let mut delete = transaction
+                .prepare("DELETE FROM rows WHERE id = ?1")
+                .map_err(|err| self.database_error(err))?;
+            for id in removed.iter().filter(|id| !keep.contains(id.as_str())) {
+                delete
+                    .execute(params![id])
+                    .map_err(|err| self.database_error(err))?;
+            }
+        }
+
+        transaction
+            .execute(
+                "INSERT INTO workspace_meta (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value
+                 WHERE workspace_meta.value != excluded.value",
+                params![
+                    SCHEMA_KEY,
+                    serde_json::to_string(schema)
+                        .map_err(|err| WorkspaceError::json(err, &self.path))?
+                ],
+            )
+            .map_err(|err| self.database_error(err))?;
+
+        transaction.commit().map_err(|err| self.database_error(err))
+    }
+
+    /// SQLiteのエラーにデータベースファイルのパスを付ける
+    fn database_error(&self, error: rusqlite::Error) -> WorkspaceError {
+        WorkspaceError::database(error, &self.path)
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Sqlite
+    }
+
+    fn data_path(&self) -> &Path {
+        &self.path
+    }
+
+    fn schema_path(&self) -> &Path {
+        &self.path
+    }
+
+    fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    fn ensure(&self) -> Result<(), WorkspaceError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|err| WorkspaceError::io(err, parent))?;
+        }
+
+        let connection = self.connect(true)?;
+        connection
+            .execute_batch(CREATE_TABLES)
+            .map_err(|err| self.database_error(err))?;
+
+        // スキーマが保存されていない場合はデフォルトスキーマを作成
+        let existing: Option<String> = connection
+            .query_row(
+                "SELECT value FROM workspace_meta WHERE key = ?1",
+                params![SCHEMA_KEY],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|err| self.database_error(err))?;
+        if existing.is_none() {
+            let now = Utc::now().to_rfc3339();
+            let table_name = self
+                .path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("Untitled");
+            let default_schema = TableSchema::new(table_name, &now);
+            connection
+                .execute(
+                    "INSERT INTO workspace_meta (key, value) VALUES (?1, ?2)",
+                    params![
+                        SCHEMA_KEY,
+                        serde_json::to_string(&default_schema)
+                            .map_err(|err| WorkspaceError::json(err, &self.path))?
+                    ],
+                )
+                .map_err(|err| self.database_error(err))?;
+        }
+
+        Ok(())
+    }
+
+    fn read_rows(&self) -> Result<Vec<Value>, WorkspaceError> {
+        let connection = self.connect(false)?;
+        let mut statement = connection
+            .prepare("SELECT id, data FROM rows ORDER BY position, id")
+            .map_err(|err| self.database_error(err))?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|err| self.database_error(err))?;
+
+        rows.map(|row| {
+            let (id, data) = row.map_err(|err| self.database_error(err))?;
+            serde_json::from_str(&data).map_err(|err| {
+                WorkspaceError::new(
+                    ErrorCode::InvalidJson,
+                    "error.row_corrupted",
+                    tr!("error.row_corrupted", id = id),
+                )
+                .with_path(&self.path)
+                .with_row(id)
+                .with_source(&err)
+            })
+        })
+        .collect()
+    }
+
+    fn read_schema(&self) -> Result<TableSchema, WorkspaceError> {
+        let connection = self.connect(false)?;
+        let contents: String = connection
+            .query_row(
+                "SELECT value FROM workspace_meta WHERE key = ?1",
+                params![SCHEMA_KEY],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|err| self.database_error(err))?
+            .ok_or_else(|| {
+                WorkspaceError::new(
+                    ErrorCode::NotFound,
+                    "error.schema_missing",
+                    tr!("error.schema_missing"),
+                )
+                .with_path(&self.path)
+            })?;
+        parse_schema(&contents).map_err(|err| err.with_path(&self.path))
+    }
+
+    fn write_table(&self, rows: &[Value], schema: &TableSchema) -> Result<(), WorkspaceError> {
+        self.write(rows, schema, None)
+    }
+
+    fn write_changes(
+        &self,
+        rows: &[Value],
+        schema: &TableSchema,
+        changes: &RowChanges,
+    ) -> Result<(), WorkspaceError> {
+        self.write(rows, schema, Some(changes))
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        vec![self.path.clone()]
+    }
+
+    /// トリガーで更新しているリビジョンを返す
+    /// トリガーを作成する前の古いファイルはファイルの内容のハッシュを返す（次の書き込みでトリガーを作成する）
+    fn revision(&self) -> Result<String, WorkspaceError> {
+        if !self.path.exists() {
+            return content_hash(&self.watch_paths());
+        }
+        let revision: Option<String> = self
+            .connect(false)?
+            .query_row(
+                "SELECT value FROM workspace_meta WHERE key = ?1",
+                params![REVISION_KEY],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|err| self.database_error(err))?;
+        match revision {
+            Some(revision) => Ok(revision),
+            None => content_hash(&self.watch_paths()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn row(id: &str, name: &str) -> Value {
+        json!({"_id": id, "name": name})
+    }
+
+    fn open(dir: &Path) -> SqliteStorage {
+        let storage = SqliteStorage::open(dir.join("data.db"));
+        storage.ensure().unwrap();
+        storage
+    }
+
+    #[test]
+    fn write_changes_touches_only_changed_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = open(dir.path());
+        let schema = storage.read_schema().unwrap();
+        let before = vec![row("row_a", "A"), row("row_b", "B"), row("row_c", "C")];
+        storage.write_table(&before, &schema).unwrap();
+
+        // 変更対象に含めない行は、内容が違っても書き込まれない
+        let after = vec![row("row_a", "A2"), row("row_b", "B2")];
+        let changes = RowChanges {
+            changed: HashSet::from(["row_a".to_string()]),
+            removed: vec!["row_c".to_string()],
+        };
+        storage.write_changes(&after, &schema, &changes).unwrap();
+        assert_eq!(
+            storage.read_rows().unwrap(),
+            vec![row("row_a", "A2"), row("row_b", "B")]
+        );
+
+        // 全体の書き込みでは保存対象にない行を削除する
+        storage.write_table(&[row("row_b", "B")], &schema).unwrap();
+        assert_eq!(storage.read_rows().unwrap(), vec![row("row_b", "B")]);
+    }
+
+    #[test]
+    fn row_changes_detect_moves_and_removals() {
+        let before = vec![row("row_a", "A"), row("row_b", "B"), row("row_c", "C")];
+        let after = vec![row("row_b", "B"), row("row_a", "A"), row("row_d", "D")];
+        let changes = RowChanges::between(&before, &after);
+        let mut changed: Vec<&str> = changes.changed.iter().map(String::as_str).collect();
+        changed.sort();
+        assert_eq!(changed, vec!["row_a", "row_b", "row_d"]);
+        assert_eq!(changes.removed, vec!["row_c".to_string()]);
+        assert!(RowChanges::between(&before, &before).changed.is_empty());
+    }
+
+    #[test]
+    fn revision_changes_on_every_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = open(dir.path());
+        let schema = storage.read_schema().unwrap();
+        let initial = storage.revision().unwrap();
+        assert_eq!(storage.revision().unwrap(), initial);
+
+        storage.write_table(&[row("row_a", "A")], &schema).unwrap();
+        let written = storage.revision().unwrap();
+        assert_ne!(written, initial);
+
+        // 変更のない書き込みではリビジョンは変わらない
+        storage.write_table(&[row("row_a", "A")], &schema).unwrap();
+        assert_eq!(storage.revision().unwrap(), written);
+
+        // 他のツールによる直接の変更も検出する
+        storage
+            .connect(false)
+            .unwrap()
+            .execute("UPDATE rows SET data = '{}' WHERE id = 'row_a'", [])
+            .unwrap();
+        assert_ne!(storage.revision().unwrap(), written);
+    }
+
+    #[test]
+    fn missing_database_has_no_revision() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SqliteStorage::open(dir.path().join("missing.db"));
+        assert!(storage.revision().is_err());
+        assert!(!storage.exists());
+    }
+
+    #[test]
+    fn reads_do_not_create_missing_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SqliteStorage::open(dir.path().join("missing.db"));
+        assert_eq!(storage.read_rows().unwrap_err().code, ErrorCode::NotFound);
+        assert_eq!(storage.read_schema().unwrap_err().code, ErrorCode::NotFound);
+        assert!(!storage.exists());
+    }
+
+    #[test]
+    fn only_databases_with_workspace_meta_are_workspaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = open(dir.path());
+        assert!(is_workspace_file(storage.data_path()));
+
+        let other = dir.path().join("other.db");
+        Connection::open(&other)
+            .unwrap()
+            .execute_batch("CREATE TABLE items (id TEXT)")
+            .unwrap();
+        assert!(!is_workspace_file(&other));
+        let empty = dir.path().join("empty.sqlite");
+        fs::write(&empty, "").unwrap();
+        assert!(!is_workspace_file(&empty));
+        assert!(!is_workspace_file(&dir.path().join("missing.db")));
+        assert!(!dir.path().join("missing.db").exists());
+    }
+}
@@ -0,0 +1,261 @@
+// 外部の表形式データ（CSV、Excel等）の取り込み・書き出しで共通に使う処理
+use std::collections::HashSet;
+
+use serde_json::{Map, Number, Value};
+
+use crate::schema::{ColumnDefinition, ColumnType, TableSchema};
+
+/// 取り込み済みのテーブル（ワークスペース作成前の中間表現）
+#[derive(Debug)]
+pub struct ImportedTable {
+    /// 追加するカラム定義（システム列は含まない）
+    pub columns: Vec<ColumnDefinition>,
+    /// 行データ（システム列は normalise_rows で付与する）
+    pub rows: Vec<Value>,
+}
+
+/// 見出し名からカラムIDを生成する
+/// フロントエンドの toColumnId と同じ規則（英小文字・数字・アンダースコア）で、重複時は連番を付ける
+///
+/// # 引数
+/// * `name` - 見出し名
+/// * `index` - 列番号（IDが作れない場合の代替に使用）
+/// * `existing` - 既に使われているID
+pub fn column_id_for(name: &str, index: usize, existing: &mut HashSet<String>) -> String {
+    let mut base = String::new();
+    for ch in name.trim().to_lowercase().chars() {
+        if ch.is_ascii_lowercase() || ch.is_ascii_digit() {
+            base.push(ch);
+        } else if !base.ends_with('_') {
+            base.push('_');
+        }
+    }
+    let base = base.trim_matches('_').to_string();
+    let base = if base.is_empty() {
+        format!("col_{}", index + 1)
+    } else {
+        base
+    };
+
+    let mut candidate = base.clone();
+    let mut counter = 1;
+    while existing.contains(&candidate) {
+        candidate = format!("{base}_{counter}");
+        counter += 1;
+    }
+    existing.insert(candidate.clone());
+    candidate
+}
+
+/// 文字列セルの内容からカラム型を推定する
+/// 空でないセルがすべて数値なら number、すべて真偽値なら checkbox、それ以外は text
+/// 先頭に0が付く値や i64 に収まらない整数を含む列は text になる
+///
+/// # 引数
+/// * `cells` - 列のセル値
+pub fn infer_column_type<'a>(cells: impl Iterator<Item = &'a str>) -> ColumnType {
+    let mut all_numbers = true;
+    let mut all_booleans = true;
+    let mut any_value = false;
+
+    for cell in cells {
+        let cell = cell.trim();
+        if cell.is_empty() {
+            continue;
+        }
+        any_value = true;
+        all_numbers &= parse_number(cell).is_some();
+        all_booleans &= parse_bool(cell).is_some();
+        if !all_numbers && !all_booleans {
+            break;
+        }
+    }
+
+    if !any_value {
+        ColumnType::Text
+    } else if all_numbers {
+        ColumnType::Number
+    } else if all_booleans {
+        ColumnType::Checkbox
+    } else {
+        ColumnType::Text
+    }
+}
+
+/// 文字列セルをカラム型に合わせたJSON値に変換する
+///
+/// # 引数
+/// * `cell` - セル値
+/// * `column_type` - 推定したカラム型
+pub fn convert_cell(cell: &str, column_type: ColumnType) -> Value {
+    let trimmed = cell.trim();
+    match column_type {
+        ColumnType::Number => parse_number(trimmed)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        ColumnType::Checkbox => Value::Bool(parse_bool(trimmed).unwrap_or(false)),
+        _ => Value::String(cell.to_string()),
+    }
+}
+
+/// 見出しと文字列セルの表から ImportedTable を作成する
+///
+/// # 引数
+/// * `headers` - 見出し（None の場合は "Column N" を使用）
+/// * `records` - 各行のセル値
+pub fn build_from_text(headers: Option<Vec<String>>, records: Vec<Vec<String>>) -> ImportedTable {
+    let width = records
+        .iter()
+        .map(Vec::len)
+        .chain(headers.iter().map(Vec::len))
+        .max()
+        .unwrap_or(0);
+
+    let mut existing = HashSet::from(["_id".to_string()]);
+    let columns: Vec<ColumnDefinition> = (0..width)
+        .map(|index| {
+            let name = headers
+                .as_ref()
+                .and_then(|headers| headers.get(index))
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("Column {}", index + 1));
+            let column_type = infer_column_type(
+                records
+                    .iter()
+                    .map(|record| record.get(index).map(String::as_str).unwrap_or("")),
+            );
+            let id = column_id_for(&name, index, &mut existing);
+            ColumnDefinition::new(id, name, column_type)
+        })
+        .collect();
+
+    let rows = records
+        .iter()
+        .map(|record| {
+            let object: Map<String, Value> = columns
+                .iter()
+                .enumerate()
+                .map(|(index, column)| {
+                    let cell = record.get(index).map(String::as_str).unwrap_or("");
+                    (column.id.clone(), convert_cell(cell, column.column_type))
+                })
+                .collect();
+            Value::Object(object)
+        })
+        .collect();
+
+    ImportedTable { columns, rows }
+}
+
+/// 文字列を数値として解釈する（整数を優先し、有限の小数のみ許可）
+/// 先頭に0が付く値（郵便番号・コード等）と i64 に収まらない整数は、
+/// 数値にすると内容が変わるため解釈しない
+pub fn parse_number(text: &str) -> Option<Number> {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    if digits.len() > 1 && digits.starts_with('0') && !digits[1..].starts_with('.') {
+        return None;
+    }
+    if let Ok(integer) = text.parse::<i64>() {
+        return Some(Number::from(integer));
+    }
+    if !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    text.parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .and_then(Number::from_f64)
+}
+
+/// 文字列を真偽値として解釈する
+pub fn parse_bool(text: &str) -> Option<bool> {
+    match text.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "on" | "✓" => Some(true),
+        "false" | "no" | "n" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// 書き出し対象のカラムをスキーマの並び順で選ぶ
+///
+/// # 引数
+/// * `schema` - スキーマ
+/// * `include_hidden` - 非表示カラムを含めるか
+/// * `include_system` - システム列を含めるか
+pub fn export_columns(
+    schema: &TableSchema,
+    include_hidden: bool,
+    include_system: bool,
+) -> Vec<&ColumnDefinition> {
+    schema
+        .columns
+        .iter()
+        .filter(|column| include_system || !column.system)
+        .filter(|column| include_hidden || column.system || !column.hidden)
+        .collect()
+}
+
+/// セルの値を書き出し用の文字列に変換する
+///
+/// # 引数
+/// * `value` - セル値
+/// * `list_separator` - 配列（multiselect等）を連結する区切り文字列
+pub fn cell_to_text(value: &Value, list_separator: &str) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| cell_to_text(item, list_separator))
+            .collect::<Vec<_>>()
+            .join(list_separator),
+        Value::Object(_) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_number_keeps_plain_numbers() {
+        assert_eq!(parse_number("0"), Some(Number::from(0)));
+        assert_eq!(parse_number("-42"), Some(Number::from(-42)));
+        assert_eq!(parse_number("0.5").and_then(|n| n.as_f64()), Some(0.5));
+        assert_eq!(parse_number("-0.25").and_then(|n| n.as_f64()), Some(-0.25));
+        assert_eq!(parse_number("1e3").and_then(|n| n.as_f64()), Some(1000.0));
+    }
+
+    #[test]
+    fn parse_number_rejects_leading_zero_and_overflow() {
+        assert_eq!(parse_number("007"), None);
+        assert_eq!(parse_number("-01"), None);
+        assert_eq!(parse_number("00.5"), None);
+        assert_eq!(parse_number("99999999999999999999"), None);
+        assert_eq!(parse_number("NaN"), None);
+    }
+
+    #[test]
+    fn infer_column_type_treats_codes_as_text() {
+        assert_eq!(
+            infer_column_type(["1", "2.5", ""].into_iter()),
+            ColumnType::Number
+        );
+        assert_eq!(
+            infer_column_type(["100", "0123"].into_iter()),
+            ColumnType::Text
+        );
+        assert_eq!(
+            infer_column_type(["12345678901234567890"].into_iter()),
+            ColumnType::Text
+        );
+        assert_eq!(
+            infer_column_type(["yes", "No"].into_iter()),
+            ColumnType::Checkbox
+        );
+        assert_eq!(infer_column_type(["", " "].into_iter()), ColumnType::Text);
+    }
+}
@@ -0,0 +1,386 @@
+// 行データをスキーマのカラム定義に照らして検証する処理
+use std::collections::HashSet;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::schema::{ColumnDefinition, ColumnType, TableSchema};
+use crate::tr;
+
+/// 検証で見つかった問題の重大度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    /// 保存を拒否する問題（型の不一致など）
+    Error,
+    /// 保存は行うが利用者に知らせる問題（必須列が空など）
+    Warning,
+}
+
+/// 検証で見つかった問題の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// 行がJSONオブジェクトではない
+    NotAnObject,
+    /// _idが存在しない、または文字列ではない
+    InvalidId,
+    /// _idが他の行と重複している
+    DuplicateId,
+    /// 値がカラムの型と一致しない
+    TypeMismatch,
+    /// 必須カラムが空
+    RequiredMissing,
+    /// リレーションの参照先の行が見つからない
+    BrokenRelation,
+}
+
+/// 1つのセル（または行）に関する検証結果
+#[derive(Debug, Clone, Serialize)]
+pub struct RowIssue {
+    /// 行のインデックス（配列上の位置）
+    pub row_index: usize,
+    /// 行の_id（取得できた場合）
+    pub row_id: Option<String>,
+    /// 問題のあるカラムID（行全体の問題の場合はNone）
+    pub column_id: Option<String>,
+    pub kind: IssueKind,
+    pub severity: IssueSeverity,
+    /// 利用者向けの説明
+    pub message: String,
+}
+
+/// 行データをスキーマに照らして検証する
+///
+/// # 引数
+/// * `rows` - 検証する行データ
+/// * `schema` - カラム定義を含むスキーマ
+///
+/// # 戻り値
+/// 見つかった問題の一覧（問題がなければ空）
+pub fn validate_rows(rows: &[Value], schema: &TableSchema) -> Vec<RowIssue> {
+    let mut issues = Vec::new();
+    let mut seen_ids = HashSet::new();
+    let columns: Vec<&ColumnDefinition> = schema
+        .columns
+        .iter()
+        // 計算列の値は保存されないため検証しない
+        .filter(|column| !column.system && !column.column_type.is_computed())
+        .collect();
+
+    for (row_index, row) in rows.iter().enumerate() {
+        let Value::Object(object) = row else {
+            issues.push(RowIssue {
+                row_index,
+                row_id: None,
+                column_id: None,
+                kind: IssueKind::NotAnObject,
+                severity: IssueSeverity::Error,
+                message: tr!("validation.not_object"),
+            });
+            continue;
+        };
+
+        let row_id = match object.get("_id") {
+            Some(Value::String(id)) if !id.is_empty() => Some(id.clone()),
+            // _idがない行は保存時に採番されるので問題にしない
+            None => None,
+            Some(_) => {
+                issues.push(RowIssue {
+                    row_index,
+                    row_id: None,
+                    column_id: Some("_id".to_string()),
+                    kind: IssueKind::InvalidId,
+                    severity: IssueSeverity::Error,
+                    message: tr!("validation.invalid_id"),
+                });
+                None
+            }
+        };
+
+        if let Some(id) = &row_id {
+            if !seen_ids.insert(id.clone()) {
+                issues.push(RowIssue {
+                    row_index,
+                    row_id: row_id.clone(),
+                    column_id: Some("_id".to_string()),
+                    kind: IssueKind::DuplicateId,
+                    severity: IssueSeverity::Error,
+                    message: tr!("validation.duplicate_id", id = id),
+                });
+            }
+        }
+
+        for column in &columns {
+            let value = object.get(&column.id).unwrap_or(&Value::Null);
+
+            if is_empty_value(value) {
+                if column.required && column.column_type != ColumnType::Checkbox {
+                    issues.push(RowIssue {
+                        row_index,
+                        row_id: row_id.clone(),
+                        column_id: Some(column.id.clone()),
+                        kind: IssueKind::RequiredMissing,
+                        severity: IssueSeverity::Warning,
+                        message: tr!("validation.required_missing", column = column.name),
+                    });
+                }
+                continue;
+            }
+
+            if !value_matches_type(value, column.column_type) {
+                issues.push(RowIssue {
+                    row_index,
+                    row_id: row_id.clone(),
+                    column_id: Some(column.id.clone()),
+                    kind: IssueKind::TypeMismatch,
+                    severity: IssueSeverity::Error,
+                    message: tr!(
+                        "validation.type_mismatch",
+                        column = column.name,
+                        type = column.column_type,
+                        value = value
+                    ),
+                });
+            }
+        }
+    }
+
+    issues
+}
+
+/// 保存を拒否すべき問題が含まれているかを判定する
+pub fn has_errors(issues: &[RowIssue]) -> bool {
+    issues
+        .iter()
+        .any(|issue| issue.severity == IssueSeverity::Error)
+}
+
+/// エラー扱いの問題をまとめたメッセージを作成する
+///
+/// # 引数
+/// * `issues` - 検証結果
+/// * `limit` - メッセージに含める最大件数
+pub fn summarise_errors(issues: &[RowIssue], limit: usize) -> String {
+    let errors: Vec<&RowIssue> = issues
+        .iter()
+        .filter(|issue| issue.severity == IssueSeverity::Error)
+        .collect();
+    let mut lines: Vec<String> = errors
+        .iter()
+        .take(limit)
+        .map(|issue| {
+            let row = issue
+                .row_id
+                .clone()
+                .unwrap_or_else(|| format!("#{}", issue.row_index + 1));
+            tr!("validation.row", row = row, message = issue.message)
+        })
+        .collect();
+    if errors.len() > limit {
+        lines.push(tr!("validation.more", count = errors.len() - limit));
+    }
+    tr!("validation.invalid_rows", details = lines.join("; "))
+}
+
+/// 空の値（未入力）かどうかを判定する
+/// null、空文字列、空配列を未入力として扱う
+pub fn is_empty_value(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.trim().is_empty(),
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+/// 値がカラム型と一致するかを判定する
+fn value_matches_type(value: &Value, column_type: ColumnType) -> bool {
+    match column_type {
+        ColumnType::Text => value.is_string(),
+        ColumnType::Number => value.is_number(),
+        ColumnType::Checkbox => value.is_boolean(),
+        ColumnType::Multiselect => value
+            .as_array()
+            .is_some_and(|items| items.iter().all(Value::is_string)),
+        // リレーションは対象行の_id（単体または配列）
+        ColumnType::Relation => {
+            value.is_string()
+                || value
+                    .as_array()
+                    .is_some_and(|items| items.iter().all(Value::is_string))
+        }
+        ColumnType::Formula | ColumnType::Lookup | ColumnType::Rollup => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 各型の列を1つずつ持つスキーマ（name と done は必須）
+    fn schema() -> TableSchema {
+        let mut schema = TableSchema::new("test", "2024-01-01T00:00:00Z");
+        let mut name = ColumnDefinition::new("name", "Name", ColumnType::Text);
+        name.required = true;
+        schema.columns.push(name);
+        schema
+            .columns
+            .push(ColumnDefinition::new("price", "Price", ColumnType::Number));
+        let mut done = ColumnDefinition::new("done", "Done", ColumnType::Checkbox);
+        done.required = true;
+        schema.columns.push(done);
+        schema.columns.push(ColumnDefinition::new(
+            "tags",
+            "Tags",
+            ColumnType::Multiselect,
+        ));
+        schema.columns.push(ColumnDefinition::new(
+            "parent",
+            "Parent",
+            ColumnType::Relation,
+        ));
+        schema
+    }
+
+    /// 問題の行・カラム・種類・重大度
+    fn summary(issues: &[RowIssue]) -> Vec<(usize, Option<&str>, IssueKind, IssueSeverity)> {
+        issues
+            .iter()
+            .map(|issue| {
+                (
+                    issue.row_index,
+                    issue.column_id.as_deref(),
+                    issue.kind,
+                    issue.severity,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn valid_rows_have_no_issues() {
+        let rows = vec![json!({
+            "_id": "a",
+            "name": "Apple",
+            "price": 120,
+            "done": false,
+            "tags": ["fruit"],
+            "parent": ["b"],
+        })];
+        assert!(validate_rows(&rows, &schema()).is_empty());
+    }
+
+    #[test]
+    fn type_mismatches_are_errors() {
+        let rows = vec![json!({
+            "_id": "a",
+            "name": 1,
+            "price": "120",
+            "done": "yes",
+            "tags": ["fruit", 1],
+            "parent": 5,
+        })];
+        let issues = validate_rows(&rows, &schema());
+        let mismatch = |column| {
+            (
+                0,
+                Some(column),
+                IssueKind::TypeMismatch,
+                IssueSeverity::Error,
+            )
+        };
+        assert_eq!(
+            summary(&issues),
+            vec![
+                mismatch("name"),
+                mismatch("price"),
+                mismatch("done"),
+                mismatch("tags"),
+                mismatch("parent"),
+            ]
+        );
+        assert!(has_errors(&issues));
+        assert_eq!(issues[0].row_id.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn empty_required_cells_are_warnings() {
+        let rows = vec![
+            json!({ "_id": "a" }),
+            json!({ "_id": "b", "name": "  ", "tags": [] }),
+            json!({ "_id": "c", "name": "Cherry" }),
+        ];
+        let issues = validate_rows(&rows, &schema());
+        // チェックボックスは未入力でも false として扱うため必須の対象外
+        assert_eq!(
+            summary(&issues),
+            vec![
+                (
+                    0,
+                    Some("name"),
+                    IssueKind::RequiredMissing,
+                    IssueSeverity::Warning
+                ),
+                (
+                    1,
+                    Some("name"),
+                    IssueKind::RequiredMissing,
+                    IssueSeverity::Warning
+                ),
+            ]
+        );
+        assert!(!has_errors(&issues));
+    }
+
+    #[test]
+    fn row_ids_are_checked() {
+        let rows = vec![
+            json!({ "_id": "a", "name": "A" }),
+            json!({ "_id": "a", "name": "B" }),
+            json!({ "_id": 3, "name": "C" }),
+            json!("not a row"),
+            json!({ "name": "D" }),
+        ];
+        assert_eq!(
+            summary(&validate_rows(&rows, &schema())),
+            vec![
+                (1, Some("_id"), IssueKind::DuplicateId, IssueSeverity::Error),
+                (2, Some("_id"), IssueKind::InvalidId, IssueSeverity::Error),
+                (3, None, IssueKind::NotAnObject, IssueSeverity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_values() {
+        assert!(is_empty_value(&Value::Null));
+        assert!(is_empty_value(&json!("")));
+        assert!(is_empty_value(&json!(" \t")));
+        assert!(is_empty_value(&json!([])));
+        assert!(!is_empty_value(&json!(0)));
+        assert!(!is_empty_value(&json!(false)));
+        assert!(!is_empty_value(&json!([""])));
+        assert!(!is_empty_value(&json!({})));
+    }
+
+    #[test]
+    fn errors_are_summarised_with_a_limit() {
+        let rows = vec![
+            json!({ "_id": "a", "price": "x" }),
+            json!({ "_id": "b", "price": "y" }),
+            json!({ "price": "z" }),
+        ];
+        let issues = validate_rows(&rows, &schema());
+        let errors = issues
+            .iter()
+            .filter(|issue| issue.severity == IssueSeverity::Error)
+            .count();
+        assert_eq!(errors, 3);
+        let full = summarise_errors(&issues, 5);
+        let limited = summarise_errors(&issues, 1);
+        assert!(full.contains("#3"), "{full}");
+        assert!(!limited.contains("#3"), "{limited}");
+    }
+}
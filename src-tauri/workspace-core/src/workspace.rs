@@ -0,0 +1,836 @@
+// ワークスペースを開く・作成する・保存する・検証する処理（Tauriに依存しない公開API）
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::formula::{evaluate_formulas, strip_computed_values};
+use crate::history::{history_path, record_change, HistoryAction};
+use crate::relation::{
+    apply_delete_rules, evaluate_linked_columns, load_related, relation_links, validate_relations,
+    ReferenceUpdate, RelatedTables, RelationLinks,
+};
+use crate::rows::{changed_cells, row_id};
+use crate::schema::TableSchema;
+use crate::storage::{
+    self, ensure_data_files, open_storage, JsonStorage, RowChanges, Storage, StorageKind,
+};
+use crate::tabular::ImportedTable;
+use crate::validation::{has_errors, validate_rows, RowIssue};
+use crate::{tr, ErrorCode, WorkspaceError};
+
+/// ワークスペース情報を表す構造体（フロントエンドやCLIに返す）
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceInfo {
+    /// ワークスペースID（コマンドの呼び出しとイベントの判別に使用）
+    pub id: String,
+    pub data_path: String,
+    pub schema_path: String,
+    pub folder: String,
+    /// 保存形式（json / sqlite）
+    pub storage: StorageKind,
+}
+
+/// テーブルデータとスキーマをまとめたペイロード（フロントエンドやCLIに返す）
+#[derive(Debug, Clone, Serialize)]
+pub struct TablePayload {
+    pub data: Vec<Value>,
+    pub schema: TableSchema,
+    pub workspace: WorkspaceInfo,
+    /// 行データの検証結果
+    pub issues: Vec<RowIssue>,
+    /// リレーション列ごとの、参照先の_idと表示値
+    pub links: RelationLinks,
+    /// 読み込んだ内容のリビジョン（保存時に送り返すと競合を検出できる）
+    pub revision: String,
+}
+
+/// 保存結果
+#[derive(Debug, Serialize)]
+pub struct SaveResult {
+    pub row_count: usize,
+    pub updated_at: String,
+    /// 保存は行われたが残っている問題（必須列が空など）
+    pub issues: Vec<RowIssue>,
+    /// 保存後のリビジョン
+    pub revision: String,
+    /// 削除時の動作で書き換えた、参照している他のワークスペースのデータファイル
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub updated_workspaces: Vec<String>,
+}
+
+/// データファイルのパスからワークスペースIDを求める（同じファイルは同じID）
+pub fn workspace_id(storage: &dyn Storage) -> String {
+    let path = storage.data_path();
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    Sha256::digest(path.to_string_lossy().as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// 既存のワークスペースを開く
+///
+/// # 引数
+/// * `data_path` - データファイル（.json / .db / .sqlite）のパス
+///
+/// # 戻り値
+/// 成功時はワークスペースのストレージ、ファイルが存在しない場合や開けない場合はエラー
+pub fn open_workspace(data_path: &Path) -> Result<Arc<dyn Storage>, WorkspaceError> {
+    let storage = open_storage(data_path)?;
+    if !storage.exists() {
+        return Err(WorkspaceError::new(
+            ErrorCode::NotFound,
+            "error.workspace_missing",
+            tr!("error.workspace_missing"),
+        )
+        .with_path(data_path));
+    }
+    Ok(storage)
+}
+
+/// 空のデータファイルとデフォルトスキーマを作成し、新しいワークスペースとして開く
+///
+/// # 引数
+/// * `path` - 新しいデータファイルのパス（.json は省略可）
+///
+/// # 戻り値
+/// 成功時はワークスペースのストレージ、失敗時はエラー
+pub fn create_workspace(path: &str) -> Result<Arc<dyn Storage>, WorkspaceError> {
+    let (data_path, schema_path) = prepare_new_workspace(path)?;
+    ensure_data_files(&data_path, &schema_path)?;
+    open_storage(&data_path)
+}
+
+/// 保存されているスキーマと行データを検証する
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+///
+/// # 戻り値
+/// 成功時は行データの問題の一覧、読み込めない場合やスキーマが不正な場合はエラー
+pub fn validate_workspace(storage: &dyn Storage) -> Result<Vec<RowIssue>, WorkspaceError> {
+    let rows = storage.read_rows()?;
+    let schema = storage.read_schema()?;
+    schema.validate()?;
+    Ok(validate_table(storage, &rows, &schema))
+}
+
+/// 行データとスキーマを検証してワークスペースに書き込み、変更を履歴に残す
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `data` - 保存する行データ（計算列の値は保存しない）
+/// * `schema` - 保存するスキーマ
+///
+/// # 戻り値
+/// 成功時は保存結果、検証に失敗した場合や書き込みに失敗した場合はエラー
+pub fn save_workspace(
+    storage: &dyn Storage,
+    data: Vec<Value>,
+    schema: TableSchema,
+) -> Result<SaveResult, WorkspaceError> {
+    let previous = read_previous(storage);
+    commit_workspace(
+        storage,
+        previous
+            .as_ref()
+            .map(|(rows, schema)| (rows.as_slice(), schema)),
+        data,
+        schema,
+        (HistoryAction::Edit, None),
+        &Utc::now().to_rfc3339(),
+    )
+}
+
+/// 変更後の行データとスキーマを検証して書き込む（保存・差分操作・元に戻す/やり直すで共通の処理）
+/// 削除時の動作の適用、行データの正規化、履歴の記録、参照している他のワークスペースの更新まで行う
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `previous` - 保存されている行データとスキーマ（読み込めなかった場合はNoneで、全体を書き込み履歴は残さない）
+/// * `data` - 保存する行データ（計算列の値は保存しない）
+/// * `schema` - 保存するスキーマ
+/// * `history` - 履歴に記録する操作と、元に戻す・やり直す対象の履歴の番号
+/// * `now` - 更新日時
+///
+/// # 戻り値
+/// 成功時は保存結果、検証に失敗した場合や書き込みに失敗した場合、
+/// 参照している他のワークスペースが読み込み後に更新されていた場合はエラー
+pub fn commit_workspace(
+    storage: &dyn Storage,
+    previous: Option<(&[Value], &TableSchema)>,
+    mut data: Vec<Value>,
+    mut schema: TableSchema,
+    history: (HistoryAction, Option<u64>),
+    now: &str,
+) -> Result<SaveResult, WorkspaceError> {
+    // 壊れたスキーマでワークスペースを上書きしないよう、書き込み前に検証する
+    schema.validate()?;
+
+    // 計算列の値は保存せず、読み込み時に求める
+    strip_computed_values(&mut data, &schema);
+
+    // 削除した行を参照している行に、リレーション列の削除時の動作を適用する
+    let previous_rows = previous.map(|(rows, _)| rows);
+    let updates = match previous_rows {
+        Some(previous_rows) => apply_delete_rules(storage, previous_rows, &mut data, &schema)?,
+        None => Vec::new(),
+    };
+
+    let (row_count, issues) = prepare_rows(storage, previous_rows, &mut data, &mut schema, now)?;
+    // 参照している他のワークスペースも、どれかを書き込む前にすべて確認する
+    let updates = prepare_reference_updates(updates, now)?;
+
+    write_with_history(storage, previous, (&data, &schema), history, now)?;
+    let mut updated_workspaces = Vec::with_capacity(updates.len());
+    for (update, schema) in updates {
+        write_with_history(
+            update.storage.as_ref(),
+            Some((&update.previous_rows, &update.schema)),
+            (&update.rows, &schema),
+            (HistoryAction::Edit, None),
+            now,
+        )?;
+        updated_workspaces.push(update.storage.data_path().to_string_lossy().into_owned());
+    }
+
+    Ok(SaveResult {
+        row_count,
+        updated_at: now.to_string(),
+        issues,
+        revision: storage.revision()?,
+        updated_workspaces,
+    })
+}
+
+/// 保存前に、クライアントが読み込んだリビジョンが現在の内容と一致するか確認する
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `expected` - クライアントが読み込んだリビジョン（Noneの場合は確認しない）
+///
+/// # 戻り値
+/// 一致する場合は`Ok(())`、一致しない場合は現在の内容を含む競合エラー
+pub fn ensure_revision(
+    storage: &dyn Storage,
+    expected: Option<&str>,
+) -> Result<(), WorkspaceError> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    if storage.revision()? == expected {
+        return Ok(());
+    }
+    let snapshot = build_table_payload(storage)?;
+    Err(WorkspaceError::conflict(snapshot))
+}
+
+/// バックアップを復元し、復元を1つの変更として履歴に残す
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `id` - 復元するバックアップのID
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、失敗時はエラー
+pub fn restore_with_history(storage: &dyn Storage, id: &str) -> Result<(), WorkspaceError> {
+    let previous = read_previous(storage);
+    storage::restore_backup(storage, id)?;
+
+    // 復元も1つの変更として履歴に残し、元に戻せるようにする
+    if let (Some((previous_rows, previous_schema)), Some((rows, schema))) =
+        (&previous, read_previous(storage))
+    {
+        record_change(
+            &history_path(storage.data_path()),
+            (previous_rows, previous_schema),
+            (&rows, &schema),
+            HistoryAction::Edit,
+            None,
+            &Utc::now().to_rfc3339(),
+        )?;
+    }
+    Ok(())
+}
+
+/// 取り込んだテーブルを新しいワークスペースのファイルとして書き込む
+/// 保存と同じ検証と正規化を行い、スキーマや行データに誤りがある場合はファイルを作成しない
+///
+/// # 引数
+/// * `path` - 新しいデータファイルのパス
+/// * `imported` - 取り込んだカラムと行
+///
+/// # 戻り値
+/// 成功時は作成したデータファイルのパスと保存結果、検証に失敗した場合や書き込みに失敗した場合はエラー
+pub fn write_imported_workspace(
+    path: &str,
+    imported: ImportedTable,
+) -> Result<(PathBuf, SaveResult), WorkspaceError> {
+    let (data_path, schema_path) = prepare_new_workspace(path)?;
+    let storage = JsonStorage::with_paths(data_path.clone(), schema_path);
+
+    // デフォルトスキーマに取り込んだカラムを追加する
+    let now = Utc::now().to_rfc3339();
+    let table_name = data_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("Untitled");
+    let mut schema = TableSchema::new(table_name, &now);
+    schema.columns.extend(imported.columns);
+
+    let saved = commit_workspace(
+        &storage,
+        None,
+        imported.rows,
+        schema,
+        (HistoryAction::Edit, None),
+        &now,
+    )?;
+    Ok((data_path, saved))
+}
+
+/// 新しいワークスペースのデータファイルとスキーマファイルのパスを決める
+/// .json拡張子の補完、ファイル名の検証、既存ファイルとの衝突チェックを行う
+///
+/// # 引数
+/// * `path` - 利用者が指定したデータファイルのパス
+///
+/// # 戻り値
+/// 成功時は(データパス, スキーマパス)のタプル、失敗時はエラー
+pub fn prepare_new_workspace(path: &str) -> Result<(PathBuf, PathBuf), WorkspaceError> {
+    let mut data_path = PathBuf::from(path.trim());
+    if data_path.to_string_lossy().trim().is_empty() {
+        return Err(WorkspaceError::invalid_input(
+            "error.path_required",
+            tr!("error.path_required"),
+        ));
+    }
+
+    // .json拡張子がない場合は追加
+    if data_path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+        data_path.set_extension("json");
+    }
+
+    let stem = data_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| {
+            WorkspaceError::invalid_input(
+                "error.file_name_unavailable",
+                tr!("error.file_name_unavailable"),
+            )
+        })?
+        .trim();
+
+    if stem.is_empty() {
+        return Err(WorkspaceError::invalid_input(
+            "error.file_name_required",
+            tr!("error.file_name_required"),
+        ));
+    }
+
+    // スキーマファイルのパスを生成（data.json → data.schema.json）
+    let schema_path = data_path
+        .parent()
+        .ok_or_else(|| {
+            WorkspaceError::invalid_input(
+                "error.folder_unavailable",
+                tr!("error.folder_unavailable"),
+            )
+        })?
+        .join(format!("{stem}.schema.json"));
+
+    // 親ディレクトリが存在しない場合は作成
+    if let Some(parent) = data_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| WorkspaceError::io(err, parent))?;
+    }
+
+    // ファイルが既に存在する場合はエラー
+    if let Some(existing) = [&data_path, &schema_path]
+        .into_iter()
+        .find(|path| path.exists())
+    {
+        return Err(WorkspaceError::new(
+            ErrorCode::AlreadyExists,
+            "error.already_exists",
+            tr!("error.already_exists"),
+        )
+        .with_path(existing));
+    }
+
+    Ok((data_path, schema_path))
+}
+
+/// 行データを正規化する（ID、タイムスタンプ、順序の追加・更新）
+///
+/// # 引数
+/// * `rows` - 正規化する行データの可変参照
+/// * `previous_rows` - 保存されている行データ（値が変わっていない行は_updatedを引き継ぐ）
+/// * `timestamp` - 更新タイムスタンプ
+///
+/// # 戻り値
+/// 行数
+pub fn normalise_rows(
+    rows: &mut [Value],
+    previous_rows: Option<&[Value]>,
+    timestamp: String,
+) -> usize {
+    let previous: HashMap<&str, &Value> = previous_rows
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|row| Some((row_id(row)?, row)))
+        .collect();
+    rows.iter_mut().enumerate().for_each(|(index, row)| {
+        let unchanged = row_id(row)
+            .and_then(|id| previous.get(id))
+            .filter(|before| changed_cells(before, row).is_empty())
+            .and_then(|before| before.get("_updated").cloned());
+
+        if let Value::Object(ref mut obj) = row {
+            // _idが存在しない場合は生成して追加
+            let id_entry = obj.entry("_id".to_string());
+            if matches!(id_entry, serde_json::map::Entry::Vacant(_)) {
+                obj.insert("_id".into(), Value::String(new_row_id()));
+            }
+
+            // _createdが存在しない場合のみ追加（作成日時は不変）
+            if !obj.contains_key("_created") {
+                obj.insert("_created".into(), Value::String(timestamp.clone()));
+            }
+            // _updatedは値が変わった行（と新しい行）だけ最新のタイムスタンプで更新
+            let updated = unchanged.unwrap_or_else(|| Value::String(timestamp.clone()));
+            obj.insert("_updated".into(), updated);
+
+            // _orderが存在しない場合は追加、または無効な値の場合は修正
+            if !obj.contains_key("_order") {
+                obj.insert(
+                    "_order".into(),
+                    Value::Number(serde_json::Number::from(index as u64)),
+                );
+            } else if let Some(order_value) = obj.get_mut("_order") {
+                if let Value::Number(_) = order_value {
+                    // ユーザー定義の数値をそのまま保持
+                } else {
+                    *order_value = Value::Number(serde_json::Number::from(index as u64));
+                }
+            }
+        }
+    });
+
+    rows.len()
+}
+
+/// 新しい行の_idを生成する
+pub fn new_row_id() -> String {
+    format!("row_{}", nanoid::nanoid!(10))
+}
+
+/// 保存前の内容を読み込む（履歴の記録に使用）
+/// 壊れたファイルを上書きする場合など、読み込めない場合はNone
+pub fn read_previous(storage: &dyn Storage) -> Option<(Vec<Value>, TableSchema)> {
+    Some((storage.read_rows().ok()?, storage.read_schema().ok()?))
+}
+
+/// 行データとスキーマを読み込み、リレーション列の参照先を読み込んで計算列の値を求める
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+///
+/// # 戻り値
+/// 成功時は計算列の値を含む行データ、スキーマ、参照先のテーブル、失敗時はエラー
+pub fn read_evaluated(
+    storage: &dyn Storage,
+) -> Result<(Vec<Value>, TableSchema, RelatedTables), WorkspaceError> {
+    let mut rows = storage.read_rows()?;
+    let schema = storage.read_schema()?;
+    let related = load_related(storage, &rows, &schema);
+    evaluate_linked_columns(&mut rows, &schema, &related);
+    evaluate_formulas(&mut rows, &schema, &related)?;
+    Ok((rows, schema, related))
+}
+
+/// 保存前に行データを検証する（型の検証と、リレーション列の参照先の確認）
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `rows` - 保存する行データ
+/// * `schema` - 保存するスキーマ
+///
+/// # 戻り値
+/// 見つかった問題の一覧
+pub fn validate_table(
+    storage: &dyn Storage,
+    rows: &[Value],
+    schema: &TableSchema,
+) -> Vec<RowIssue> {
+    let mut issues = validate_rows(rows, schema);
+    let related = load_related(storage, rows, schema);
+    issues.extend(validate_relations(rows, schema, &related));
+    issues
+}
+
+/// 書き込む行データを正規化（ID、タイムスタンプ、順序の更新）して検証し、スキーマのメタデータを更新する
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `previous_rows` - 保存されている行データ（読み込めなかった場合はNone）
+/// * `data` - 書き込む行データ
+/// * `schema` - 書き込むスキーマ
+/// * `now` - 更新日時
+///
+/// # 戻り値
+/// 成功時は行数と残っている問題、型の合わない値や参照先のない_idを含む場合はエラー
+fn prepare_rows(
+    storage: &dyn Storage,
+    previous_rows: Option<&[Value]>,
+    data: &mut [Value],
+    schema: &mut TableSchema,
+    now: &str,
+) -> Result<(usize, Vec<RowIssue>), WorkspaceError> {
+    let row_count = normalise_rows(data, previous_rows, now.to_string());
+
+    let issues = validate_table(storage, data, schema);
+    if has_errors(&issues) {
+        return Err(WorkspaceError::invalid_rows(&issues));
+    }
+
+    update_schema_metadata(schema, row_count, now);
+    Ok((row_count, issues))
+}
+
+/// リレーション列の削除時の動作で変更された他のワークスペースを、書き込む前に確認する
+/// 読み込み後に他で更新されていた場合は競合とし、どのワークスペースにも書き込まない
+///
+/// # 引数
+/// * `updates` - 変更されたワークスペース
+/// * `now` - 更新日時
+///
+/// # 戻り値
+/// 成功時は変更されたワークスペースと書き込むスキーマ、競合や検証に失敗した場合はエラー
+fn prepare_reference_updates(
+    updates: Vec<ReferenceUpdate>,
+    now: &str,
+) -> Result<Vec<(ReferenceUpdate, TableSchema)>, WorkspaceError> {
+    let mut prepared = Vec::with_capacity(updates.len());
+    for mut update in updates {
+        let storage = update.storage.as_ref();
+        ensure_revision(storage, Some(&update.revision))?;
+        let mut schema = update.schema.clone();
+        prepare_rows(
+            storage,
+            Some(&update.previous_rows),
+            &mut update.rows,
+            &mut schema,
+            now,
+        )?;
+        prepared.push((update, schema));
+    }
+    Ok(prepared)
+}
+
+/// 行データとスキーマを書き込み、保存されていた内容があれば変更を履歴に残す
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `previous` - 保存されている行データとスキーマ（読み込めなかった場合はNoneで、全体を書き込み履歴は残さない）
+/// * `current` - 書き込む行データとスキーマ
+/// * `history` - 履歴に記録する操作と、元に戻す・やり直す対象の履歴の番号
+/// * `now` - 更新日時
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、失敗時はエラー
+fn write_with_history(
+    storage: &dyn Storage,
+    previous: Option<(&[Value], &TableSchema)>,
+    current: (&[Value], &TableSchema),
+    history: (HistoryAction, Option<u64>),
+    now: &str,
+) -> Result<(), WorkspaceError> {
+    let (data, schema) = current;
+    write_workspace(storage, previous.map(|(rows, _)| rows), data, schema)?;
+    if let Some(previous) = previous {
+        let (action, target) = history;
+        record_change(
+            &history_path(storage.data_path()),
+            previous,
+            current,
+            action,
+            target,
+            now,
+        )?;
+    }
+    Ok(())
+}
+
+/// 保存前の内容をバックアップしてから、前回の保存内容からの変更を書き込む
+/// バックアップは保持ルールの最短間隔ごとに作成する
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+/// * `previous_rows` - 保存されている行データ（読み込めなかった場合はNoneで、全体を書き込む）
+/// * `rows` - 保存する行データ
+/// * `schema` - 保存するスキーマ
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、失敗時はエラー
+pub fn write_workspace(
+    storage: &dyn Storage,
+    previous_rows: Option<&[Value]>,
+    rows: &[Value],
+    schema: &TableSchema,
+) -> Result<(), WorkspaceError> {
+    storage::rotate_backups(storage)?;
+    match previous_rows {
+        Some(previous_rows) => {
+            storage.write_changes(rows, schema, &RowChanges::between(previous_rows, rows))
+        }
+        None => storage.write_table(rows, schema),
+    }
+}
+
+/// スキーマのメタデータを更新する
+///
+/// # 引数
+/// * `schema` - 更新するスキーマの可変参照
+/// * `row_count` - 行数
+/// * `updated_at` - 更新日時
+pub fn update_schema_metadata(schema: &mut TableSchema, row_count: usize, updated_at: &str) {
+    schema.touch(row_count, updated_at);
+}
+
+/// テーブルペイロードを構築する
+/// データとスキーマを読み込み、ワークスペース情報と共にまとめる
+///
+/// # 引数
+/// * `storage` - ワークスペースのストレージ
+///
+/// # 戻り値
+/// 成功時はTablePayload、失敗時はエラー
+pub fn build_table_payload(storage: &dyn Storage) -> Result<TablePayload, WorkspaceError> {
+    // 読み込みと同時に更新された場合に備え、内容より先にリビジョンを取得する
+    let revision = storage.revision()?;
+    let (data, schema, related) = read_evaluated(storage)?;
+    let mut issues = validate_rows(&data, &schema);
+    issues.extend(validate_relations(&data, &schema, &related));
+    let links = relation_links(&data, &schema, &related);
+
+    let data_path = storage.data_path();
+    let workspace = WorkspaceInfo {
+        id: workspace_id(storage),
+        data_path: data_path.to_string_lossy().into_owned(),
+        schema_path: storage.schema_path().to_string_lossy().into_owned(),
+        folder: data_path
+            .parent()
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        storage: storage.kind(),
+    };
+
+    Ok(TablePayload {
+        data,
+        schema,
+        workspace,
+        issues,
+        links,
+        revision,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::read_history;
+    use crate::schema::{ColumnDefinition, ColumnType, OnDelete, RelationConfig};
+    use serde_json::json;
+
+    /// 名前のカラムを持つワークスペースを作成する
+    fn create(path: &Path) -> (Arc<dyn Storage>, TableSchema) {
+        let storage = open_storage(path).unwrap();
+        storage.ensure().unwrap();
+        let mut schema = storage.read_schema().unwrap();
+        schema
+            .columns
+            .push(ColumnDefinition::new("name", "Name", ColumnType::Text));
+        (storage, schema)
+    }
+
+    #[test]
+    fn stale_revision_is_a_conflict_with_the_current_content() {
+        let dir = tempfile::tempdir().unwrap();
+        for file_name in ["data.json", "data.db"] {
+            let path = dir.path().join(file_name);
+            let (storage, schema) = create(&path);
+            let saved = save_workspace(
+                storage.as_ref(),
+                vec![json!({"_id": "a", "name": "A"})],
+                schema.clone(),
+            )
+            .unwrap();
+            assert_eq!(storage.revision().unwrap(), saved.revision);
+            assert!(ensure_revision(storage.as_ref(), Some(&saved.revision)).is_ok());
+
+            // 別のウィンドウやプロセスが同じファイルに保存した
+            let other = open_storage(&path).unwrap();
+            let rows = other.read_rows().unwrap();
+            let mut changed = rows.clone();
+            changed[0]["name"] = json!("B");
+            let theirs = save_workspace(other.as_ref(), changed, schema).unwrap();
+            assert_ne!(theirs.revision, saved.revision, "{file_name}");
+
+            let error = ensure_revision(storage.as_ref(), Some(&saved.revision)).unwrap_err();
+            assert_eq!(error.code, ErrorCode::Conflict);
+            let snapshot = error.snapshot.unwrap();
+            assert_eq!(snapshot.revision, theirs.revision);
+            assert_eq!(snapshot.data[0]["name"], "B");
+
+            // 最新のリビジョンや、リビジョンを指定しない場合は保存できる
+            assert!(ensure_revision(storage.as_ref(), Some(&theirs.revision)).is_ok());
+            assert!(ensure_revision(storage.as_ref(), None).is_ok());
+        }
+    }
+
+    #[test]
+    fn cascaded_workspaces_are_saved_with_history_and_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let (customers, schema) = create(&dir.path().join("customers.json"));
+        save_workspace(
+            customers.as_ref(),
+            vec![
+                json!({"_id": "c1", "name": "A"}),
+                json!({"_id": "c2", "name": "B"}),
+            ],
+            schema.clone(),
+        )
+        .unwrap();
+        let (orders, mut orders_schema) = create(&dir.path().join("orders.json"));
+        let mut customer = ColumnDefinition::new("customer", "Customer", ColumnType::Relation);
+        customer.relation = Some(RelationConfig {
+            target: "customers.json".to_string(),
+            display_column: None,
+            on_delete: OnDelete::Cascade,
+        });
+        orders_schema.columns.push(customer);
+        save_workspace(
+            orders.as_ref(),
+            vec![
+                json!({"_id": "o1", "customer": "c1"}),
+                json!({"_id": "o2", "customer": "c2"}),
+            ],
+            orders_schema,
+        )
+        .unwrap();
+        let orders_revision = orders.revision().unwrap();
+
+        let rows = customers.read_rows().unwrap();
+        let saved = save_workspace(customers.as_ref(), rows[1..].to_vec(), schema).unwrap();
+        assert_eq!(
+            saved.updated_workspaces,
+            [orders.data_path().to_string_lossy()]
+        );
+
+        let rows = orders.read_rows().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["_id"], "o2");
+        assert_ne!(orders.revision().unwrap(), orders_revision);
+        // 参照している側の変更も履歴に残る
+        let log = read_history(&history_path(orders.data_path())).unwrap();
+        let last = log.entries.last().unwrap();
+        assert_eq!(last.rows.len(), 1);
+        assert_eq!(last.rows[0].row_id, "o1");
+        // 書き込む前の内容はバックアップされている
+        assert!(!storage::list_backups(orders.as_ref()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn imports_are_validated_before_anything_is_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("imported.json");
+        let price = ColumnDefinition::new("price", "Price", ColumnType::Number);
+        let imported = ImportedTable {
+            columns: vec![price.clone()],
+            rows: vec![json!({"price": 120}), json!({"price": "x"})],
+        };
+        let error = write_imported_workspace(&path.to_string_lossy(), imported).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidRows);
+        assert!(!path.exists());
+        assert!(!dir.path().join("imported.schema.json").exists());
+
+        let imported = ImportedTable {
+            columns: vec![price],
+            rows: vec![json!({"price": 120})],
+        };
+        let (data_path, saved) =
+            write_imported_workspace(&path.to_string_lossy(), imported).unwrap();
+        assert_eq!(saved.row_count, 1);
+        let issues = validate_workspace(open_storage(&data_path).unwrap().as_ref()).unwrap();
+        assert!(!has_errors(&issues));
+    }
+
+    #[test]
+    fn reading_does_not_change_the_revision() {
+        let dir = tempfile::tempdir().unwrap();
+        for file_name in ["data.json", "data.db"] {
+            let (storage, schema) = create(&dir.path().join(file_name));
+            let saved = save_workspace(
+                storage.as_ref(),
+                vec![json!({"_id": "a", "name": "A"})],
+                schema,
+            )
+            .unwrap();
+            build_table_payload(storage.as_ref()).unwrap();
+            assert_eq!(storage.revision().unwrap(), saved.revision, "{file_name}");
+        }
+    }
+
+    #[test]
+    fn unchanged_rows_keep_their_timestamp_and_are_not_rewritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.db");
+        let (storage, schema) = create(&path);
+        save_workspace(
+            storage.as_ref(),
+            vec![
+                json!({"_id": "a", "name": "A"}),
+                json!({"_id": "b", "name": "B"}),
+            ],
+            schema.clone(),
+        )
+        .unwrap();
+        let before = storage.read_rows().unwrap();
+
+        // 書き換えられた行を記録する
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE rewritten (id TEXT);
+                 CREATE TRIGGER record_rewrite AFTER UPDATE ON rows BEGIN
+                     INSERT INTO rewritten (id) VALUES (NEW.id);
+                 END;",
+            )
+            .unwrap();
+
+        // 読み込んだ内容のうち b だけを変更して保存する
+        let mut rows = before.clone();
+        rows[1]["name"] = json!("B2");
+        save_workspace(storage.as_ref(), rows, schema).unwrap();
+
+        let after = storage.read_rows().unwrap();
+        assert_eq!(after[0], before[0]);
+        assert_ne!(after[1]["_updated"], before[1]["_updated"]);
+        let rewritten: Vec<String> = connection
+            .prepare("SELECT id FROM rewritten")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rewritten, vec!["b".to_string()]);
+    }
+}
@@ -0,0 +1,505 @@
+// Excel (.xlsx) ファイルの読み込み・書き出し処理
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use calamine::{open_workbook_auto, Data, Range, Reader};
+use rust_xlsxwriter::{ColNum, Format, RowNum, Workbook, XlsxError};
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+
+use crate::schema::{ColumnDefinition, ColumnType, TableSchema};
+use crate::tabular::{cell_to_text, column_id_for, export_columns, ImportedTable};
+use crate::{tr, ErrorCode, WorkspaceError};
+
+/// シート名の最大文字数（Excel の制限）
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+/// Excel 取り込みのオプション
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct XlsxImportOptions {
+    /// 取り込むシート名（省略時は先頭のシート）
+    #[serde(default)]
+    pub sheet: Option<String>,
+    /// 見出し行の行番号（1始まり、省略時は自動判定）
+    #[serde(default)]
+    pub header_row: Option<usize>,
+    /// 見出し行の有無（省略時は自動判定、false の場合は見出しなし）
+    #[serde(default)]
+    pub has_header: Option<bool>,
+}
+
+/// Excel 書き出しのオプション
+#[derive(Debug, Clone, Deserialize)]
+pub struct XlsxExportOptions {
+    /// 非表示カラムを含めるか
+    #[serde(default)]
+    pub include_hidden: bool,
+    /// システム列（_id等）を含めるか
+    #[serde(default)]
+    pub include_system: bool,
+    /// multiselect の値を連結する区切り文字列
+    #[serde(default = "default_list_separator")]
+    pub list_separator: String,
+}
+
+impl Default for XlsxExportOptions {
+    fn default() -> Self {
+        Self {
+            include_hidden: false,
+            include_system: false,
+            list_separator: default_list_separator(),
+        }
+    }
+}
+
+/// Excel ファイルのシートを読み込み、カラム型を推定したテーブルに変換する
+///
+/// # 引数
+/// * `path` - 入力ファイルのパス
+/// * `options` - シートと見出し行の指定
+///
+/// # 戻り値
+/// 成功時は取り込んだテーブル、失敗時はエラー
+pub fn read_xlsx_file(
+    path: &Path,
+    options: &XlsxImportOptions,
+) -> Result<ImportedTable, WorkspaceError> {
+    // ファイルがない・読めない場合は、形式のエラーではなく読み込みのエラーにする
+    fs::metadata(path).map_err(|err| WorkspaceError::io(err, path))?;
+    let mut workbook = open_workbook_auto(path).map_err(|err| {
+        WorkspaceError::invalid_input(
+            "xlsx.unreadable",
+            tr!("xlsx.unreadable", path = path.display()),
+        )
+        .with_path(path)
+        .with_source(&err)
+    })?;
+
+    let sheet = match &options.sheet {
+        Some(name) => name.clone(),
+        None => workbook.sheet_names().first().cloned().ok_or_else(|| {
+            WorkspaceError::invalid_input("xlsx.no_sheets", tr!("xlsx.no_sheets")).with_path(path)
+        })?,
+    };
+    let range = workbook.worksheet_range(&sheet).map_err(|err| {
+        WorkspaceError::invalid_input(
+            "xlsx.sheet_unreadable",
+            tr!("xlsx.sheet_unreadable", sheet = sheet, error = err),
+        )
+        .with_path(path)
+        .with_source(&err)
+    })?;
+
+    let grid: Vec<Vec<Data>> = range_rows(&range);
+
+    let header_index = match (options.has_header, options.header_row) {
+        (Some(false), _) => None,
+        (_, Some(row)) if row >= 1 => Some(row - 1),
+        (_, Some(_)) => {
+            return Err(WorkspaceError::invalid_input(
+                "xlsx.header_row_invalid",
+                tr!("xlsx.header_row_invalid"),
+            ))
+        }
+        (Some(true), None) => grid.iter().position(|row| !is_empty_row(row)),
+        (None, None) => detect_header_row(&grid),
+    };
+
+    let (headers, records) = match header_index {
+        Some(index) if index < grid.len() => {
+            let headers = grid[index].iter().map(cell_text).collect::<Vec<_>>();
+            let records = grid[index + 1..]
+                .iter()
+                .filter(|row| !is_empty_row(row))
+                .cloned()
+                .collect();
+            (Some(headers), records)
+        }
+        Some(_) => {
+            return Err(WorkspaceError::invalid_input(
+                "xlsx.header_row_out_of_range",
+                tr!("xlsx.header_row_out_of_range"),
+            )
+            .with_path(path))
+        }
+        None => {
+            let records = grid.into_iter().filter(|row| !is_empty_row(row)).collect();
+            (None, records)
+        }
+    };
+
+    Ok(build_from_cells(headers, records))
+}
+
+/// テーブルを Excel ファイルに書き出す
+/// 1シートに見出し行と型付きのセルを出力する（checkbox は TRUE/FALSE）
+///
+/// # 引数
+/// * `path` - 出力先のパス
+/// * `schema` - スキーマ
+/// * `rows` - 行データ
+/// * `options` - 書き出しオプション
+///
+/// # 戻り値
+/// 成功時は書き出した行数、失敗時はエラー
+pub fn write_xlsx_file(
+    path: &Path,
+    schema: &TableSchema,
+    rows: &[Value],
+    options: &XlsxExportOptions,
+) -> Result<usize, WorkspaceError> {
+    let columns = export_columns(schema, options.include_hidden, options.include_system);
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet
+        .set_name(sheet_name_for(&schema.table_name))
+        .map_err(|err| write_error(err, path))?;
+
+    let header_format = Format::new().set_bold();
+    for (col, column) in columns.iter().enumerate() {
+        let col = col as ColNum;
+        worksheet
+            .write_string_with_format(0, col, &column.name, &header_format)
+            .map_err(|err| write_error(err, path))?;
+        if let Some(width) = column.width {
+            // ピクセル幅をおおよその文字数に換算する
+            worksheet
+                .set_column_width(col, f64::from(width) / 7.0)
+                .map_err(|err| write_error(err, path))?;
+        }
+    }
+    worksheet
+        .set_freeze_panes(1, 0)
+        .map_err(|err| write_error(err, path))?;
+
+    for (index, row) in rows.iter().enumerate() {
+        let row_num = RowNum::try_from(index + 1).map_err(|_| {
+            WorkspaceError::invalid_input("xlsx.too_many_rows", tr!("xlsx.too_many_rows"))
+        })?;
+        for (col, column) in columns.iter().enumerate() {
+            let col = col as ColNum;
+            let value = row.get(&column.id).unwrap_or(&Value::Null);
+            let result = match (column.column_type, value) {
+                (_, Value::Null) => continue,
+                (
+                    ColumnType::Number | ColumnType::Formula | ColumnType::Rollup,
+                    Value::Number(number),
+                ) => match number.as_f64() {
+                    Some(number) => worksheet.write_number(row_num, col, number),
+                    None => worksheet.write_string(row_num, col, number.to_string()),
+                },
+                (ColumnType::Checkbox | ColumnType::Formula, Value::Bool(flag)) => {
+                    worksheet.write_boolean(row_num, col, *flag)
+                }
+                _ => worksheet.write_string(
+                    row_num,
+                    col,
+                    cell_to_text(value, &options.list_separator),
+                ),
+            };
+            result.map_err(|err| write_error(err, path))?;
+        }
+    }
+
+    workbook.save(path).map_err(|err| write_error(err, path))?;
+    Ok(rows.len())
+}
+
+/// 書き出しのエラーをワークスペースのエラーにする（ファイルを書き込めない場合は読み書きのエラー）
+fn write_error(error: XlsxError, path: &Path) -> WorkspaceError {
+    match error {
+        XlsxError::IoError(err) => WorkspaceError::io(err, path),
+        err => WorkspaceError::new(
+            ErrorCode::Failed,
+            "xlsx.write_failed",
+            tr!("xlsx.write_failed", path = path.display()),
+        )
+        .with_path(path)
+        .with_source(&err),
+    }
+}
+
+/// 範囲の内容を行ごとのセル配列に変換する（範囲の開始位置より前の空白も含める）
+fn range_rows(range: &Range<Data>) -> Vec<Vec<Data>> {
+    let (start_row, start_col) = range
+        .start()
+        .map(|(row, col)| (row as usize, col as usize))
+        .unwrap_or((0, 0));
+    let mut grid = vec![Vec::new(); start_row];
+    grid.extend(range.rows().map(|row| {
+        let mut cells = vec![Data::Empty; start_col];
+        cells.extend(row.iter().cloned());
+        cells
+    }));
+    grid
+}
+
+/// 見出し行を推定する
+/// 最初の空でない行のセルがすべて文字列で重複もなければ、その行を見出しとみなす
+fn detect_header_row(grid: &[Vec<Data>]) -> Option<usize> {
+    let first = grid.iter().position(|row| !is_empty_row(row))?;
+    let mut seen = HashSet::new();
+    let is_header = grid[first]
+        .iter()
+        .filter(|cell| !matches!(cell, Data::Empty))
+        .all(|cell| matches!(cell, Data::String(text) if seen.insert(text.trim().to_string())));
+    is_header.then_some(first)
+}
+
+fn is_empty_row(row: &[Data]) -> bool {
+    row.iter().all(|cell| match cell {
+        Data::Empty => true,
+        Data::String(text) => text.trim().is_empty(),
+        _ => false,
+    })
+}
+
+/// 見出しと型付きセルの表から ImportedTable を作成する
+fn build_from_cells(headers: Option<Vec<String>>, records: Vec<Vec<Data>>) -> ImportedTable {
+    let width = records
+        .iter()
+        .map(Vec::len)
+        .chain(headers.iter().map(Vec::len))
+        .max()
+        .unwrap_or(0);
+
+    let mut existing = HashSet::from(["_id".to_string()]);
+    let columns: Vec<ColumnDefinition> = (0..width)
+        .map(|index| {
+            let name = headers
+                .as_ref()
+                .and_then(|headers| headers.get(index))
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("Column {}", index + 1));
+            let column_type = infer_cell_type(records.iter().filter_map(|row| row.get(index)));
+            let id = column_id_for(&name, index, &mut existing);
+            ColumnDefinition::new(id, name, column_type)
+        })
+        .collect();
+
+    let rows = records
+        .iter()
+        .map(|record| {
+            let object: Map<String, Value> = columns
+                .iter()
+                .enumerate()
+                .map(|(index, column)| {
+                    let cell = record.get(index).unwrap_or(&Data::Empty);
+                    (column.id.clone(), convert_cell(cell, column.column_type))
+                })
+                .collect();
+            Value::Object(object)
+        })
+        .collect();
+
+    ImportedTable { columns, rows }
+}
+
+/// セルの型からカラム型を推定する
+/// 空でないセルがすべて数値なら number、すべて真偽値なら checkbox、それ以外は text
+fn infer_cell_type<'a>(cells: impl Iterator<Item = &'a Data>) -> ColumnType {
+    let mut all_numbers = true;
+    let mut all_booleans = true;
+    let mut any_value = false;
+
+    for cell in cells {
+        match cell {
+            Data::Empty => continue,
+            Data::String(text) if text.trim().is_empty() => continue,
+            Data::Int(_) | Data::Float(_) => all_booleans = false,
+            Data::Bool(_) => all_numbers = false,
+            _ => {
+                all_numbers = false;
+                all_booleans = false;
+            }
+        }
+        any_value = true;
+    }
+
+    if !any_value {
+        ColumnType::Text
+    } else if all_numbers {
+        ColumnType::Number
+    } else if all_booleans {
+        ColumnType::Checkbox
+    } else {
+        ColumnType::Text
+    }
+}
+
+/// セルをカラム型に合わせたJSON値に変換する
+fn convert_cell(cell: &Data, column_type: ColumnType) -> Value {
+    match (column_type, cell) {
+        (ColumnType::Number, Data::Int(number)) => Value::Number(Number::from(*number)),
+        (ColumnType::Number, Data::Float(number)) => float_value(*number),
+        (ColumnType::Number, _) => Value::Null,
+        (ColumnType::Checkbox, Data::Bool(flag)) => Value::Bool(*flag),
+        (ColumnType::Checkbox, _) => Value::Bool(false),
+        _ => Value::String(cell_text(cell)),
+    }
+}
+
+/// 小数を JSON の数値に変換する（整数値は整数として保存する）
+fn float_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Value::Number(Number::from(number as i64))
+    } else {
+        Number::from_f64(number)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+}
+
+/// セルを表示用の文字列に変換する
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(text) | Data::DateTimeIso(text) | Data::DurationIso(text) => text.clone(),
+        Data::Int(number) => number.to_string(),
+        Data::Float(number) => match float_value(*number) {
+            Value::Number(number) => number.to_string(),
+            _ => number.to_string(),
+        },
+        Data::Bool(flag) => if *flag { "TRUE" } else { "FALSE" }.to_string(),
+        Data::DateTime(datetime) => match datetime.as_datetime() {
+            Some(value) if value.time() == chrono::NaiveTime::MIN => {
+                value.format("%Y-%m-%d").to_string()
+            }
+            Some(value) => value.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            None => datetime.to_string(),
+        },
+        Data::Error(error) => error.to_string(),
+    }
+}
+
+/// テーブル名から Excel で使えるシート名を作る
+fn sheet_name_for(table_name: &str) -> String {
+    let name: String = table_name
+        .chars()
+        .map(|ch| match ch {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            _ => ch,
+        })
+        .collect();
+    let name = name.trim_matches('\'').trim();
+    if name.is_empty() {
+        "Sheet1".to_string()
+    } else {
+        name.chars().take(MAX_SHEET_NAME_LEN).collect()
+    }
+}
+
+fn default_list_separator() -> String {
+    ", ".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> TableSchema {
+        let mut schema = TableSchema::new("在庫/一覧", "2024-01-01T00:00:00Z");
+        let mut note = ColumnDefinition::new("note", "Note", ColumnType::Text);
+        note.hidden = true;
+        schema.columns.extend([
+            ColumnDefinition::new("name", "Name", ColumnType::Text),
+            ColumnDefinition::new("qty", "Qty", ColumnType::Number),
+            ColumnDefinition::new("done", "Done", ColumnType::Checkbox),
+            ColumnDefinition::new("tags", "Tags", ColumnType::Multiselect),
+            note,
+        ]);
+        schema
+    }
+
+    #[test]
+    fn round_trips_typed_cells() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.xlsx");
+        let rows = vec![
+            json!({"_id": "a", "name": "りんご", "qty": 3, "done": true, "tags": ["x", "y"], "note": "n"}),
+            json!({"_id": "b", "name": "007", "qty": 2.5, "done": false, "tags": []}),
+        ];
+        let written =
+            write_xlsx_file(&path, &schema(), &rows, &XlsxExportOptions::default()).unwrap();
+        assert_eq!(written, 2);
+
+        let imported = read_xlsx_file(&path, &XlsxImportOptions::default()).unwrap();
+        let columns: Vec<(&str, ColumnType)> = imported
+            .columns
+            .iter()
+            .map(|column| (column.name.as_str(), column.column_type))
+            .collect();
+        // 非表示のカラムは書き出さず、multiselectは区切り文字列で連結した文字列になる
+        assert_eq!(
+            columns,
+            [
+                ("Name", ColumnType::Text),
+                ("Qty", ColumnType::Number),
+                ("Done", ColumnType::Checkbox),
+                ("Tags", ColumnType::Text),
+            ]
+        );
+        assert_eq!(
+            imported.rows,
+            [
+                json!({"name": "りんご", "qty": 3, "done": true, "tags": "x, y"}),
+                json!({"name": "007", "qty": 2.5, "done": false, "tags": ""}),
+            ]
+        );
+
+        // シート名にはテーブル名の使えない文字を置き換えて使う
+        let options = XlsxImportOptions {
+            sheet: Some("在庫_一覧".to_string()),
+            header_row: Some(1),
+            ..XlsxImportOptions::default()
+        };
+        assert_eq!(read_xlsx_file(&path, &options).unwrap().rows.len(), 2);
+    }
+
+    #[test]
+    fn reports_missing_files_and_bad_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.xlsx");
+        let error = read_xlsx_file(&missing, &XlsxImportOptions::default()).unwrap_err();
+        assert_eq!(error.code, ErrorCode::NotFound);
+
+        let not_excel = dir.path().join("text.xlsx");
+        fs::write(&not_excel, "not a workbook").unwrap();
+        let error = read_xlsx_file(&not_excel, &XlsxImportOptions::default()).unwrap_err();
+        assert_eq!(error.message_key, "xlsx.unreadable");
+
+        let path = dir.path().join("out.xlsx");
+        write_xlsx_file(&path, &schema(), &[], &XlsxExportOptions::default()).unwrap();
+        for (options, key) in [
+            (
+                XlsxImportOptions {
+                    header_row: Some(0),
+                    ..XlsxImportOptions::default()
+                },
+                "xlsx.header_row_invalid",
+            ),
+            (
+                XlsxImportOptions {
+                    header_row: Some(5),
+                    ..XlsxImportOptions::default()
+                },
+                "xlsx.header_row_out_of_range",
+            ),
+            (
+                XlsxImportOptions {
+                    sheet: Some("Other".to_string()),
+                    ..XlsxImportOptions::default()
+                },
+                "xlsx.sheet_unreadable",
+            ),
+        ] {
+            assert_eq!(
+                read_xlsx_file(&path, &options).unwrap_err().message_key,
+                key
+            );
+        }
+    }
+}
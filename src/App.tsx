@@ -25,7 +25,18 @@ import { CSS } from "@dnd-kit/utilities";
 import type { UnlistenFn } from "@tauri-apps/api/event";
 
 // カラムのデータ型
-type ColumnType = "text" | "number" | "checkbox" | "multiselect" | "relation";
+type ColumnType =
+  | "text"
+  | "number"
+  | "checkbox"
+  | "multiselect"
+  | "relation"
+  | "formula"
+  | "lookup"
+  | "rollup";
+
+// 読み込み時にバックエンドが値を求める（ファイルに保存しない）カラム型
+const COMPUTED_TYPES: ColumnType[] = ["formula", "lookup", "rollup"];
 
 // テーブルの1行を表す型（キーは列ID、値は任意の型）
 type TableRow = Record<string, unknown>;
@@ -40,8 +51,35 @@ interface ColumnDefinition {
   hidden?: boolean;     // 非表示かどうか
   system?: boolean;     // システム列かどうか（_id, _created等）
   format?: string;      // フォーマット指定（将来の拡張用）
+  formula?: string;     // 計算列の式（formula型の場合のみ）
+  relation?: RelationConfig; // リレーションの参照先（relation型の場合のみ）
+  lookup?: LookupConfig;     // 表示するリレーション先のカラム（lookup型の場合のみ）
+  rollup?: RollupConfig;     // 集計するリレーション先のカラム（rollup型の場合のみ）
 }
 
+/** リレーション列の参照先 */
+interface RelationConfig {
+  target: string;                                   // 参照先のデータファイル（ワークスペースのフォルダからの相対パス）
+  display_column?: string;                          // 表示値に使う参照先のカラムID
+  on_delete?: "restrict" | "cascade" | "nullify";   // 参照先の行が削除されたときの動作
+}
+
+/** lookup列の設定 */
+interface LookupConfig {
+  relation: string;   // 同じスキーマのリレーション列のID
+  column: string;     // 表示するリレーション先のカラム（IDまたは表示名）
+}
+
+/** rollup列の設定 */
+interface RollupConfig {
+  relation: string;                                              // 同じスキーマのリレーション列のID
+  column?: string;                                               // 集計するリレーション先のカラム（countでは省略可）
+  function: "count" | "sum" | "avg" | "min" | "max" | "concat";  // 集計方法
+}
+
+// リレーション列ごとの、参照先の_idと表示値
+type RelationLinks = Record<string, Record<string, string>>;
+
 /** テーブルスキーマを表すインターフェース */
 interface TableSchema {
   version?: string;                     // スキーマバージョン
@@ -53,9 +91,21 @@ interface TableSchema {
 
 /** バックエンドから受け取るワークスペース情報 */
 interface WorkspaceInfoPayload {
+  id: string;                  // ワークスペースID（コマンドの呼び出しとイベントの判別に使用）
   data_path: string;
   schema_path: string;
   folder: string;
+  storage: "json" | "sqlite";  // 保存形式
+}
+
+/** バックエンドが返す行データの検証結果 */
+interface RowIssue {
+  row_index: number;          // 行のインデックス
+  row_id: string | null;      // 行の_id
+  column_id: string | null;   // 問題のあるカラムID
+  kind: string;               // 問題の種類
+  severity: "error" | "warning";
+  message: string;            // 説明文
 }
 
 /** バックエンドから受け取るテーブルデータのペイロード */
@@ -63,10 +113,14 @@ interface TablePayload {
   data: TableRow[];
   schema: TableSchema;
   workspace: WorkspaceInfoPayload;
+  issues: RowIssue[];
+  links: RelationLinks;       // リレーション列の表示値
+  revision: string;           // 読み込んだ内容のリビジョン
 }
 
 /** フロントエンドで管理するワークスペース情報 */
 interface WorkspaceInfo {
+  id: string;
   dataPath: string;
   schemaPath: string;
   folder: string;
@@ -76,6 +130,58 @@ interface WorkspaceInfo {
 interface SaveResult {
   row_count: number;
   updated_at: string;
+  issues: RowIssue[];
+  revision: string;           // 保存後のリビジョン
+}
+
+/** コマンドが失敗した場合にバックエンドから返るエラー */
+interface CommandError {
+  code: string;               // "not_found" | "invalid_json" | "conflict" | "restricted" など（変わらないコード）
+  message_key: string;        // メッセージカタログのキー
+  message: string;
+  context: {
+    path?: string;
+    line?: number;            // JSONの読み込みに失敗した行
+    column?: number;
+    row_id?: string;          // 問題のある行の_id
+    column_id?: string;
+  };
+  sources?: string[];         // 原因となったエラーのメッセージ
+  snapshot?: TablePayload;    // 競合時に現在保存されている内容
+  details?: CommandError[];   // スキーマの検証で見つかった個々の問題
+}
+
+/** 3-wayマージで自動解決できなかった競合 */
+interface MergeConflict {
+  row_id: string;
+  column_id: string | null;   // 行単位の競合の場合はnull
+  kind: "cell" | "deleted_on_disk" | "deleted_locally";
+  base: unknown;
+  local: unknown;             // マージ結果に採用した値
+  remote: unknown;
+}
+
+/** merge_workspaceの結果 */
+interface MergeResult {
+  data: TableRow[];
+  schema: TableSchema;
+  conflicts: MergeConflict[];
+  revision: string;           // マージの基準にしたリビジョン
+}
+
+/** undo/redoの結果 */
+interface HistoryResult {
+  table: TablePayload;        // 適用後の内容
+  target: number;             // 元に戻した・やり直した履歴の番号
+  can_undo: boolean;
+  can_redo: boolean;
+}
+
+/** 起動中のローカルAPIの接続先 */
+interface ApiServerInfo {
+  url: string;                // http://127.0.0.1:<port>
+  port: number;
+  token: string;              // Authorization: Bearer <token> で指定する
 }
 
 /** 外部変更検出時の競合状態を表すインターフェース */
@@ -147,6 +253,11 @@ function createEmptyRow(columns: ColumnDefinition[], order: number): TableRow {
       case "checkbox":
         row[column.id] = false;
         break;
+      case "formula":
+      case "lookup":
+      case "rollup":
+        // 計算列の値はバックエンドが読み込み時に求める
+        break;
       default:
         row[column.id] = "";
     }
@@ -201,6 +312,21 @@ function cloneRows(rows: TableRow[]): TableRow[] {
   return rows.map((row) => ({ ...row }));
 }
 
+/**
+ * コマンドのエラーを表示用の文字列にする
+ * @param error invokeが投げた値
+ * @returns エラーメッセージ
+ */
+function formatError(error: unknown): string {
+  if (error instanceof Error) return error.message;
+  if (typeof error === "string") return error;
+  const commandError = error as Partial<CommandError> | null;
+  if (commandError && typeof commandError.message === "string") {
+    return commandError.message;
+  }
+  return JSON.stringify(error);
+}
+
 /**
  * メインアプリケーションコンポーネント
  * テーブルエディタのUI全体を管理
@@ -209,6 +335,8 @@ export default function App(): JSX.Element {
   // ========== State管理 ==========
   const [rows, setRows] = useState<TableRow[]>([]);                      // テーブルの行データ
   const [schema, setSchema] = useState<TableSchema | null>(null);         // テーブルスキーマ
+  const [links, setLinks] = useState<RelationLinks>({});                  // リレーション列の表示値
+  const [tables, setTables] = useState<CatalogEntry[]>([]);               // 同じフォルダのテーブルの一覧
   const [workspace, setWorkspace] = useState<WorkspaceInfo | null>(null); // ワークスペース情報
   const [statusMessage, setStatusMessage] = useState<string>("ワークスペースを選択してください");
   const [isSaving, setIsSaving] = useState(false);                        // 保存中フラグ
@@ -216,6 +344,7 @@ export default function App(): JSX.Element {
   const [dirty, setDirty] = useState(false);                              // 未保存の変更があるか
   const [conflict, setConflict] = useState<ConflictState | null>(null);   // 外部変更の競合状態
   const [errorMessage, setErrorMessage] = useState<string | null>(null);  // エラーメッセージ
+  const [apiServer, setApiServer] = useState<ApiServerInfo | null>(null); // 起動中のローカルAPI
   const [columnDialog, setColumnDialog] = useState<{
     open: boolean;
     name: string;
@@ -225,9 +354,12 @@ export default function App(): JSX.Element {
   // ========== Ref管理 ==========
   const saveTimerRef = useRef<number | null>(null);                       // 自動保存タイマー
   const latestPayloadRef = useRef<{ rows: TableRow[]; schema: TableSchema } | null>(null); // 最新の保存予定データ
-  const ignoreEventsUntilRef = useRef<number>(0);                         // ファイル変更イベントを無視する期限
+  const revisionRef = useRef<string | null>(null);                        // 最後に読み込み・保存したリビジョン
+  const baseRowsRef = useRef<TableRow[]>([]);                             // 最後に読み込み・保存した行データ（マージの基準）
   const suspendAutoSaveRef = useRef<boolean>(false);                      // 自動保存を一時停止するフラグ
   const unlistenRef = useRef<Promise<UnlistenFn> | null>(null);           // イベントリスナーの解除関数
+  const workspaceIdRef = useRef<string | null>(null);                      // 開いているワークスペースのID
+  const dirtyRef = useRef<boolean>(false);                                 // イベントハンドラから参照する未保存フラグ
   const draggedColumnIdRef = useRef<string | null>(null);                 // ドラッグ中のカラムID
 
   // ユーザーに表示するカラム（非表示カラムを除外）
@@ -262,18 +394,26 @@ export default function App(): JSX.Element {
     setIsSaving(true);
     setStatusMessage("保存中…");
     setErrorMessage(null);
-    // 保存後2秒間はファイル変更イベントを無視（自分の変更を検出しないため）
-    ignoreEventsUntilRef.current = Date.now() + 2000;
 
     try {
+      // 読み込み時のリビジョンを送り、他で更新されていれば競合として扱う
       const result = await invoke<SaveResult>("save_table", {
+        workspaceId: workspace.id,
         payload: {
           data: payload.rows,
           schema: payload.schema,
+          revision: revisionRef.current,
         },
       });
+      revisionRef.current = result.revision;
+      baseRowsRef.current = cloneRows(payload.rows);
       setDirty(false);
-      setStatusMessage(`保存完了 (${new Date(result.updated_at).toLocaleTimeString()})`);
+      const savedAt = new Date(result.updated_at).toLocaleTimeString();
+      setStatusMessage(
+        result.issues.length > 0
+          ? `保存完了 (${savedAt}) ・ 入力チェック ${result.issues.length}件`
+          : `保存完了 (${savedAt})`
+      );
       // 保存成功したデータを記録
       latestPayloadRef.current = {
         rows: cloneRows(payload.rows),
@@ -281,12 +421,13 @@ export default function App(): JSX.Element {
       };
     } catch (error) {
       console.error(error);
-      const message =
-        error instanceof Error
-          ? error.message
-          : typeof error === "string"
-          ? error
-          : JSON.stringify(error);
+      const commandError = error as Partial<CommandError>;
+      if (commandError.code === "conflict" && commandError.snapshot) {
+        setConflict({ snapshot: commandError.snapshot, detectedAt: new Date().toISOString() });
+        setStatusMessage("外部変更と競合しました");
+        return;
+      }
+      const message = formatError(error);
       setErrorMessage(`保存中にエラーが発生しました: ${message}`);
       setStatusMessage(`保存失敗 (${message})`);
     } finally {
@@ -366,7 +507,15 @@ export default function App(): JSX.Element {
     suspendAutoSaveRef.current = true;
     setRows(cloneRows(snapshot.data));
     setSchema({ ...snapshot.schema });
+    setLinks(snapshot.links ?? {});
+    // この画面では1つのテーブルだけを表示するため、別のテーブルを開いたら前のワークスペースを閉じる
+    const previousId = workspaceIdRef.current;
+    if (previousId && previousId !== snapshot.workspace.id) {
+      invoke("close_workspace", { workspaceId: previousId }).catch((error) => console.error(error));
+    }
+    workspaceIdRef.current = snapshot.workspace.id;
     setWorkspace({
+      id: snapshot.workspace.id,
       dataPath: snapshot.workspace.data_path,
       schemaPath: snapshot.workspace.schema_path,
       folder: snapshot.workspace.folder,
@@ -375,12 +524,43 @@ export default function App(): JSX.Element {
       rows: cloneRows(snapshot.data),
       schema: { ...snapshot.schema },
     };
+    revisionRef.current = snapshot.revision;
+    baseRowsRef.current = cloneRows(snapshot.data);
     setDirty(false);
-    setStatusMessage("最新の内容を読み込みました");
+    setStatusMessage(
+      snapshot.issues.length > 0
+        ? `最新の内容を読み込みました ・ 入力チェック ${snapshot.issues.length}件`
+        : "最新の内容を読み込みました"
+    );
     setConflict(null);
     suspendAutoSaveRef.current = false;
   }, []);
 
+  /**
+   * 指定したデータファイルのワークスペースを読み込む
+   * @param dataPath データファイルのパス
+   */
+  const loadWorkspace = useCallback(
+    async (dataPath: string) => {
+      setIsLoading(true);
+      setStatusMessage("読み込み中…");
+      setErrorMessage(null);
+
+      try {
+        const payload = await invoke<TablePayload>("load_table", { dataPath });
+        applySnapshot(payload);
+      } catch (error) {
+        console.error(error);
+        const message = formatError(error);
+        setErrorMessage(`ワークスペースの読み込みに失敗しました: ${message}`);
+        setStatusMessage(`読み込み失敗 (${message})`);
+      } finally {
+        setIsLoading(false);
+      }
+    },
+    [applySnapshot],
+  );
+
   /**
    * ワークスペースを開く処理
    * ファイルダイアログを表示し、選択されたファイルを読み込む
@@ -391,34 +571,43 @@ export default function App(): JSX.Element {
     const selected = await open({
       directory: false,
       multiple: false,
-      filters: [{ name: "JSON", extensions: ["json"] }],
+      filters: [
+        { name: "JSON", extensions: ["json"] },
+        { name: "SQLite", extensions: ["db", "sqlite", "sqlite3"] },
+      ],
     });
 
     if (!selected || Array.isArray(selected)) {
       return;
     }
 
-    setIsLoading(true);
-    setStatusMessage("読み込み中…");
-    setErrorMessage(null);
+    await loadWorkspace(selected);
+  }, [flushPendingSave, loadWorkspace]);
 
-    try {
-      const payload = await invoke<TablePayload>("load_table", { dataPath: selected });
-      applySnapshot(payload);
-    } catch (error) {
-      console.error(error);
-      const message =
-        error instanceof Error
-          ? error.message
-          : typeof error === "string"
-          ? error
-          : JSON.stringify(error);
-      setErrorMessage(`ワークスペースの読み込みに失敗しました: ${message}`);
-      setStatusMessage(`読み込み失敗 (${message})`);
-    } finally {
-      setIsLoading(false);
+  /**
+   * サイドバーで選択した同じフォルダのテーブルを開く
+   * @param dataPath データファイルのパス
+   */
+  const handleSelectTable = useCallback(
+    async (dataPath: string) => {
+      if (workspace?.dataPath === dataPath) return;
+      await flushPendingSave();
+      await loadWorkspace(dataPath);
+    },
+    [workspace, flushPendingSave, loadWorkspace],
+  );
+
+  // ワークスペースのフォルダが変わったらテーブルの一覧を読み込む
+  const workspaceFolder = workspace?.folder;
+  useEffect(() => {
+    if (!workspaceFolder) {
+      setTables([]);
+      return;
     }
-  }, [applySnapshot, flushPendingSave]);
+    invoke<CatalogEntry[]>("list_tables", { folder: workspaceFolder })
+      .then(setTables)
+      .catch((error) => console.error(error));
+  }, [workspaceFolder]);
 
   /**
    * 新しいワークスペースを作成する処理
@@ -452,12 +641,7 @@ export default function App(): JSX.Element {
       setStatusMessage("新しいテーブルを読み込みました");
     } catch (error) {
       console.error(error);
-      const message =
-        error instanceof Error
-          ? error.message
-          : typeof error === "string"
-          ? error
-          : JSON.stringify(error);
+      const message = formatError(error);
       setErrorMessage(`テーブルの作成に失敗しました: ${message}`);
       setStatusMessage(`作成失敗 (${message})`);
     } finally {
@@ -474,19 +658,119 @@ export default function App(): JSX.Element {
       unlistenRef.current.then((unlisten) => unlisten());
     }
 
-    unlistenRef.current = listen<WorkspaceChangePayload>("workspace:file-changed", async () => {
-      if (!workspace) return;
-      if (Date.now() < ignoreEventsUntilRef.current) return;
+    const fileChanged = listen<WorkspaceChangePayload>("workspace:file-changed", async (event) => {
+      if (!workspace || event.payload.workspace_id !== workspace.id) return;
 
       try {
-        const snapshot = await invoke<TablePayload>("fetch_workspace");
+        const snapshot = await invoke<TablePayload>("fetch_workspace", { workspaceId: workspace.id });
+        // 自分の保存による変更通知は無視する
+        if (snapshot.revision === revisionRef.current) return;
+        // 未保存の変更がなければ、同じファイルを開いている他のウィンドウ等の保存をそのまま反映する
+        if (!dirtyRef.current) {
+          applySnapshot(snapshot);
+          setStatusMessage("他のウィンドウでの変更を読み込みました");
+          return;
+        }
         setConflict({ snapshot, detectedAt: new Date().toISOString() });
         setStatusMessage("外部変更を検出しました");
       } catch (error) {
         console.error(error);
       }
     });
-  }, [workspace]);
+
+    // リレーションの参照先が変更された場合は、編集中の値を残して計算列と表示値だけを更新する
+    const relatedChanged = listen<WorkspaceChangePayload>("workspace:related-changed", async (event) => {
+      if (!workspace || event.payload.workspace_id !== workspace.id) return;
+
+      try {
+        const snapshot = await invoke<TablePayload>("fetch_workspace", { workspaceId: workspace.id });
+        const computedIds = snapshot.schema.columns
+          .filter((column) => COMPUTED_TYPES.includes(column.type))
+          .map((column) => column.id);
+        const freshRows = new Map(snapshot.data.map((row) => [String(row._id), row]));
+        setRows((current) =>
+          current.map((row) => {
+            const fresh = freshRows.get(String(row._id));
+            if (!fresh) return row;
+            const updated: TableRow = { ...row };
+            computedIds.forEach((id) => {
+              updated[id] = fresh[id];
+            });
+            return updated;
+          }),
+        );
+        setLinks(snapshot.links ?? {});
+      } catch (error) {
+        console.error(error);
+      }
+    });
+
+    // フォルダ内のテーブルが追加・削除・名前変更された場合はサイドバーの一覧を更新する
+    const catalogChanged = listen<CatalogPayload>("workspace:catalog-changed", (event) => {
+      if (!workspace || event.payload.workspace_id !== workspace.id) return;
+      setTables(event.payload.tables);
+    });
+
+    unlistenRef.current = Promise.all([fileChanged, relatedChanged, catalogChanged]).then(
+      (unlisteners) => () => unlisteners.forEach((unlisten) => unlisten()),
+    );
+  }, [workspace, applySnapshot]);
+
+  useEffect(() => {
+    dirtyRef.current = dirty;
+  }, [dirty]);
+
+  // 新しいウィンドウとして開かれた場合は、URLで指定されたワークスペースを読み込む
+  useEffect(() => {
+    const workspaceId = new URLSearchParams(window.location.search).get("workspace");
+    if (!workspaceId) return;
+    invoke<TablePayload>("fetch_workspace", { workspaceId })
+      .then(applySnapshot)
+      .catch((error) => {
+        console.error(error);
+        setErrorMessage(`ワークスペースの読み込みに失敗しました: ${formatError(error)}`);
+      });
+  }, [applySnapshot]);
+
+  // 他のウィンドウで開始したローカルAPIの状態も表示する
+  useEffect(() => {
+    invoke<ApiServerInfo | null>("get_api_server")
+      .then(setApiServer)
+      .catch((error) => console.error(error));
+  }, []);
+
+  /**
+   * ローカルAPI（他のツールから開いているテーブルを読み書きするHTTP API）を開始・停止する
+   */
+  const handleToggleApi = useCallback(async () => {
+    try {
+      if (apiServer) {
+        await invoke<boolean>("stop_api_server");
+        setApiServer(null);
+        setStatusMessage("ローカルAPIを停止しました");
+      } else {
+        const info = await invoke<ApiServerInfo>("start_api_server", {});
+        setApiServer(info);
+        setStatusMessage(`ローカルAPIを開始しました (${info.url})`);
+      }
+    } catch (error) {
+      console.error(error);
+      setErrorMessage(`ローカルAPIを切り替えられませんでした: ${formatError(error)}`);
+    }
+  }, [apiServer]);
+
+  /**
+   * テーブルを新しいウィンドウで開く
+   * @param dataPath データファイルのパス
+   */
+  const handleOpenInWindow = useCallback(async (dataPath: string) => {
+    try {
+      await invoke<string>("open_table_window", { dataPath });
+    } catch (error) {
+      console.error(error);
+      setErrorMessage(`ウィンドウを開けませんでした: ${formatError(error)}`);
+    }
+  }, []);
 
   useEffect(() => {
     registerWorkspaceListeners();
@@ -553,6 +837,10 @@ export default function App(): JSX.Element {
           case "checkbox":
             updated[columnId] = false;
             break;
+          case "formula":
+          case "lookup":
+          case "rollup":
+            break;
           default:
             updated[columnId] = "";
         }
@@ -681,9 +969,13 @@ export default function App(): JSX.Element {
    * 競合解決: 自分の変更を保持する
    */
   const handleResolveKeep = useCallback(async () => {
-    await performSave();
+    // 外部の変更を確認した上で上書きする
+    if (conflict) {
+      revisionRef.current = conflict.snapshot.revision;
+    }
     setConflict(null);
-  }, [performSave]);
+    await performSave();
+  }, [conflict, performSave]);
 
   /**
    * 競合解決: 外部の変更を読み込む
@@ -693,6 +985,73 @@ export default function App(): JSX.Element {
     applySnapshot(conflict.snapshot);
   }, [conflict, applySnapshot]);
 
+  /**
+   * 保存済みの変更を元に戻す・やり直す
+   * 履歴はバックエンドのファイルに記録されているため、再起動後も使える
+   * @param command 実行するコマンド（undo / redo）
+   */
+  const handleHistoryStep = useCallback(
+    async (command: "undo" | "redo") => {
+      if (!workspace) return;
+      await flushPendingSave();
+      setErrorMessage(null);
+      try {
+        const result = await invoke<HistoryResult>(command, {
+          workspaceId: workspace.id,
+          revision: revisionRef.current,
+        });
+        applySnapshot(result.table);
+        setStatusMessage(command === "undo" ? "変更を元に戻しました" : "変更をやり直しました");
+      } catch (error) {
+        console.error(error);
+        const commandError = error as Partial<CommandError>;
+        if (commandError.code === "conflict" && commandError.snapshot) {
+          setConflict({ snapshot: commandError.snapshot, detectedAt: new Date().toISOString() });
+          setStatusMessage("外部変更と競合しました");
+          return;
+        }
+        setStatusMessage(formatError(error));
+      }
+    },
+    [workspace, flushPendingSave, applySnapshot]
+  );
+
+  /**
+   * 競合解決: 外部の変更と自分の変更をマージする
+   * 両方が同じセルを変更していた場合は自分の値を残し、件数を表示する
+   */
+  const handleResolveMerge = useCallback(async () => {
+    if (!conflict || !schema || !workspace) return;
+    try {
+      const result = await invoke<MergeResult>("merge_workspace", {
+        workspaceId: workspace.id,
+        base: baseRowsRef.current,
+        local: rows,
+      });
+      // 自分だけが追加したカラムは残す
+      const localOnlyColumns = schema.columns.filter(
+        (column) => !result.schema.columns.some((existing) => existing.id === column.id)
+      );
+      const mergedSchema: TableSchema = {
+        ...result.schema,
+        columns: [...result.schema.columns, ...localOnlyColumns],
+      };
+      revisionRef.current = result.revision;
+      setConflict(null);
+      setRows(result.data);
+      setSchema(mergedSchema);
+      scheduleSave(result.data, mergedSchema);
+      setStatusMessage(
+        result.conflicts.length > 0
+          ? `マージしました ・ 競合 ${result.conflicts.length}件は自分の値を残しました`
+          : "マージしました"
+      );
+    } catch (error) {
+      console.error(error);
+      setErrorMessage(`マージに失敗しました: ${formatError(error)}`);
+    }
+  }, [conflict, schema, workspace, rows, scheduleSave]);
+
   return (
     <div className="app-shell">
       <header className="app-toolbar">
@@ -703,6 +1062,31 @@ export default function App(): JSX.Element {
           <button type="button" onClick={handleCreateWorkspace} disabled={isLoading}>
             新規ワークスペース作成
           </button>
+          <button
+            type="button"
+            onClick={() => void handleHistoryStep("undo")}
+            disabled={!workspace || isSaving}
+          >
+            元に戻す
+          </button>
+          <button
+            type="button"
+            onClick={() => void handleHistoryStep("redo")}
+            disabled={!workspace || isSaving}
+          >
+            やり直す
+          </button>
+          <button type="button" onClick={() => void handleToggleApi()}>
+            {apiServer ? "ローカルAPIを停止" : "ローカルAPIを開始"}
+          </button>
+          {apiServer && (
+            <div className="api-info">
+              <span className="workspace-label">API:</span>
+              <span>{apiServer.url}</span>
+              <span className="workspace-label">トークン:</span>
+              <code className="api-token">{apiServer.token}</code>
+            </div>
+          )}
           <div className="workspace-info">
             {workspace ? (
               <>
@@ -732,6 +1116,9 @@ export default function App(): JSX.Element {
             <button type="button" onClick={handleResolveReload}>
               外部の変更を読み込む
             </button>
+            <button type="button" onClick={handleResolveMerge}>
+              変更をマージ
+            </button>
             <button type="button" onClick={handleResolveKeep}>
               自分の変更を保存
             </button>
@@ -739,82 +1126,119 @@ export default function App(): JSX.Element {
         </div>
       )}
 
-      <main className="app-main">
-        {schema ? (
-          <>
-            <div className="table-actions">
-              <button type="button" onClick={handleAddRow} disabled={isSaving}>
-                + 行を追加
-              </button>
-              <button type="button" onClick={handleAddColumn} disabled={isSaving}>
-                + 列を追加
-              </button>
-            </div>
-            <div className="table-wrapper">
-              <DndContext
-                sensors={sensors}
-                collisionDetection={closestCenter}
-                onDragEnd={handleDragEnd}
-              >
-                <table>
-                  <thead>
-                    <tr>
-                      <th className="row-handle-column" aria-label="行の並び替えハンドル" />
-                      {userColumns.map((column) => (
-                        <th
-                          key={column.id}
-                          style={column.width ? { width: `${column.width}px` } : undefined}
-                          draggable
-                          onDragStart={() => {
-                            draggedColumnIdRef.current = column.id;
-                          }}
-                          onDragOver={(event) => event.preventDefault()}
-                          onDrop={() => handleColumnDrop(column.id)}
-                        >
-                          <div className="column-header">
-                            <span>{column.name}</span>
-                            {!isSystemColumn(column) && (
-                              <button
-                                type="button"
-                                className="icon-button"
-                                onClick={() => handleDeleteColumn(column.id)}
-                                title="列を削除"
-                              >
-                                ×
-                              </button>
-                            )}
-                          </div>
-                        </th>
-                      ))}
-                      <th className="actions-column">操作</th>
-                    </tr>
-                  </thead>
-                  <SortableContext
-                    items={rows.map((row) => row._id as string)}
-                    strategy={verticalListSortingStrategy}
+      <div className="app-body">
+        {tables.length > 0 && (
+          <aside className="table-sidebar">
+            <div className="sidebar-title">テーブル</div>
+            <ul>
+              {tables.map((table) => (
+                <li key={table.data_path} className="sidebar-item">
+                  <button
+                    type="button"
+                    className={`sidebar-table-button ${
+                      table.data_path === workspace?.dataPath ? "active" : ""
+                    }`}
+                    onClick={() => void handleSelectTable(table.data_path)}
+                    disabled={isLoading}
+                    title={table.error ?? table.file_name}
                   >
-                    <tbody>
-                      {rows.map((row) => (
-                        <SortableRow
-                          key={row._id as string}
-                          row={row}
-                          userColumns={userColumns}
-                          onCellChange={updateCell}
-                          onDelete={handleDeleteRow}
-                        />
-                      ))}
-                    </tbody>
-                  </SortableContext>
-                </table>
-              </DndContext>
-            </div>
-          </>
-        ) : (
-          <div className="empty-state">
-            <p>ワークスペースを選択してテーブルを表示してください。</p>
-          </div>
+                    <span className="sidebar-table-name">{table.table_name}</span>
+                    <span className="sidebar-table-meta">
+                      {table.row_count} 行
+                      {table.updated_at && ` · ${new Date(table.updated_at).toLocaleString()}`}
+                    </span>
+                  </button>
+                  <button
+                    type="button"
+                    className="sidebar-window-button"
+                    onClick={() => void handleOpenInWindow(table.data_path)}
+                    title="新しいウィンドウで開く"
+                  >
+                    ⧉
+                  </button>
+                </li>
+              ))}
+            </ul>
+          </aside>
         )}
-      </main>
+        <main className="app-main">
+          {schema ? (
+            <>
+              <div className="table-actions">
+                <button type="button" onClick={handleAddRow} disabled={isSaving}>
+                  + 行を追加
+                </button>
+                <button type="button" onClick={handleAddColumn} disabled={isSaving}>
+                  + 列を追加
+                </button>
+              </div>
+              <div className="table-wrapper">
+                <DndContext
+                  sensors={sensors}
+                  collisionDetection={closestCenter}
+                  onDragEnd={handleDragEnd}
+                >
+                  <table>
+                    <thead>
+                      <tr>
+                        <th className="row-handle-column" aria-label="行の並び替えハンドル" />
+                        {userColumns.map((column) => (
+                          <th
+                            key={column.id}
+                            style={column.width ? { width: `${column.width}px` } : undefined}
+                            draggable
+                            onDragStart={() => {
+                              draggedColumnIdRef.current = column.id;
+                            }}
+                            onDragOver={(event) => event.preventDefault()}
+                            onDrop={() => handleColumnDrop(column.id)}
+                          >
+                            <div className="column-header">
+                              <span>{column.name}</span>
+                              {!isSystemColumn(column) && (
+                                <button
+                                  type="button"
+                                  className="icon-button"
+                                  onClick={() => handleDeleteColumn(column.id)}
+                                  title="列を削除"
+                                >
+                                  ×
+                                </button>
+                              )}
+                            </div>
+                          </th>
+                        ))}
+                        <th className="actions-column">操作</th>
+                      </tr>
+                    </thead>
+                    <SortableContext
+                      items={rows.map((row) => row._id as string)}
+                      strategy={verticalListSortingStrategy}
+                    >
+                      <tbody>
+                        {rows.map((row) => (
+                          <SortableRow
+                            key={row._id as string}
+                            row={row}
+                            userColumns={userColumns}
+                            links={links}
+                            onCellChange={updateCell}
+                            onDelete={handleDeleteRow}
+                          />
+                        ))}
+                      </tbody>
+                    </SortableContext>
+                  </table>
+                </DndContext>
+              </div>
+            </>
+          ) : (
+            <div className="empty-state">
+              <p>ワークスペースを選択してテーブルを表示してください。</p>
+            </div>
+          )}
+        </main>
+      </div>
 
       {columnDialog.open && (
         <div className="modal-backdrop">
@@ -869,6 +1293,7 @@ export default function App(): JSX.Element {
 interface SortableRowProps {
   row: TableRow;
   userColumns: ColumnDefinition[];
+  links: RelationLinks;
   onCellChange: (rowId: string, column: ColumnDefinition, value: unknown) => void;
   onDelete: (rowId: string) => void;
 }
@@ -876,7 +1301,7 @@ interface SortableRowProps {
 /**
  * ドラッグ&ドロップ可能な行コンポーネント
  */
-function SortableRow({ row, userColumns, onCellChange, onDelete }: SortableRowProps): JSX.Element {
+function SortableRow({ row, userColumns, links, onCellChange, onDelete }: SortableRowProps): JSX.Element {
   const { attributes, listeners, setNodeRef, transform, transition, isDragging } = useSortable({
     id: row._id as string,
   });
@@ -899,6 +1324,7 @@ function SortableRow({ row, userColumns, onCellChange, onDelete }: SortableRowPr
           <EditableCell
             column={column}
             value={row[column.id]}
+            links={links[column.id]}
             onChange={(value) => onCellChange(row._id as string, column, value)}
           />
         </td>
@@ -918,6 +1344,7 @@ function SortableRow({ row, userColumns, onCellChange, onDelete }: SortableRowPr
 interface EditableCellProps {
   column: ColumnDefinition;
   value: unknown;
+  links?: Record<string, string>;  // リレーション列の参照先の_idと表示値
   onChange: (value: unknown) => void;
 }
 
@@ -925,7 +1352,7 @@ interface EditableCellProps {
  * 編集可能なセルコンポーネント
  * ダブルクリックで編集モードに入る
  */
-function EditableCell({ column, value, onChange }: EditableCellProps): JSX.Element {
+function EditableCell({ column, value, links, onChange }: EditableCellProps): JSX.Element {
   const [draft, setDraft] = useState<string>(String(value ?? ""));
   const [isEditing, setIsEditing] = useState(false);
   const inputRef = useRef<HTMLInputElement | null>(null);
@@ -943,6 +1370,21 @@ function EditableCell({ column, value, onChange }: EditableCellProps): JSX.Eleme
     }
   }, [isEditing]);
 
+  // 計算列・lookup列・rollup列は編集できない
+  if (COMPUTED_TYPES.includes(column.type)) {
+    return <span className="cell-display">{renderDisplayValue(column, value)}</span>;
+  }
+
+  // リレーション列は参照先の表示値を表示する（見つからない_idはそのまま）
+  if (column.type === "relation") {
+    const ids = Array.isArray(value) ? value : value ? [value] : [];
+    return (
+      <span className="cell-display">
+        {ids.map((id) => links?.[String(id)] ?? String(id)).join(", ")}
+      </span>
+    );
+  }
+
   if (column.type === "checkbox") {
     const checked = Boolean(value);
     return (
@@ -999,6 +1441,7 @@ function EditableCell({ column, value, onChange }: EditableCellProps): JSX.Eleme
  */
 function renderDisplayValue(column: ColumnDefinition, value: unknown): string {
   if (value === null || value === undefined) return "";
+  if (Array.isArray(value)) return value.join(", ");
   if (column.type === "number") {
     return String(value ?? 0);
   }
@@ -1007,6 +1450,26 @@ function renderDisplayValue(column: ColumnDefinition, value: unknown): string {
 
 /** バックエンドからのワークスペース変更イベントペイロード */
 interface WorkspaceChangePayload {
+  workspace_id: string;
   data_path: string;
   schema_path: string;
 }
+
+/** フォルダ内のテーブルの一覧の1件 */
+interface CatalogEntry {
+  file_name: string;
+  data_path: string;
+  schema_path: string;
+  storage: "json" | "sqlite";
+  table_name: string;       // テーブル名（スキーマを読み込めない場合はファイル名）
+  row_count: number;        // スキーマのメタデータの行数
+  updated_at?: string;      // スキーマのメタデータの最終更新日時
+  error?: string;           // スキーマを読み込めなかった場合のエラーメッセージ
+}
+
+/** バックエンドからのテーブル一覧変更イベントペイロード */
+interface CatalogPayload {
+  workspace_id: string;
+  folder: string;
+  tables: CatalogEntry[];
+}